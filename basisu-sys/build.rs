@@ -1,20 +1,99 @@
 
 use std::path::PathBuf;
 use std::env;
+use std::fs;
 
 fn main() {
-    cc::Build::new()
+    let zstd = if env::var_os("CARGO_FEATURE_ZSTD").is_some() { "1" } else { "0" };
+    let encoder = if env::var_os("CARGO_FEATURE_ENCODER").is_some() { "1" } else { "0" };
+
+    // wasm32-unknown-unknown has no C or C++ library. Headers come from a WASI sysroot, and the
+    // few runtime functions the transcoder calls are implemented in wasm.cpp and src/wasm.rs.
+    let wasm = env::var("TARGET").unwrap() == "wasm32-unknown-unknown";
+    let wasm_flags = if wasm {
+        if encoder == "1" {
+            panic!("the encoder isn't supported on wasm32-unknown-unknown");
+        }
+        println!("cargo:rerun-if-env-changed=WASI_SYSROOT");
+        let sysroot = env::var("WASI_SYSROOT")
+            .expect("building for wasm32-unknown-unknown requires WASI_SYSROOT to point to a \
+                     WASI sysroot, such as share/wasi-sysroot from wasi-sdk");
+        vec![
+            // WASI objects link fine into wasm32-unknown-unknown modules as long as they don't
+            // import anything, which the functions used here don't.
+            "--target=wasm32-wasi".to_string(),
+            format!("--sysroot={}", sysroot),
+            "-fno-exceptions".to_string(),
+        ]
+    } else {
+        Vec::new()
+    };
+
+    // Bindgen sees the same defines as the C++ compiler, so the struct layouts match.
+    let format_defines: Vec<(&str, &str)> = FORMAT_FEATURES
+        .iter()
+        .flat_map(|&(feature, defines)| {
+            let enabled = env::var_os(format!("CARGO_FEATURE_{}", feature)).is_some();
+            defines.iter().map(move |&define| (define, if enabled { "1" } else { "0" }))
+        })
+        .collect();
+
+    let mut build = cc::Build::new();
+    build
 		.cpp(true)
 		.warnings(false)
+		.define("BASISD_SUPPORT_KTX2_ZSTD", zstd)
+        .define("BASISU_SYS_ENCODER", encoder)
         .file("vendor/transcoder/basisu_transcoder.cpp")
-        .compile("libbasisu_transcoder.a");
+        .file("wrapper.cpp");
+    for &(define, value) in &format_defines {
+        build.define(define, value);
+    }
+    if wasm {
+        build.cpp_link_stdlib(None).define("NDEBUG", None).file("wasm.cpp");
+        for flag in &wasm_flags {
+            build.flag(flag);
+        }
+    }
+    if encoder == "1" {
+        // The encoder sources change between releases, so build whatever the submodule holds.
+        // The few C files among them are compiled separately below.
+        build.files(encoder_sources("cpp"));
+    }
+    build.compile("libbasisu_transcoder.a");
+
+    let c_sources = if encoder == "1" { encoder_sources("c") } else { Vec::new() };
+    if !c_sources.is_empty() {
+        cc::Build::new()
+            .warnings(false)
+            .files(c_sources)
+            .compile("libbasisu_encoder_c.a");
+    }
+
+    if zstd == "1" {
+        let mut build = cc::Build::new();
+        build.warnings(false).file("vendor/zstd/zstd.c");
+        if wasm {
+            build.define("NDEBUG", None);
+            for flag in &wasm_flags {
+                build.flag(flag);
+            }
+        }
+        build.compile("libzstd.a");
+    }
 
     let bindings = bindgen::Builder::default()
         .header("wrapper.hpp")
 		.enable_cxx_namespaces()
+		.use_core()
+		.ctypes_prefix("::core::ffi")
         .parse_callbacks(Box::new(bindgen::CargoCallbacks))
 		.clang_arg("-x")
 		.clang_arg("c++")
+		.clang_args(&wasm_flags)
+		.clang_arg(format!("-DBASISD_SUPPORT_KTX2_ZSTD={}", zstd))
+        .clang_arg(format!("-DBASISU_SYS_ENCODER={}", encoder))
+		.clang_args(format_defines.iter().map(|(define, value)| format!("-D{}={}", define, value)))
 		.blacklist_item("FP_.*")
 		.blacklist_type("size_type")
 		.blacklist_type("std::size_type")
@@ -38,3 +117,26 @@ fn main() {
         .write_to_file(out_path.join("bindings.rs"))
         .expect("Couldn't write bindings!");
 }
+
+/// The output format features, as they appear in `CARGO_FEATURE_*` variables, and the transcoder
+/// defines each of them controls.
+const FORMAT_FEATURES: &[(&str, &[&str])] = &[
+    ("BC7", &["BASISD_SUPPORT_BC7", "BASISD_SUPPORT_BC7_MODE5"]),
+    ("ASTC", &["BASISD_SUPPORT_ASTC"]),
+    ("PVRTC1", &["BASISD_SUPPORT_PVRTC1"]),
+    ("PVRTC2", &["BASISD_SUPPORT_PVRTC2"]),
+    ("ATC", &["BASISD_SUPPORT_ATC"]),
+    ("FXT1", &["BASISD_SUPPORT_FXT1"]),
+    ("ETC2_EAC", &["BASISD_SUPPORT_ETC2_EAC_A8", "BASISD_SUPPORT_ETC2_EAC_RG11"]),
+];
+
+/// Paths of the encoder source files with the given extension.
+fn encoder_sources(extension: &str) -> Vec<PathBuf> {
+    let mut sources: Vec<PathBuf> = fs::read_dir("vendor/encoder")
+        .expect("basis_universal encoder sources are missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == extension))
+        .collect();
+    sources.sort();
+    sources
+}
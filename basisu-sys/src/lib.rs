@@ -1,4 +1,5 @@
 
+#![no_std]
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
@@ -7,6 +8,9 @@
 // Not great to ignore, but we'd otherwise get many hundreds of warnings.
 #![allow(improper_ctypes)]
 
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+mod wasm;
+
 mod inner {
 	include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
@@ -17,3 +21,6 @@ pub mod basisu {
 pub mod basist {
 	pub use crate::inner::root::basist::*;
 }
+pub mod shim {
+	pub use crate::inner::root::shim::*;
+}
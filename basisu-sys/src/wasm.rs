@@ -0,0 +1,76 @@
+//! The C allocation functions and `abort` for wasm32-unknown-unknown, which has no C library.
+//! The transcoder's other runtime needs are covered by wasm.cpp.
+
+extern crate alloc;
+
+use alloc::alloc::Layout;
+use core::ptr;
+
+/// Alignment of every allocation, as for `max_align_t`. The size of the allocation is stored in
+/// the bytes just before the returned pointer so `free` can rebuild its layout.
+const ALIGN: usize = 16;
+
+fn layout(size: usize) -> Option<Layout> {
+    Layout::from_size_align(size.checked_add(ALIGN)?, ALIGN).ok()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn malloc(size: usize) -> *mut u8 {
+    let layout = match layout(size) {
+        Some(layout) => layout,
+        None => return ptr::null_mut(),
+    };
+    let base = alloc::alloc::alloc(layout);
+    if base.is_null() {
+        return base;
+    }
+    (base as *mut usize).write(size);
+    base.add(ALIGN)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn calloc(count: usize, size: usize) -> *mut u8 {
+    let total = match count.checked_mul(size) {
+        Some(total) => total,
+        None => return ptr::null_mut(),
+    };
+    let p = malloc(total);
+    if !p.is_null() {
+        ptr::write_bytes(p, 0, total);
+    }
+    p
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn realloc(p: *mut u8, size: usize) -> *mut u8 {
+    if p.is_null() {
+        return malloc(size);
+    }
+    if layout(size).is_none() {
+        return ptr::null_mut();
+    }
+    let base = p.sub(ALIGN);
+    let old_size = (base as *const usize).read();
+    let old_layout = Layout::from_size_align_unchecked(old_size + ALIGN, ALIGN);
+    let base = alloc::alloc::realloc(base, old_layout, size + ALIGN);
+    if base.is_null() {
+        return base;
+    }
+    (base as *mut usize).write(size);
+    base.add(ALIGN)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn free(p: *mut u8) {
+    if p.is_null() {
+        return;
+    }
+    let base = p.sub(ALIGN);
+    let size = (base as *const usize).read();
+    alloc::alloc::dealloc(base, Layout::from_size_align_unchecked(size + ALIGN, ALIGN));
+}
+
+#[no_mangle]
+pub extern "C" fn abort() -> ! {
+    core::arch::wasm32::unreachable()
+}
@@ -0,0 +1,47 @@
+// Runtime support for wasm32-unknown-unknown, which has no C or C++ library. Memory comes from
+// the Rust allocator through malloc and free in src/wasm.rs, and output is discarded. Everything
+// is weak so a real C library linked into the same module takes precedence.
+
+#include <stdarg.h>
+#include <stddef.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <new>
+
+#define WEAK __attribute__((weak))
+
+extern "C" {
+	WEAK int vprintf(const char*, va_list) { return 0; }
+	WEAK int printf(const char*, ...) { return 0; }
+	WEAK int vfprintf(FILE*, const char*, va_list) { return 0; }
+	WEAK int fprintf(FILE*, const char*, ...) { return 0; }
+	WEAK int puts(const char*) { return 0; }
+	WEAK int putchar(int c) { return c; }
+	WEAK int fflush(FILE*) { return 0; }
+
+	WEAK size_t strlen(const char* s) {
+		size_t len = 0;
+		while (s[len])
+			len++;
+		return len;
+	}
+
+	WEAK int strcmp(const char* a, const char* b) {
+		while (*a && *a == *b) {
+			a++;
+			b++;
+		}
+		return (unsigned char)*a - (unsigned char)*b;
+	}
+
+	WEAK void __cxa_pure_virtual() { abort(); }
+}
+
+WEAK void* operator new(size_t size) { return malloc(size); }
+WEAK void* operator new[](size_t size) { return malloc(size); }
+WEAK void* operator new(size_t size, const std::nothrow_t&) noexcept { return malloc(size); }
+WEAK void* operator new[](size_t size, const std::nothrow_t&) noexcept { return malloc(size); }
+WEAK void operator delete(void* p) noexcept { free(p); }
+WEAK void operator delete[](void* p) noexcept { free(p); }
+WEAK void operator delete(void* p, size_t) noexcept { free(p); }
+WEAK void operator delete[](void* p, size_t) noexcept { free(p); }
@@ -0,0 +1,354 @@
+#include "wrapper.hpp"
+
+#include <string.h>
+
+#if BASISU_SYS_ENCODER
+#include "vendor/encoder/basisu_comp.h"
+#endif
+
+// The defaults basisu_transcoder.cpp uses for the options build.rs doesn't set.
+#ifndef BASISD_SUPPORT_DXT1
+#define BASISD_SUPPORT_DXT1 1
+#endif
+#ifndef BASISD_SUPPORT_DXT5A
+#define BASISD_SUPPORT_DXT5A 1
+#endif
+
+namespace shim {
+	bool get_file_info(const basist::basisu_transcoder& transcoder, const void* pData, uint32_t data_size, file_info& info) {
+		basist::basisu_file_info fi;
+		if (!transcoder.get_file_info(pData, data_size, fi))
+			return false;
+
+		info.version = fi.m_version;
+		info.total_header_size = fi.m_total_header_size;
+		info.total_selectors = fi.m_total_selectors;
+		info.selector_codebook_size = fi.m_selector_codebook_size;
+		info.total_endpoints = fi.m_total_endpoints;
+		info.endpoint_codebook_size = fi.m_endpoint_codebook_size;
+		info.tables_size = fi.m_tables_size;
+		info.slices_size = fi.m_slices_size;
+		info.tex_type = fi.m_tex_type;
+		info.us_per_frame = fi.m_us_per_frame;
+		info.total_slices = (uint32_t)fi.m_slice_info.size();
+		info.total_images = fi.m_total_images;
+		info.userdata0 = fi.m_userdata0;
+		info.userdata1 = fi.m_userdata1;
+		info.tex_format = fi.m_tex_format;
+		info.y_flipped = fi.m_y_flipped;
+		info.etc1s = fi.m_etc1s;
+		info.has_alpha_slices = fi.m_has_alpha_slices;
+		return true;
+	}
+
+	bool get_slice_info(const basist::basisu_transcoder& transcoder, const void* pData, uint32_t data_size, basist::basisu_slice_info* pSlices, uint32_t total_slices) {
+		basist::basisu_file_info fi;
+		if (!transcoder.get_file_info(pData, data_size, fi))
+			return false;
+		if (fi.m_slice_info.size() != total_slices)
+			return false;
+
+		for (uint32_t i = 0; i < total_slices; i++)
+			pSlices[i] = fi.m_slice_info[i];
+		return true;
+	}
+
+	void destroy_transcoder(basist::basisu_transcoder& transcoder) {
+		transcoder.~basisu_transcoder();
+	}
+
+	basist::basisu_transcoder_state* new_transcoder_state() {
+		return new basist::basisu_transcoder_state();
+	}
+
+	void delete_transcoder_state(basist::basisu_transcoder_state* pState) {
+		delete pState;
+	}
+
+	basist::etc1_global_selector_codebook* new_selector_codebook(uint32_t N, const uint32_t* pEntries) {
+		return new basist::etc1_global_selector_codebook(N, pEntries);
+	}
+
+	void delete_selector_codebook(basist::etc1_global_selector_codebook* pCodebook) {
+		delete pCodebook;
+	}
+
+	basist::ktx2_transcoder* new_ktx2_transcoder(const basist::etc1_global_selector_codebook* pGlobal_sel_codebook) {
+		// The transcoder only reads from the codebook.
+		return new basist::ktx2_transcoder(const_cast<basist::etc1_global_selector_codebook*>(pGlobal_sel_codebook));
+	}
+
+	void delete_ktx2_transcoder(basist::ktx2_transcoder* pTranscoder) {
+		delete pTranscoder;
+	}
+
+	basist::basisu_lowlevel_etc1s_transcoder* new_lowlevel_etc1s_transcoder(const basist::etc1_global_selector_codebook* pGlobal_sel_codebook) {
+		return new basist::basisu_lowlevel_etc1s_transcoder(pGlobal_sel_codebook);
+	}
+
+	void delete_lowlevel_etc1s_transcoder(basist::basisu_lowlevel_etc1s_transcoder* pTranscoder) {
+		delete pTranscoder;
+	}
+
+	uint32_t get_etc1s_total_endpoints(const basist::basisu_lowlevel_etc1s_transcoder* pTranscoder) {
+		return pTranscoder->get_endpoints().size();
+	}
+
+	void get_etc1s_endpoints(const basist::basisu_lowlevel_etc1s_transcoder* pTranscoder, etc1s_endpoint* pEndpoints) {
+		const auto& endpoints = pTranscoder->get_endpoints();
+		for (uint32_t i = 0; i < endpoints.size(); i++) {
+			pEndpoints[i].color5[0] = endpoints[i].m_color5.r;
+			pEndpoints[i].color5[1] = endpoints[i].m_color5.g;
+			pEndpoints[i].color5[2] = endpoints[i].m_color5.b;
+			pEndpoints[i].inten5 = endpoints[i].m_inten5;
+		}
+	}
+
+	uint32_t get_etc1s_total_selectors(const basist::basisu_lowlevel_etc1s_transcoder* pTranscoder) {
+		return pTranscoder->get_selectors().size();
+	}
+
+	void get_etc1s_selectors(const basist::basisu_lowlevel_etc1s_transcoder* pTranscoder, etc1s_selector* pSelectors) {
+		const auto& selectors = pTranscoder->get_selectors();
+		for (uint32_t i = 0; i < selectors.size(); i++)
+			memcpy(pSelectors[i].selectors, selectors[i].m_selectors, sizeof(pSelectors[i].selectors));
+	}
+
+	basist::basisu_lowlevel_uastc_transcoder* new_lowlevel_uastc_transcoder() {
+		return new basist::basisu_lowlevel_uastc_transcoder();
+	}
+
+	void delete_lowlevel_uastc_transcoder(basist::basisu_lowlevel_uastc_transcoder* pTranscoder) {
+		delete pTranscoder;
+	}
+
+	bool unpack_uastc_block(const uint8_t* pBlock, uint8_t* pPixels, bool srgb) {
+		basist::uastc_block block;
+		memcpy(&block, pBlock, sizeof(block));
+		basist::color32 pixels[16];
+		if (!basist::unpack_uastc(block, pixels, srgb))
+			return false;
+		memcpy(pPixels, pixels, sizeof(pixels));
+		return true;
+	}
+
+	bool transcoder_format_enabled(basist::transcoder_texture_format fmt) {
+		switch (fmt) {
+		case basist::transcoder_texture_format::cTFBC1_RGB:
+			return BASISD_SUPPORT_DXT1;
+		case basist::transcoder_texture_format::cTFBC3_RGBA:
+			return BASISD_SUPPORT_DXT1 && BASISD_SUPPORT_DXT5A;
+		case basist::transcoder_texture_format::cTFBC4_R:
+		case basist::transcoder_texture_format::cTFBC5_RG:
+			return BASISD_SUPPORT_DXT5A;
+		case basist::transcoder_texture_format::cTFBC7_RGBA:
+			return BASISD_SUPPORT_BC7 && BASISD_SUPPORT_BC7_MODE5;
+		case basist::transcoder_texture_format::cTFETC2_RGBA:
+			return BASISD_SUPPORT_ETC2_EAC_A8;
+		case basist::transcoder_texture_format::cTFETC2_EAC_R11:
+		case basist::transcoder_texture_format::cTFETC2_EAC_RG11:
+			return BASISD_SUPPORT_ETC2_EAC_RG11;
+		case basist::transcoder_texture_format::cTFASTC_4x4_RGBA:
+			return BASISD_SUPPORT_ASTC;
+		case basist::transcoder_texture_format::cTFPVRTC1_4_RGB:
+		case basist::transcoder_texture_format::cTFPVRTC1_4_RGBA:
+			return BASISD_SUPPORT_PVRTC1;
+		case basist::transcoder_texture_format::cTFPVRTC2_4_RGB:
+		case basist::transcoder_texture_format::cTFPVRTC2_4_RGBA:
+			return BASISD_SUPPORT_PVRTC2;
+		case basist::transcoder_texture_format::cTFATC_RGB:
+			return BASISD_SUPPORT_ATC;
+		case basist::transcoder_texture_format::cTFATC_RGBA:
+			return BASISD_SUPPORT_ATC && BASISD_SUPPORT_DXT5A;
+		case basist::transcoder_texture_format::cTFFXT1_RGB:
+			return BASISD_SUPPORT_FXT1;
+		default:
+			// ETC1 and the uncompressed formats are always available.
+			return true;
+		}
+	}
+
+	basist::ktx2_transcoder_state* new_ktx2_transcoder_state() {
+		return new basist::ktx2_transcoder_state();
+	}
+
+	void delete_ktx2_transcoder_state(basist::ktx2_transcoder_state* pState) {
+		delete pState;
+	}
+
+	void reset_ktx2_transcoder_state(basist::ktx2_transcoder_state* pState) {
+		pState->m_uncomp_data_level_index = -1;
+	}
+}
+
+#if BASISU_SYS_ENCODER
+namespace shim {
+	struct compressor {
+		basisu::basis_compressor_params params;
+		basisu::uint8_vec output;
+	};
+
+	void encoder_init() {
+		basisu::basisu_encoder_init();
+	}
+
+	compressor* new_compressor(const basist::etc1_global_selector_codebook* pGlobal_sel_codebook) {
+		compressor* pComp = new compressor();
+		// Images are passed in memory and the output is handed back rather than written out.
+		pComp->params.m_read_source_images = false;
+		pComp->params.m_write_output_basis_files = false;
+		pComp->params.m_status_output = false;
+		pComp->params.m_pSel_codebook = pGlobal_sel_codebook;
+		return pComp;
+	}
+
+	void delete_compressor(compressor* pComp) {
+		delete pComp;
+	}
+
+	void compressor_add_image(compressor* pComp, const uint8_t* pRGBA, uint32_t width, uint32_t height) {
+		basisu::image img(width, height);
+		memcpy(img.get_ptr(), pRGBA, (size_t)width * height * 4);
+		pComp->params.m_source_images.push_back(img);
+	}
+
+	void compressor_add_mipmap(compressor* pComp, uint32_t image_index, const uint8_t* pRGBA, uint32_t width, uint32_t height) {
+		basisu::image img(width, height);
+		memcpy(img.get_ptr(), pRGBA, (size_t)width * height * 4);
+		if (pComp->params.m_source_mipmap_images.size() <= image_index)
+			pComp->params.m_source_mipmap_images.resize(image_index + 1);
+		pComp->params.m_source_mipmap_images[image_index].push_back(img);
+	}
+
+	void compressor_resize_image(compressor* pComp, uint32_t image_index, uint32_t width, uint32_t height) {
+		const basisu::basis_compressor_params& params = pComp->params;
+		basisu::image& img = pComp->params.m_source_images[image_index];
+		basisu::image resized(width, height);
+		basisu::image_resample(img, resized, params.m_mip_srgb, params.m_mip_filter.c_str(), params.m_mip_scale, params.m_mip_wrapping);
+		img.swap(resized);
+	}
+
+	void compressor_set_tex_type(compressor* pComp, basist::basis_texture_type tex_type) {
+		pComp->params.m_tex_type = tex_type;
+	}
+
+	void compressor_set_us_per_frame(compressor* pComp, uint32_t us_per_frame) {
+		pComp->params.m_us_per_frame = us_per_frame;
+	}
+
+	void compressor_set_quality_level(compressor* pComp, int quality_level) {
+		pComp->params.m_quality_level = quality_level;
+	}
+
+	void compressor_set_uastc(compressor* pComp, bool uastc, uint32_t level) {
+		pComp->params.m_uastc = uastc;
+		pComp->params.m_pack_uastc_flags = (pComp->params.m_pack_uastc_flags & ~basisu::cPackUASTCLevelMask) | level;
+	}
+
+	void compressor_set_uastc_rdo(compressor* pComp, bool rdo, float quality_scalar, int dict_size, float max_smooth_block_error_scale, float max_smooth_block_std_dev) {
+		pComp->params.m_rdo_uastc = rdo;
+		if (rdo) {
+			pComp->params.m_rdo_uastc_quality_scalar = quality_scalar;
+			pComp->params.m_rdo_uastc_dict_size = dict_size;
+			pComp->params.m_rdo_uastc_max_smooth_block_error_scale = max_smooth_block_error_scale;
+			pComp->params.m_rdo_uastc_smooth_block_max_std_dev = max_smooth_block_std_dev;
+		}
+	}
+
+	void compressor_set_perceptual(compressor* pComp, bool perceptual) {
+		pComp->params.m_perceptual = perceptual;
+	}
+
+	void compressor_set_y_flip(compressor* pComp, bool y_flip) {
+		pComp->params.m_y_flip = y_flip;
+	}
+
+	void compressor_set_alpha(compressor* pComp, bool check_for_alpha, bool force_alpha) {
+		pComp->params.m_check_for_alpha = check_for_alpha;
+		pComp->params.m_force_alpha = force_alpha;
+	}
+
+	void compressor_set_swizzle(compressor* pComp, uint8_t r, uint8_t g, uint8_t b, uint8_t a) {
+		pComp->params.m_swizzle[0] = r;
+		pComp->params.m_swizzle[1] = g;
+		pComp->params.m_swizzle[2] = b;
+		pComp->params.m_swizzle[3] = a;
+	}
+
+	void compressor_set_etc1s_rdo(compressor* pComp, bool rdo) {
+		pComp->params.m_no_endpoint_rdo = !rdo;
+		pComp->params.m_no_selector_rdo = !rdo;
+	}
+
+	void compressor_set_global_sel_pal(compressor* pComp, bool global_sel_pal) {
+		pComp->params.m_global_sel_pal = global_sel_pal;
+	}
+
+	void compressor_set_mip_gen(compressor* pComp, bool mip_gen) {
+		pComp->params.m_mip_gen = mip_gen;
+	}
+
+	void compressor_set_mip_filter(compressor* pComp, const char* pFilter) {
+		pComp->params.m_mip_filter = pFilter;
+	}
+
+	void compressor_set_mip_srgb(compressor* pComp, bool mip_srgb) {
+		pComp->params.m_mip_srgb = mip_srgb;
+	}
+
+	void compressor_set_mip_wrapping(compressor* pComp, bool mip_wrapping) {
+		pComp->params.m_mip_wrapping = mip_wrapping;
+	}
+
+	void compressor_set_mip_renormalize(compressor* pComp, bool mip_renormalize) {
+		pComp->params.m_mip_renormalize = mip_renormalize;
+	}
+
+	void compressor_set_ktx2(compressor* pComp, bool create_ktx2, bool zstd, int zstd_level, bool srgb) {
+		pComp->params.m_create_ktx2_file = create_ktx2;
+		pComp->params.m_ktx2_uastc_supercompression = zstd ? basist::KTX2_SS_ZSTANDARD : basist::KTX2_SS_NONE;
+		pComp->params.m_ktx2_zstd_supercompression_level = zstd_level;
+		pComp->params.m_ktx2_srgb_transfer_func = srgb;
+	}
+
+	struct job_pool {
+		basisu::job_pool pool;
+		uint32_t num_threads;
+
+		job_pool(uint32_t n) : pool(n), num_threads(n) {}
+	};
+
+	job_pool* new_job_pool(uint32_t num_threads) {
+		return new job_pool(num_threads);
+	}
+
+	void delete_job_pool(job_pool* pPool) {
+		delete pPool;
+	}
+
+	int compressor_process(compressor* pComp, job_pool* pPool) {
+		// The compressor expects either no mipmap lists at all or one for every source image.
+		if (!pComp->params.m_source_mipmap_images.empty())
+			pComp->params.m_source_mipmap_images.resize(pComp->params.m_source_images.size());
+
+		pComp->params.m_pJob_pool = &pPool->pool;
+		pComp->params.m_multithreading = pPool->num_threads > 1;
+
+		basisu::basis_compressor comp;
+		basisu::basis_compressor::error_code result = basisu::basis_compressor::cECFailedValidating;
+		if (comp.init(pComp->params)) {
+			result = comp.process();
+			if (result == basisu::basis_compressor::cECSuccess)
+				pComp->output = pComp->params.m_create_ktx2_file ? comp.get_output_ktx2_file() : comp.get_output_basis_file();
+		}
+
+		pComp->params.m_pJob_pool = nullptr;
+		return (int)result;
+	}
+
+	const uint8_t* compressor_get_output(const compressor* pComp, uint32_t* pSize) {
+		*pSize = (uint32_t)pComp->output.size();
+		return pComp->output.data();
+	}
+}
+#endif
@@ -1,2 +1,137 @@
-
 #include "vendor/transcoder/basisu_transcoder.h"
+
+// Helpers for the parts of the transcoder API that bindgen can't bind directly, such as structs
+// containing STL containers and implicitly declared destructors.
+namespace shim {
+	struct file_info {
+		uint32_t version;
+		uint32_t total_header_size;
+		uint32_t total_selectors;
+		uint32_t selector_codebook_size;
+		uint32_t total_endpoints;
+		uint32_t endpoint_codebook_size;
+		uint32_t tables_size;
+		uint32_t slices_size;
+		basist::basis_texture_type tex_type;
+		uint32_t us_per_frame;
+		uint32_t total_slices;
+		uint32_t total_images;
+		uint32_t userdata0;
+		uint32_t userdata1;
+		basist::basis_tex_format tex_format;
+		bool y_flipped;
+		bool etc1s;
+		bool has_alpha_slices;
+	};
+
+	bool get_file_info(const basist::basisu_transcoder& transcoder, const void* pData, uint32_t data_size, file_info& info);
+	// Copies the per-slice info into pSlices, which must have room for exactly total_slices
+	// entries as reported by get_file_info.
+	bool get_slice_info(const basist::basisu_transcoder& transcoder, const void* pData, uint32_t data_size, basist::basisu_slice_info* pSlices, uint32_t total_slices);
+
+	void destroy_transcoder(basist::basisu_transcoder& transcoder);
+
+	basist::basisu_transcoder_state* new_transcoder_state();
+	void delete_transcoder_state(basist::basisu_transcoder_state* pState);
+
+	basist::etc1_global_selector_codebook* new_selector_codebook(uint32_t N, const uint32_t* pEntries);
+	void delete_selector_codebook(basist::etc1_global_selector_codebook* pCodebook);
+
+	basist::ktx2_transcoder* new_ktx2_transcoder(const basist::etc1_global_selector_codebook* pGlobal_sel_codebook);
+	void delete_ktx2_transcoder(basist::ktx2_transcoder* pTranscoder);
+
+	basist::basisu_lowlevel_etc1s_transcoder* new_lowlevel_etc1s_transcoder(const basist::etc1_global_selector_codebook* pGlobal_sel_codebook);
+	void delete_lowlevel_etc1s_transcoder(basist::basisu_lowlevel_etc1s_transcoder* pTranscoder);
+
+	struct etc1s_endpoint {
+		uint8_t color5[3];
+		uint8_t inten5;
+	};
+	struct etc1s_selector {
+		// 2 bits per pixel, with one byte per row.
+		uint8_t selectors[4];
+	};
+
+	// The codebooks decoded by the last successful decode_palettes call.
+	uint32_t get_etc1s_total_endpoints(const basist::basisu_lowlevel_etc1s_transcoder* pTranscoder);
+	// Copies the endpoint codebook into pEndpoints, which must have room for every entry.
+	void get_etc1s_endpoints(const basist::basisu_lowlevel_etc1s_transcoder* pTranscoder, etc1s_endpoint* pEndpoints);
+	uint32_t get_etc1s_total_selectors(const basist::basisu_lowlevel_etc1s_transcoder* pTranscoder);
+	// Copies the selector codebook into pSelectors, which must have room for every entry.
+	void get_etc1s_selectors(const basist::basisu_lowlevel_etc1s_transcoder* pTranscoder, etc1s_selector* pSelectors);
+
+	basist::basisu_lowlevel_uastc_transcoder* new_lowlevel_uastc_transcoder();
+	void delete_lowlevel_uastc_transcoder(basist::basisu_lowlevel_uastc_transcoder* pTranscoder);
+
+	// Decodes the 16 byte UASTC block at pBlock to the 4x4 RGBA8 pixels at pPixels, row by row.
+	// Wraps the basist::unpack_uastc overload taking a block, which bindgen can't tell apart from
+	// the others.
+	bool unpack_uastc_block(const uint8_t* pBlock, uint8_t* pPixels, bool srgb);
+
+	// Whether the transcoder was compiled with the BASISD_SUPPORT_* options fmt needs.
+	bool transcoder_format_enabled(basist::transcoder_texture_format fmt);
+
+	basist::ktx2_transcoder_state* new_ktx2_transcoder_state();
+	void delete_ktx2_transcoder_state(basist::ktx2_transcoder_state* pState);
+	// Forgets which level's supercompressed data the state holds, keeping the memory for reuse.
+	void reset_ktx2_transcoder_state(basist::ktx2_transcoder_state* pState);
+}
+
+#if BASISU_SYS_ENCODER
+// Helpers wrapping basisu::basis_compressor, which bindgen can't bind at all. The compressor
+// parameters and output only ever live on the C++ side.
+namespace shim {
+	struct compressor;
+
+	// Performs the global encoder initialization. Must be called before any compressor is run.
+	void encoder_init();
+
+	compressor* new_compressor(const basist::etc1_global_selector_codebook* pGlobal_sel_codebook);
+	void delete_compressor(compressor* pComp);
+
+	// Appends a copy of a width x height RGBA8 image to the source images.
+	void compressor_add_image(compressor* pComp, const uint8_t* pRGBA, uint32_t width, uint32_t height);
+	// Appends a copy of an RGBA8 image to the mipmaps of the source image at image_index, which
+	// must already have been added.
+	void compressor_add_mipmap(compressor* pComp, uint32_t image_index, const uint8_t* pRGBA, uint32_t width, uint32_t height);
+	// Resamples the source image at image_index to width x height, using the mipmap filter
+	// settings.
+	void compressor_resize_image(compressor* pComp, uint32_t image_index, uint32_t width, uint32_t height);
+	void compressor_set_tex_type(compressor* pComp, basist::basis_texture_type tex_type);
+	void compressor_set_us_per_frame(compressor* pComp, uint32_t us_per_frame);
+	void compressor_set_quality_level(compressor* pComp, int quality_level);
+	// Selects UASTC instead of ETC1S, packing at the given basisu::cPackUASTCLevel* level.
+	void compressor_set_uastc(compressor* pComp, bool uastc, uint32_t level);
+	void compressor_set_uastc_rdo(compressor* pComp, bool rdo, float quality_scalar, int dict_size, float max_smooth_block_error_scale, float max_smooth_block_std_dev);
+	void compressor_set_perceptual(compressor* pComp, bool perceptual);
+	void compressor_set_y_flip(compressor* pComp, bool y_flip);
+	// Without check_for_alpha or force_alpha the source alpha is ignored.
+	void compressor_set_alpha(compressor* pComp, bool check_for_alpha, bool force_alpha);
+	// Each argument is the index of the source channel that feeds the corresponding output
+	// channel.
+	void compressor_set_swizzle(compressor* pComp, uint8_t r, uint8_t g, uint8_t b, uint8_t a);
+	// Enables or disables both ETC1S endpoint and selector RDO.
+	void compressor_set_etc1s_rdo(compressor* pComp, bool rdo);
+	void compressor_set_global_sel_pal(compressor* pComp, bool global_sel_pal);
+	void compressor_set_mip_gen(compressor* pComp, bool mip_gen);
+	// pFilter names one of the filters of basisu_resample_filters.cpp, like "kaiser".
+	void compressor_set_mip_filter(compressor* pComp, const char* pFilter);
+	void compressor_set_mip_srgb(compressor* pComp, bool mip_srgb);
+	void compressor_set_mip_wrapping(compressor* pComp, bool mip_wrapping);
+	void compressor_set_mip_renormalize(compressor* pComp, bool mip_renormalize);
+	// Produces a .ktx2 instead of a .basis file if create_ktx2 is set. srgb selects the transfer
+	// function recorded in the data format descriptor.
+	void compressor_set_ktx2(compressor* pComp, bool create_ktx2, bool zstd, int zstd_level, bool srgb);
+
+	// A basisu::job_pool with num_threads threads, including the thread using it.
+	struct job_pool;
+	job_pool* new_job_pool(uint32_t num_threads);
+	void delete_job_pool(job_pool* pPool);
+
+	// Runs the compressor on the threads of pPool, which must not be used by anything else in the
+	// meantime. Returns a basisu::basis_compressor::error_code.
+	int compressor_process(compressor* pComp, job_pool* pPool);
+	// Returns the .basis or .ktx2 file produced by the last successful compressor_process call.
+	const uint8_t* compressor_get_output(const compressor* pComp, uint32_t* pSize);
+}
+#endif
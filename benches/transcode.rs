@@ -0,0 +1,89 @@
+//! Transcode throughput for each output format, from ETC1S and UASTC files with and without
+//! alpha. The inputs are encoded when the benchmark starts, so it needs the `encoder` feature:
+//!
+//! ```sh
+//! cargo bench --features encoder
+//! ```
+
+use basisu::encoder::UastcLevel;
+use basisu::{Compressor, CompressorParams, OutputFormat, TranscodeParams, Transcoder, Workspace};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const SIZE: u32 = 512;
+
+/// A `SIZE` x `SIZE` image with smooth gradients and some noise, so neither codec gets an
+/// unrealistically easy input. Without `alpha` every pixel is opaque.
+fn test_image(alpha: bool) -> Vec<u8> {
+    let mut state = 0x1234_5678u32;
+    let mut noise = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        (state % 32) as u8
+    };
+    let mut rgba = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let r = (x * 255 / SIZE) as u8;
+            let g = (y * 255 / SIZE) as u8;
+            let b = ((x + y) * 127 / SIZE) as u8;
+            let a = if alpha { ((x ^ y) & 0xFF) as u8 } else { 255 };
+            rgba.extend_from_slice(&[r.wrapping_add(noise()), g.wrapping_add(noise()), b, a]);
+        }
+    }
+    rgba
+}
+
+fn encode(rgba: &[u8], params: CompressorParams) -> Vec<u8> {
+    let mut compressor = Compressor::new(params);
+    compressor.add_image_rgba(rgba, SIZE, SIZE).unwrap();
+    compressor.compress().unwrap()
+}
+
+fn transcode(c: &mut Criterion) {
+    let opaque = test_image(false);
+    let transparent = test_image(true);
+    let uastc = || CompressorParams::new().uastc(UastcLevel::Default);
+    let inputs = [
+        ("etc1s", encode(&opaque, CompressorParams::new())),
+        ("etc1s-alpha", encode(&transparent, CompressorParams::new())),
+        ("uastc", encode(&opaque, uastc())),
+        ("uastc-alpha", encode(&transparent, uastc())),
+    ];
+
+    let mut workspace = Workspace::new();
+    for (name, data) in &inputs {
+        let file = Transcoder::open(data).unwrap();
+        let mut group = c.benchmark_group(format!("transcode/{}", name));
+        group.throughput(Throughput::Elements(u64::from(SIZE * SIZE)));
+        for &format in OutputFormat::ALL
+            .iter()
+            .filter(|&&format| file.can_transcode(format))
+        {
+            let size = file.required_output_size(0, 0, format).unwrap();
+            let mut output = vec![0; size];
+            group.bench_with_input(
+                BenchmarkId::from_parameter(format),
+                &format,
+                |b, &format| {
+                    b.iter(|| {
+                        file.transcode_image_level_with_workspace(
+                            0,
+                            0,
+                            &mut output,
+                            format,
+                            TranscodeParams::default(),
+                            &mut workspace,
+                        )
+                        .unwrap();
+                        black_box(&output);
+                    })
+                },
+            );
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, transcode);
+criterion_main!(benches);
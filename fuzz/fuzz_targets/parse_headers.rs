@@ -0,0 +1,25 @@
+//! The Rust side header parsers and the C++ header validation. None of these decode any image
+//! data, so they run quickly on large inputs.
+
+#![no_main]
+
+use basisu::{header, ktx2, metadata};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(parsed) = header::ParsedHeader::parse(data) {
+        for slice in &parsed.slices {
+            let _ = parsed.level_data_range(slice.image_index, slice.level_index.into());
+        }
+        let _ = parsed.verify_checksums(data);
+    }
+    let _ = ktx2::ParsedHeader::parse(data);
+
+    if metadata::validate_file_header(data) {
+        let _ = metadata::get_file_info(data);
+        let _ = metadata::get_slice_info(data);
+        for image in 0..metadata::get_total_images(data).min(64) {
+            let _ = metadata::get_image_info(data, image);
+        }
+    }
+});
@@ -0,0 +1,44 @@
+//! Starting to transcode arbitrary .basis and .ktx2 files, then transcoding their levels to an
+//! ETC1S friendly, a UASTC friendly and an uncompressed format.
+//!
+//! Levels larger than `MAX_PIXELS` are skipped so that each iteration stays fast. The limit is on
+//! the dimensions from the header, which is what the library trusts in order to size buffers.
+
+#![no_main]
+
+use basisu::{OutputFormat, Transcoder};
+use libfuzzer_sys::fuzz_target;
+
+const MAX_PIXELS: u64 = 256 * 256;
+const MAX_IMAGES: u32 = 8;
+const FORMATS: [OutputFormat; 3] = [
+    OutputFormat::BC1_RGB,
+    OutputFormat::BC7_RGBA,
+    OutputFormat::RGBA32,
+];
+
+fuzz_target!(|data: &[u8]| {
+    let file = match Transcoder::open(data) {
+        Ok(file) => file,
+        Err(_) => return,
+    };
+    let _ = file.get_texture_type();
+    let _ = file.has_alpha();
+
+    for image in 0..file.get_total_images().min(MAX_IMAGES) {
+        for level in 0..file.get_total_image_levels(image) {
+            let (width, height) = match file.level_dimensions(image, level) {
+                Ok(dimensions) => dimensions,
+                Err(_) => continue,
+            };
+            if u64::from(width) * u64::from(height) > MAX_PIXELS {
+                continue;
+            }
+            for &format in &FORMATS {
+                if file.can_transcode(format) {
+                    let _ = file.transcode_image_level_to_vec(image, level, format);
+                }
+            }
+        }
+    }
+});
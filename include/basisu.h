@@ -0,0 +1,74 @@
+/* C interface of the basisu crate, built with its `capi` feature. See src/capi.rs for details. */
+
+#ifndef BASISU_H
+#define BASISU_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes returned by the functions below. */
+#define BASISU_OK 0
+#define BASISU_INVALID_FILE_CONTENTS 1
+#define BASISU_INVALID_ARGUMENT 2
+#define BASISU_INVALID_HEADER 3
+#define BASISU_UNSUPPORTED_FORMAT 4
+#define BASISU_OUTPUT_BUFFER_TOO_SMALL 5
+#define BASISU_SLICE_DECODE_FAILED 6
+#define BASISU_INDEX_OUT_OF_RANGE 7
+#define BASISU_FILE_TOO_LARGE 8
+#define BASISU_UNSUPPORTED_SUPERCOMPRESSION 9
+#define BASISU_OTHER_ERROR 10
+#define BASISU_IMAGE_TOO_LARGE 11
+
+/* Output formats, matching basist::transcoder_texture_format. */
+#define BASISU_FORMAT_ETC1_RGB 0
+#define BASISU_FORMAT_ETC2_RGBA 1
+#define BASISU_FORMAT_BC1_RGB 2
+#define BASISU_FORMAT_BC3_RGBA 3
+#define BASISU_FORMAT_BC4_R 4
+#define BASISU_FORMAT_BC5_RG 5
+#define BASISU_FORMAT_BC7_RGBA 6
+#define BASISU_FORMAT_PVRTC1_4_RGB 8
+#define BASISU_FORMAT_PVRTC1_4_RGBA 9
+#define BASISU_FORMAT_ASTC_4x4_RGBA 10
+#define BASISU_FORMAT_ATC_RGB 11
+#define BASISU_FORMAT_ATC_RGBA 12
+#define BASISU_FORMAT_RGBA32 13
+#define BASISU_FORMAT_RGB565 14
+#define BASISU_FORMAT_BGR565 15
+#define BASISU_FORMAT_RGBA4444 16
+#define BASISU_FORMAT_FXT1_RGB 17
+#define BASISU_FORMAT_PVRTC2_4_RGB 18
+#define BASISU_FORMAT_PVRTC2_4_RGBA 19
+#define BASISU_FORMAT_ETC2_EAC_R11 20
+#define BASISU_FORMAT_ETC2_EAC_RG11 21
+
+/* An open .basis or .ktx2 file. */
+typedef struct BasisuFile BasisuFile;
+
+/* Open a .basis or .ktx2 file. The data is copied. Free the handle with basisu_free. */
+int basisu_open(const uint8_t *data, size_t len, BasisuFile **file);
+void basisu_free(BasisuFile *file);
+
+/* Images of layered files are numbered layer * faces + face. */
+uint32_t basisu_total_images(const BasisuFile *file);
+uint32_t basisu_total_levels(const BasisuFile *file, uint32_t image_index);
+int basisu_level_dimensions(const BasisuFile *file, uint32_t image_index, uint32_t level_index,
+                            uint32_t *width, uint32_t *height);
+
+/* Returns 1 if the file can be transcoded to format and 0 otherwise. */
+int basisu_can_transcode(const BasisuFile *file, uint32_t format);
+int basisu_required_output_size(const BasisuFile *file, uint32_t image_index,
+                                uint32_t level_index, uint32_t format, size_t *size);
+int basisu_transcode(const BasisuFile *file, uint32_t image_index, uint32_t level_index,
+                     uint32_t format, uint8_t *output, size_t output_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
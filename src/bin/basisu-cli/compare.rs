@@ -0,0 +1,241 @@
+//! `basisu-cli compare`: measure how closely a file matches the images it was encoded from.
+
+use std::ops::Range;
+
+use basisu::{BasisFile, OutputFormat};
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+use serde::{Serialize, Serializer};
+
+use crate::{Args, Result};
+
+const HELP: &str = "usage: basisu-cli compare <input> <reference>... [options]
+
+Transcode every image and mip level of a .basis or .ktx2 file to RGBA32 and compare it with the
+reference PNGs, printing the PSNR of the color and alpha channels and the SSIM of the luma. Give
+either one reference for every image or a single one shared by all of them. Mip levels are
+compared with the reference scaled down to their size.
+
+options:
+    --json  print the results as JSON, where a PSNR of \"inf\" means the channels match exactly";
+
+/// Side of the square windows SSIM is computed over.
+const SSIM_WINDOW: usize = 8;
+
+#[derive(Serialize)]
+struct Comparison {
+    image: u32,
+    level: u32,
+    width: u32,
+    height: u32,
+    /// PSNR of the RGB channels in dB, infinite if they match exactly.
+    #[serde(serialize_with = "serialize_psnr")]
+    psnr: f64,
+    /// PSNR of the alpha channel in dB.
+    #[serde(serialize_with = "serialize_psnr")]
+    alpha_psnr: f64,
+    ssim: f64,
+}
+
+impl Comparison {
+    fn new(image: u32, level: u32, rgba: &[u8], reference: &RgbaImage) -> Self {
+        let (width, height) = reference.dimensions();
+        let reference = reference.as_raw();
+        Self {
+            image,
+            level,
+            width,
+            height,
+            psnr: psnr(rgba, reference, 0..3),
+            alpha_psnr: psnr(rgba, reference, 3..4),
+            ssim: ssim(rgba, reference, width as usize, height as usize),
+        }
+    }
+}
+
+/// JSON has no infinity, so exact matches are written as the string `"inf"` rather than `null`.
+fn serialize_psnr<S: Serializer>(
+    psnr: &f64,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    if psnr.is_finite() {
+        serializer.serialize_f64(*psnr)
+    } else {
+        serializer.serialize_str("inf")
+    }
+}
+
+/// Peak signal to noise ratio in dB between the `channels` of two RGBA images.
+fn psnr(a: &[u8], b: &[u8], channels: Range<usize>) -> f64 {
+    let mut squared_error = 0u64;
+    let mut count = 0u64;
+    for (a, b) in a.chunks_exact(4).zip(b.chunks_exact(4)) {
+        for channel in channels.clone() {
+            let difference = a[channel] as i64 - b[channel] as i64;
+            squared_error += (difference * difference) as u64;
+            count += 1;
+        }
+    }
+    if squared_error == 0 {
+        return f64::INFINITY;
+    }
+    let mse = squared_error as f64 / count as f64;
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
+
+/// Structural similarity between the luma of two `width` x `height` RGBA images, averaged over
+/// non-overlapping windows.
+fn ssim(a: &[u8], b: &[u8], width: usize, height: usize) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+    let luma =
+        |pixel: &[u8]| 0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64;
+
+    let mut total = 0.0;
+    let mut windows = 0;
+    for y0 in (0..height).step_by(SSIM_WINDOW) {
+        for x0 in (0..width).step_by(SSIM_WINDOW) {
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) =
+                (0.0, 0.0, 0.0, 0.0, 0.0);
+            let mut n = 0.0;
+            for y in y0..(y0 + SSIM_WINDOW).min(height) {
+                for x in x0..(x0 + SSIM_WINDOW).min(width) {
+                    let i = (y * width + x) * 4;
+                    let (la, lb) = (luma(&a[i..i + 4]), luma(&b[i..i + 4]));
+                    sum_a += la;
+                    sum_b += lb;
+                    sum_aa += la * la;
+                    sum_bb += lb * lb;
+                    sum_ab += la * lb;
+                    n += 1.0;
+                }
+            }
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let variance_a = sum_aa / n - mean_a * mean_a;
+            let variance_b = sum_bb / n - mean_b * mean_b;
+            let covariance = sum_ab / n - mean_a * mean_b;
+            total += (2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2)
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (variance_a + variance_b + C2));
+            windows += 1;
+        }
+    }
+    total / windows as f64
+}
+
+pub fn run(mut args: Args) -> Result<()> {
+    if args.flag("--help") {
+        println!("{}", HELP);
+        return Ok(());
+    }
+    let json = args.flag("--json");
+    let (input, references) = match &args.positional()?[..] {
+        [input, references @ ..] if !references.is_empty() => (input.clone(), references.to_vec()),
+        _ => return Err(HELP.into()),
+    };
+
+    let file = BasisFile::open(&input)?;
+    let file = file.transcoder();
+    let total_images = file.get_total_images();
+    if references.len() != 1 && references.len() != total_images as usize {
+        return Err(format!(
+            "expected 1 or {} reference images, got {}",
+            total_images,
+            references.len()
+        )
+        .into());
+    }
+    let references = references
+        .iter()
+        .map(|path| Ok(image::open(path)?.to_rgba8()))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut comparisons = Vec::new();
+    for image in 0..total_images {
+        let reference = &references[image as usize % references.len()];
+        let (width, height) = file.level_dimensions(image, 0)?;
+        if reference.dimensions() != (width, height) {
+            return Err(format!(
+                "image {} is {}x{} but its reference is {}x{}",
+                image,
+                width,
+                height,
+                reference.width(),
+                reference.height()
+            )
+            .into());
+        }
+        for level in 0..file.get_total_image_levels(image) {
+            let (width, height) = file.level_dimensions(image, level)?;
+            let scaled;
+            let reference = if level == 0 {
+                reference
+            } else {
+                scaled = imageops::resize(reference, width, height, FilterType::Triangle);
+                &scaled
+            };
+            let rgba = file.transcode_image_level_to_vec(image, level, OutputFormat::RGBA32)?;
+            comparisons.push(Comparison::new(image, level, &rgba, reference));
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&comparisons)?);
+    } else {
+        for c in &comparisons {
+            println!(
+                "image {} level {} ({}x{}): PSNR {:.2} dB, alpha PSNR {:.2} dB, SSIM {:.4}",
+                c.image, c.level, c.width, c.height, c.psnr, c.alpha_psnr, c.ssim
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_images() {
+        let rgba: Vec<u8> = (0..16 * 12 * 4).map(|i| (i * 7) as u8).collect();
+        assert_eq!(psnr(&rgba, &rgba, 0..3), f64::INFINITY);
+        assert!((ssim(&rgba, &rgba, 16, 12) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn psnr_of_known_error() {
+        let a = [10, 20, 30, 255, 10, 20, 30, 255];
+        let b = [11, 21, 31, 255, 9, 19, 29, 255];
+        // Every color channel is off by one, so the MSE is 1.
+        assert!((psnr(&a, &b, 0..3) - 20.0 * 255f64.log10()).abs() < 1e-9);
+        assert_eq!(psnr(&a, &b, 3..4), f64::INFINITY);
+    }
+
+    #[test]
+    fn json_output() {
+        let comparison = Comparison {
+            image: 0,
+            level: 0,
+            width: 4,
+            height: 4,
+            psnr: 40.5,
+            alpha_psnr: f64::INFINITY,
+            ssim: 1.0,
+        };
+        let json = serde_json::to_string(&comparison).unwrap();
+        assert!(json.contains(r#""psnr":40.5"#), "{}", json);
+        assert!(json.contains(r#""alpha_psnr":"inf""#), "{}", json);
+    }
+
+    #[test]
+    fn ssim_drops_with_noise() {
+        let a: Vec<u8> = (0..16 * 16 * 4).map(|i| (i * 13) as u8).collect();
+        let b: Vec<u8> = a
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| v ^ (i as u8 & 0x30))
+            .collect();
+        let score = ssim(&a, &b, 16, 16);
+        assert!(score < 0.99 && score > -1.0, "{}", score);
+    }
+}
@@ -0,0 +1,204 @@
+//! `basisu-cli info`: describe the contents of a file.
+
+use std::fmt;
+
+use basisu::{BasisFile, Container, TexFormat, TextureType, Transcoder};
+use serde::Serialize;
+
+use crate::{Args, Result};
+
+const HELP: &str = "usage: basisu-cli info <input> [options]
+
+Print the texture type, compression format, images and mip levels of a .basis or .ktx2 file,
+along with the user data of .basis files and the key/value data of .ktx2 files.
+
+options:
+    --json  print the information as JSON";
+
+#[derive(Serialize)]
+struct Info {
+    container: Container,
+    texture_type: TextureType,
+    tex_format: TexFormat,
+    has_alpha: bool,
+    /// Dimensions of every level, indexed by image and then by level.
+    images: Vec<Vec<Dimensions>>,
+    /// Only present in .basis files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    userdata: Option<[u32; 2]>,
+    /// Only present in .ktx2 files.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    key_values: Vec<KeyValue>,
+}
+
+#[derive(Serialize)]
+struct Dimensions {
+    width: u32,
+    height: u32,
+}
+
+#[derive(Serialize)]
+struct KeyValue {
+    key: String,
+    #[serde(flatten)]
+    value: Value,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Value {
+    /// A UTF-8 string, without its NUL terminator.
+    Text(String),
+    /// Any other value, as a hex string.
+    Hex(String),
+}
+
+impl KeyValue {
+    fn new(key: &str, value: &[u8]) -> Self {
+        let text = value.strip_suffix(&[0]).unwrap_or(value);
+        let text = std::str::from_utf8(text)
+            .ok()
+            .filter(|text| !text.chars().any(|c| c.is_control() && c != '\n'));
+        Self {
+            key: key.to_owned(),
+            value: match text {
+                Some(text) => Value::Text(text.to_owned()),
+                None => Value::Hex(value.iter().map(|byte| format!("{:02x}", byte)).collect()),
+            },
+        }
+    }
+}
+
+impl Info {
+    fn new(file: &Transcoder) -> Result<Self> {
+        let images = (0..file.get_total_images())
+            .map(|image| {
+                (0..file.get_total_image_levels(image))
+                    .map(|level| {
+                        let (width, height) = file.level_dimensions(image, level)?;
+                        Ok(Dimensions { width, height })
+                    })
+                    .collect::<Result<_>>()
+            })
+            .collect::<Result<_>>()?;
+        let userdata = match file.as_basis() {
+            Some(file) => {
+                let (userdata0, userdata1) = file.get_userdata();
+                Some([userdata0, userdata1])
+            }
+            None => None,
+        };
+        let key_values = file
+            .as_ktx2()
+            .into_iter()
+            .flat_map(|file| file.key_values())
+            .map(|(key, value)| KeyValue::new(key, value))
+            .collect();
+        Ok(Self {
+            container: file.container(),
+            texture_type: file.get_texture_type(),
+            tex_format: file.get_tex_format(),
+            has_alpha: file.has_alpha(),
+            images,
+            userdata,
+            key_values,
+        })
+    }
+}
+
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "container:    {:?}", self.container)?;
+        writeln!(f, "texture type: {:?}", self.texture_type)?;
+        writeln!(f, "format:       {:?}", self.tex_format)?;
+        writeln!(f, "alpha:        {}", self.has_alpha)?;
+        if let Some([userdata0, userdata1]) = self.userdata {
+            writeln!(f, "userdata:     {:#010x} {:#010x}", userdata0, userdata1)?;
+        }
+        writeln!(f, "images:       {}", self.images.len())?;
+        for (image, levels) in self.images.iter().enumerate() {
+            writeln!(f, "  image {}:", image)?;
+            for (level, dimensions) in levels.iter().enumerate() {
+                writeln!(
+                    f,
+                    "    level {}: {}x{}",
+                    level, dimensions.width, dimensions.height
+                )?;
+            }
+        }
+        if !self.key_values.is_empty() {
+            writeln!(f, "key/values:")?;
+        }
+        for key_value in &self.key_values {
+            match &key_value.value {
+                Value::Text(text) => writeln!(f, "  {}: {:?}", key_value.key, text)?,
+                Value::Hex(hex) => {
+                    writeln!(f, "  {}: {} bytes: {}", key_value.key, hex.len() / 2, hex)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn run(mut args: Args) -> Result<()> {
+    if args.flag("--help") {
+        println!("{}", HELP);
+        return Ok(());
+    }
+    let json = args.flag("--json");
+    let input = match &args.positional()?[..] {
+        [input] => input.clone(),
+        _ => return Err(HELP.into()),
+    };
+
+    let file = BasisFile::open(&input)?;
+    let info = Info::new(file.transcoder())?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+    } else {
+        print!("{}", info);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_value_text() {
+        let value = KeyValue::new("KTXwriter", b"basisu\0");
+        assert_eq!(value.value, Value::Text("basisu".to_owned()));
+
+        let value = KeyValue::new("KTXanimData", &[1, 0, 0, 0, 0xff]);
+        assert_eq!(value.value, Value::Hex("01000000ff".to_owned()));
+    }
+
+    #[test]
+    fn text_output() {
+        let info = Info {
+            container: Container::Ktx2,
+            texture_type: TextureType::Texture2D,
+            tex_format: TexFormat::UASTC4x4,
+            has_alpha: false,
+            images: vec![vec![
+                Dimensions {
+                    width: 8,
+                    height: 4,
+                },
+                Dimensions {
+                    width: 4,
+                    height: 2,
+                },
+            ]],
+            userdata: None,
+            key_values: vec![KeyValue::new("KTXwriter", b"basisu\0")],
+        };
+        let text = info.to_string();
+        assert!(text.contains("format:       UASTC4x4\n"));
+        assert!(text.contains("  image 0:\n    level 0: 8x4\n    level 1: 4x2\n"));
+        assert!(text.ends_with("key/values:\n  KTXwriter: \"basisu\"\n"));
+        assert!(!text.contains("userdata"));
+    }
+}
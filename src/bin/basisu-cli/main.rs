@@ -0,0 +1,127 @@
+//! Command line interface to the transcoder, built with the `cli` feature.
+
+use std::error::Error;
+use std::process;
+use std::str::FromStr;
+
+mod compare;
+mod info;
+mod transcode;
+
+const USAGE: &str = "usage: basisu-cli <command> [options]
+
+commands:
+    compare     measure the quality of a file against reference PNGs
+    info        describe the contents of a .basis or .ktx2 file
+    transcode   convert a .basis or .ktx2 file to another format
+
+Run `basisu-cli <command> --help` for the options of a command.";
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// The arguments following the command name. Options are removed as they are looked up, which
+/// leaves the positional arguments.
+struct Args(Vec<String>);
+
+impl Args {
+    /// Remove `--name` and return whether it was present.
+    fn flag(&mut self, name: &str) -> bool {
+        let len = self.0.len();
+        self.0.retain(|arg| arg != name);
+        self.0.len() != len
+    }
+
+    /// Remove `--name <value>` or `--name=<value>` and return the value.
+    fn value(&mut self, name: &str) -> Result<Option<String>> {
+        let prefix = format!("{}=", name);
+        let position = match self
+            .0
+            .iter()
+            .position(|arg| arg == name || arg.starts_with(&prefix))
+        {
+            Some(position) => position,
+            None => return Ok(None),
+        };
+        let arg = self.0.remove(position);
+        if arg != name {
+            return Ok(Some(arg[prefix.len()..].to_owned()));
+        }
+        if position == self.0.len() {
+            return Err(format!("{} needs a value", name).into());
+        }
+        Ok(Some(self.0.remove(position)))
+    }
+
+    /// Like [`value`](Self::value), but parse the value.
+    fn parse<T: FromStr>(&mut self, name: &str) -> Result<Option<T>>
+    where
+        T::Err: Error + 'static,
+    {
+        match self.value(name)? {
+            Some(value) => value.parse().map(Some).map_err(|error: T::Err| {
+                format!("invalid {} \"{}\": {}", name, value, error).into()
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Return the remaining arguments, which must all be positional.
+    fn positional(self) -> Result<Vec<String>> {
+        match self.0.iter().find(|arg| arg.starts_with("--")) {
+            Some(arg) => Err(format!("unknown option {}", arg).into()),
+            None => Ok(self.0),
+        }
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let command = args.next();
+    let args = Args(args.collect());
+    let result = match command.as_deref() {
+        Some("compare") => compare::run(args),
+        Some("info") => info::run(args),
+        Some("transcode") => transcode::run(args),
+        Some("--help") | Some("-h") | Some("help") => {
+            println!("{}", USAGE);
+            return;
+        }
+        Some(command) => Err(format!("unknown command \"{}\"\n\n{}", command, USAGE).into()),
+        None => Err(USAGE.into()),
+    };
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Args {
+        Args(args.iter().map(|&arg| arg.to_owned()).collect())
+    }
+
+    #[test]
+    fn parse_args() {
+        let mut a = args(&[
+            "in.basis",
+            "--srgb",
+            "--level",
+            "2",
+            "--format=bc7",
+            "out.dds",
+        ]);
+        assert!(a.flag("--srgb"));
+        assert!(!a.flag("--srgb"));
+        assert_eq!(a.parse::<u32>("--level").unwrap(), Some(2));
+        assert_eq!(a.value("--format").unwrap().as_deref(), Some("bc7"));
+        assert_eq!(a.value("--image").unwrap(), None);
+        assert_eq!(a.positional().unwrap(), ["in.basis", "out.dds"]);
+
+        assert!(args(&["--level"]).value("--level").is_err());
+        assert!(args(&["--level", "x"]).parse::<u32>("--level").is_err());
+        assert!(args(&["in.basis", "--bogus"]).positional().is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! `basisu-cli transcode`: convert a file to one output format.
+
+use std::fs;
+use std::path::Path;
+
+use basisu::container::{dds, ktx, ktx2};
+use basisu::{BasisFile, OutputFormat};
+
+use crate::{Args, Result};
+
+const HELP: &str = "usage: basisu-cli transcode <input> <output> [options]
+
+Transcode a .basis or .ktx2 file. The output container is chosen from the extension of <output>:
+.dds, .ktx and .ktx2 files hold every image and mip level, and anything else gets the raw data
+of a single level.
+
+options:
+    --format <format>        output format, such as bc7, astc or rgba32 (default: rgba32)
+    --container <container>  raw, dds, ktx or ktx2, overriding the extension of <output>
+    --image <index>          image written to raw output (default: 0)
+    --level <index>          mip level written to raw output (default: 0)
+    --srgb                   mark the data in a container as sRGB";
+
+/// How the transcoded data is stored.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Container {
+    Raw,
+    Dds,
+    Ktx,
+    Ktx2,
+}
+
+impl Container {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match &*name.to_ascii_lowercase() {
+            "raw" => Container::Raw,
+            "dds" => Container::Dds,
+            "ktx" => Container::Ktx,
+            "ktx2" => Container::Ktx2,
+            _ => return None,
+        })
+    }
+
+    fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Self::from_name)
+            .unwrap_or(Container::Raw)
+    }
+}
+
+pub fn run(mut args: Args) -> Result<()> {
+    if args.flag("--help") {
+        println!("{}", HELP);
+        return Ok(());
+    }
+    let format = args
+        .parse::<OutputFormat>("--format")?
+        .unwrap_or(OutputFormat::RGBA32);
+    let container = args.value("--container")?;
+    let image = args.parse::<u32>("--image")?;
+    let level = args.parse::<u32>("--level")?;
+    let srgb = args.flag("--srgb");
+    let (input, output) = match &args.positional()?[..] {
+        [input, output] => (input.clone(), output.clone()),
+        _ => return Err(HELP.into()),
+    };
+
+    let container = match container {
+        Some(name) => {
+            Container::from_name(&name).ok_or_else(|| format!("unknown container \"{}\"", name))?
+        }
+        None => Container::from_path(Path::new(&output)),
+    };
+    if container != Container::Raw && (image.is_some() || level.is_some()) {
+        return Err("--image and --level only apply to raw output".into());
+    }
+
+    let file = BasisFile::open(&input)?;
+    let file = file.transcoder();
+    let data = match container {
+        Container::Raw => {
+            file.transcode_image_level_to_vec(image.unwrap_or(0), level.unwrap_or(0), format)?
+        }
+        Container::Dds => dds::transcode_to_dds(file, format, srgb)?,
+        Container::Ktx => ktx::transcode_to_ktx(file, format, srgb)?,
+        Container::Ktx2 => ktx2::transcode_to_ktx2(file, format, srgb)?,
+    };
+    fs::write(&output, data)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn container_from_path() {
+        assert_eq!(Container::from_path(Path::new("a/b.dds")), Container::Dds);
+        assert_eq!(Container::from_path(Path::new("b.KTX")), Container::Ktx);
+        assert_eq!(Container::from_path(Path::new("b.ktx2")), Container::Ktx2);
+        assert_eq!(Container::from_path(Path::new("b.bin")), Container::Raw);
+        assert_eq!(Container::from_path(Path::new("b")), Container::Raw);
+        assert_eq!(Container::from_name("raw"), Some(Container::Raw));
+        assert_eq!(Container::from_name("png"), None);
+    }
+}
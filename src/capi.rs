@@ -0,0 +1,275 @@
+//! A C interface to [`Transcoder`], available with the `capi` feature. The matching declarations
+//! are in `include/basisu.h`.
+//!
+//! Functions returning `c_int` give [`BASISU_OK`] on success and one of the other `BASISU_*`
+//! codes on failure. Output formats are passed as the raw [`OutputFormat`] values, which match
+//! basis_universal's `transcoder_texture_format`.
+
+use alloc::boxed::Box;
+use core::ffi::c_int;
+use core::mem::ManuallyDrop;
+use core::slice;
+
+use crate::{BasisError, OutputFormat, Transcoder};
+
+pub const BASISU_OK: c_int = 0;
+pub const BASISU_INVALID_FILE_CONTENTS: c_int = 1;
+pub const BASISU_INVALID_ARGUMENT: c_int = 2;
+pub const BASISU_INVALID_HEADER: c_int = 3;
+pub const BASISU_UNSUPPORTED_FORMAT: c_int = 4;
+pub const BASISU_OUTPUT_BUFFER_TOO_SMALL: c_int = 5;
+pub const BASISU_SLICE_DECODE_FAILED: c_int = 6;
+pub const BASISU_INDEX_OUT_OF_RANGE: c_int = 7;
+pub const BASISU_FILE_TOO_LARGE: c_int = 8;
+pub const BASISU_UNSUPPORTED_SUPERCOMPRESSION: c_int = 9;
+pub const BASISU_OTHER_ERROR: c_int = 10;
+pub const BASISU_IMAGE_TOO_LARGE: c_int = 11;
+
+/// An open .basis or .ktx2 file, owning a copy of its data.
+pub struct BasisuFile {
+    transcoder: ManuallyDrop<Transcoder<'static>>,
+    // Kept as a raw pointer rather than a `Box`, whose moves would invalidate the borrow held by
+    // `transcoder`. Freed in `drop`, after the transcoder.
+    data: *mut [u8],
+}
+
+impl Drop for BasisuFile {
+    fn drop(&mut self) {
+        unsafe {
+            ManuallyDrop::drop(&mut self.transcoder);
+            drop(Box::from_raw(self.data));
+        }
+    }
+}
+
+fn output_format(raw: u32) -> Option<OutputFormat> {
+    OutputFormat::ALL
+        .iter()
+        .copied()
+        .find(|&format| format as u32 == raw)
+}
+
+fn error_code(error: BasisError) -> c_int {
+    match error {
+        BasisError::InvalidFileContents => BASISU_INVALID_FILE_CONTENTS,
+        BasisError::InvalidArgument => BASISU_INVALID_ARGUMENT,
+        BasisError::InvalidHeader => BASISU_INVALID_HEADER,
+        BasisError::UnsupportedFormat(_) => BASISU_UNSUPPORTED_FORMAT,
+        BasisError::OutputBufferTooSmall { .. } => BASISU_OUTPUT_BUFFER_TOO_SMALL,
+        BasisError::SliceDecodeFailed => BASISU_SLICE_DECODE_FAILED,
+        BasisError::ImageIndexOutOfRange { .. }
+        | BasisError::LevelIndexOutOfRange { .. }
+        | BasisError::LayerIndexOutOfRange { .. }
+        | BasisError::FaceIndexOutOfRange { .. } => BASISU_INDEX_OUT_OF_RANGE,
+        BasisError::FileTooLarge => BASISU_FILE_TOO_LARGE,
+        BasisError::UnsupportedSupercompression(_) => BASISU_UNSUPPORTED_SUPERCOMPRESSION,
+        BasisError::ImageTooLarge => BASISU_IMAGE_TOO_LARGE,
+        #[cfg(feature = "encoder")]
+        BasisError::EncodeFailed(_) => BASISU_OTHER_ERROR,
+        #[cfg(feature = "std")]
+        BasisError::Io(_) => BASISU_OTHER_ERROR,
+    }
+}
+
+fn status(result: Result<(), BasisError>) -> c_int {
+    result.map_or_else(error_code, |()| BASISU_OK)
+}
+
+/// Open the `len` bytes at `data` as a .basis or .ktx2 file. The data is copied, so it may be
+/// freed once this returns. On success `*file` is set to a handle that must be passed to
+/// [`basisu_free`].
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `file` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn basisu_open(
+    data: *const u8,
+    len: usize,
+    file: *mut *mut BasisuFile,
+) -> c_int {
+    if data.is_null() || file.is_null() {
+        return BASISU_INVALID_ARGUMENT;
+    }
+    let data: Box<[u8]> = slice::from_raw_parts(data, len).into();
+    let data = Box::into_raw(data);
+    // The copy is only freed after the transcoder, see `BasisuFile`.
+    match Transcoder::open(&*data) {
+        Ok(transcoder) => {
+            *file = Box::into_raw(Box::new(BasisuFile {
+                transcoder: ManuallyDrop::new(transcoder),
+                data,
+            }));
+            BASISU_OK
+        }
+        Err(error) => {
+            drop(Box::from_raw(data));
+            error_code(error)
+        }
+    }
+}
+
+/// Close a file opened by [`basisu_open`]. Passing null does nothing.
+///
+/// # Safety
+///
+/// `file` must be null or a handle from [`basisu_open`] that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn basisu_free(file: *mut BasisuFile) {
+    if !file.is_null() {
+        drop(Box::from_raw(file));
+    }
+}
+
+/// Return the number of images in `file`, numbered as described on [`Transcoder`].
+///
+/// # Safety
+///
+/// `file` must be a live handle from [`basisu_open`].
+#[no_mangle]
+pub unsafe extern "C" fn basisu_total_images(file: *const BasisuFile) -> u32 {
+    (*file).transcoder.get_total_images()
+}
+
+/// Return the number of mip levels of image `image_index`, or zero if it doesn't exist.
+///
+/// # Safety
+///
+/// `file` must be a live handle from [`basisu_open`].
+#[no_mangle]
+pub unsafe extern "C" fn basisu_total_levels(file: *const BasisuFile, image_index: u32) -> u32 {
+    (*file).transcoder.get_total_image_levels(image_index)
+}
+
+/// Store the dimensions of the indicated `image_index` / `level_index` pair in `*width` and
+/// `*height`.
+///
+/// # Safety
+///
+/// `file` must be a live handle from [`basisu_open`], and `width` and `height` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn basisu_level_dimensions(
+    file: *const BasisuFile,
+    image_index: u32,
+    level_index: u32,
+    width: *mut u32,
+    height: *mut u32,
+) -> c_int {
+    if width.is_null() || height.is_null() {
+        return BASISU_INVALID_ARGUMENT;
+    }
+    status(
+        (*file)
+            .transcoder
+            .level_dimensions(image_index, level_index)
+            .map(|(w, h)| {
+                *width = w;
+                *height = h;
+            }),
+    )
+}
+
+/// Return 1 if `file` can be transcoded to `format` and 0 otherwise.
+///
+/// # Safety
+///
+/// `file` must be a live handle from [`basisu_open`].
+#[no_mangle]
+pub unsafe extern "C" fn basisu_can_transcode(file: *const BasisuFile, format: u32) -> c_int {
+    output_format(format).map_or(0, |format| {
+        (*file).transcoder.can_transcode(format) as c_int
+    })
+}
+
+/// Store in `*size` the number of bytes needed to transcode the indicated `image_index` /
+/// `level_index` pair to `format`.
+///
+/// # Safety
+///
+/// `file` must be a live handle from [`basisu_open`] and `size` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn basisu_required_output_size(
+    file: *const BasisuFile,
+    image_index: u32,
+    level_index: u32,
+    format: u32,
+    size: *mut usize,
+) -> c_int {
+    let format = match output_format(format) {
+        Some(format) => format,
+        None => return BASISU_INVALID_ARGUMENT,
+    };
+    if size.is_null() {
+        return BASISU_INVALID_ARGUMENT;
+    }
+    status(
+        (*file)
+            .transcoder
+            .required_output_size(image_index, level_index, format)
+            .map(|needed| *size = needed),
+    )
+}
+
+/// Transcode the indicated `image_index` / `level_index` pair to `format`, writing it to the
+/// `output_len` bytes at `output`. [`basisu_required_output_size`] gives the size needed.
+///
+/// # Safety
+///
+/// `file` must be a live handle from [`basisu_open`] and `output` must point to `output_len`
+/// writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn basisu_transcode(
+    file: *const BasisuFile,
+    image_index: u32,
+    level_index: u32,
+    format: u32,
+    output: *mut u8,
+    output_len: usize,
+) -> c_int {
+    let format = match output_format(format) {
+        Some(format) => format,
+        None => return BASISU_INVALID_ARGUMENT,
+    };
+    if output.is_null() {
+        return BASISU_INVALID_ARGUMENT;
+    }
+    let output = slice::from_raw_parts_mut(output, output_len);
+    status(
+        (*file)
+            .transcoder
+            .transcode_image_level(image_index, level_index, output, format),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::ptr;
+
+    #[test]
+    fn raw_formats() {
+        for (raw, &format) in OutputFormat::ALL.iter().enumerate() {
+            assert_eq!(output_format(format as u32), Some(format), "{}", raw);
+        }
+        assert_eq!(output_format(7), None);
+        assert_eq!(output_format(u32::MAX), None);
+    }
+
+    #[test]
+    fn open_invalid_files() {
+        let mut file = ptr::null_mut();
+        unsafe {
+            assert_eq!(
+                basisu_open(ptr::null(), 0, &mut file),
+                BASISU_INVALID_ARGUMENT
+            );
+            let data = b"not a texture";
+            assert_eq!(
+                basisu_open(data.as_ptr(), data.len(), &mut file),
+                BASISU_INVALID_HEADER
+            );
+            assert!(file.is_null());
+            basisu_free(file);
+        }
+    }
+}
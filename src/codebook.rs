@@ -0,0 +1,64 @@
+//! User supplied ETC1S global selector codebooks.
+
+use core::convert::TryInto;
+
+use basisu_sys::{basist, shim};
+
+use crate::BasisError;
+
+/// A codebook of ETC1S selector patterns, used in place of the built-in global codebook.
+///
+/// ETC1S files encoded with the encoder's global selector palette refer to entries of this
+/// codebook by index, so they can only be transcoded with the same codebook they were encoded with.
+/// Share one codebook between transcoders and compressors by wrapping it in an `Arc`.
+pub struct SelectorCodebook(*mut basist::etc1_global_selector_codebook);
+
+// The codebook is never modified after construction.
+unsafe impl Send for SelectorCodebook {}
+unsafe impl Sync for SelectorCodebook {}
+
+impl SelectorCodebook {
+    /// Build a codebook from its packed entries, in the same format as the built-in
+    /// `g_global_selector_cb` table.
+    pub fn new(entries: &[u32]) -> Result<Self, BasisError> {
+        let len: u32 = entries
+            .len()
+            .try_into()
+            .map_err(|_| BasisError::InvalidArgument)?;
+        if len == 0 {
+            return Err(BasisError::InvalidArgument);
+        }
+        Ok(Self(unsafe {
+            shim::new_selector_codebook(len, entries.as_ptr())
+        }))
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const basist::etc1_global_selector_codebook {
+        self.0
+    }
+}
+
+impl Drop for SelectorCodebook {
+    fn drop(&mut self) {
+        unsafe { shim::delete_selector_codebook(self.0) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_empty_codebook() {
+        assert!(matches!(
+            SelectorCodebook::new(&[]),
+            Err(BasisError::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn codebook_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SelectorCodebook>();
+    }
+}
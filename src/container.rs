@@ -0,0 +1,77 @@
+//! Writers that store transcoded data in standard texture containers, for tools that convert
+//! .basis and .ktx2 files once ahead of time.
+
+use alloc::vec::Vec;
+
+use crate::{BasisError, OutputFormat, TextureType, Transcoder};
+
+pub mod dds;
+pub mod ktx;
+pub mod ktx2;
+
+/// The images of a file after transcoding to a single format.
+pub(crate) struct Surfaces {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// Number of array layers, counting each cubemap as one layer.
+    pub(crate) layers: u32,
+    /// Either 1 or 6.
+    pub(crate) faces: u32,
+    /// Transcoded data, indexed by image and then by mip level. Images are in the order
+    /// documented on [`Transcoder`], so face `f` of layer `l` is image `l * faces + f`.
+    pub(crate) levels: Vec<Vec<Vec<u8>>>,
+}
+
+impl Surfaces {
+    /// Transcode every level of every image in `file` to `format`.
+    ///
+    /// Containers need all images to share their dimensions and level count, so files where they
+    /// differ are rejected with [`BasisError::InvalidArgument`], as are videos, whose frames can
+    /// only be decoded in order with a [`VideoDecoder`](crate::VideoDecoder). Volume slices become
+    /// array layers.
+    pub(crate) fn transcode(file: &Transcoder, format: OutputFormat) -> Result<Self, BasisError> {
+        let texture_type = file.get_texture_type();
+        let total_images = file.get_total_images();
+        if texture_type == TextureType::VideoFrames || total_images == 0 {
+            return Err(BasisError::InvalidArgument);
+        }
+        if !file.can_transcode(format) {
+            return Err(BasisError::UnsupportedFormat(format));
+        }
+        let faces = if texture_type == TextureType::CubemapArray {
+            6
+        } else {
+            1
+        };
+
+        let (width, height) = file.level_dimensions(0, 0)?;
+        let level_count = file.get_total_image_levels(0);
+        let mut levels = Vec::with_capacity(total_images as usize);
+        for image_index in 0..total_images {
+            if file.get_total_image_levels(image_index) != level_count
+                || file.level_dimensions(image_index, 0)? != (width, height)
+            {
+                return Err(BasisError::InvalidArgument);
+            }
+            levels.push(
+                (0..level_count)
+                    .map(|level_index| {
+                        file.transcode_image_level_to_vec(image_index, level_index, format)
+                    })
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+
+        Ok(Self {
+            width,
+            height,
+            layers: total_images / faces,
+            faces,
+            levels,
+        })
+    }
+
+    pub(crate) fn level_count(&self) -> u32 {
+        self.levels[0].len() as u32
+    }
+}
@@ -0,0 +1,208 @@
+//! DirectDraw Surface files, the usual container for BC compressed textures on Windows.
+//!
+//! Files are always written with the DX10 header extension, which is needed for BC7, sRGB formats
+//! and texture arrays.
+
+use alloc::vec::Vec;
+
+use super::Surfaces;
+use crate::{BasisError, OutputFormat, Transcoder};
+
+const DDSD_CAPS: u32 = 0x1;
+const DDSD_HEIGHT: u32 = 0x2;
+const DDSD_WIDTH: u32 = 0x4;
+const DDSD_PITCH: u32 = 0x8;
+const DDSD_PIXELFORMAT: u32 = 0x1000;
+const DDSD_MIPMAPCOUNT: u32 = 0x2_0000;
+const DDSD_LINEARSIZE: u32 = 0x8_0000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDSCAPS_COMPLEX: u32 = 0x8;
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+const DDSCAPS_MIPMAP: u32 = 0x40_0000;
+const DDSCAPS2_CUBEMAP_ALLFACES: u32 = 0xfe00;
+const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+const D3D10_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+
+/// Size of the magic number, the header and the DX10 header extension.
+const HEADER_SIZE: usize = 4 + 124 + 20;
+
+/// The `DXGI_FORMAT` value for `format`, using the sRGB variant if `srgb` is set and one exists.
+/// Returns `None` for formats DDS files can't hold.
+pub fn dxgi_format(format: OutputFormat, srgb: bool) -> Option<u32> {
+    Some(match (format, srgb) {
+        (OutputFormat::BC1_RGB, false) => 71,
+        (OutputFormat::BC1_RGB, true) => 72,
+        (OutputFormat::BC3_RGBA, false) => 77,
+        (OutputFormat::BC3_RGBA, true) => 78,
+        (OutputFormat::BC4_R, _) => 80,
+        (OutputFormat::BC5_RG, _) => 83,
+        (OutputFormat::BC7_RGBA, false) => 98,
+        (OutputFormat::BC7_RGBA, true) => 99,
+        (OutputFormat::RGBA32, false) => 28,
+        (OutputFormat::RGBA32, true) => 29,
+        _ => return None,
+    })
+}
+
+/// Transcode every image and mip level of `file` to `format` and store them in a DDS file.
+///
+/// `format` must be one of the BC formats or [`OutputFormat::RGBA32`]. Set `srgb` for color
+/// textures so GPUs decode them to linear light. Cubemaps are written as cubemap arrays and every
+/// other texture type as a 2D array; see [`Transcoder`] for how images are numbered.
+pub fn transcode_to_dds(
+    file: &Transcoder,
+    format: OutputFormat,
+    srgb: bool,
+) -> Result<Vec<u8>, BasisError> {
+    let dxgi_format = dxgi_format(format, srgb).ok_or(BasisError::UnsupportedFormat(format))?;
+    let surfaces = Surfaces::transcode(file, format)?;
+    Ok(write(&surfaces, format, dxgi_format))
+}
+
+fn write(surfaces: &Surfaces, format: OutputFormat, dxgi_format: u32) -> Vec<u8> {
+    let level_count = surfaces.level_count();
+    let mut flags = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT | DDSD_MIPMAPCOUNT;
+    let pitch_or_linear_size = match format.bytes_per_pixel() {
+        Some(bytes_per_pixel) => {
+            flags |= DDSD_PITCH;
+            surfaces.width * bytes_per_pixel
+        }
+        None => {
+            flags |= DDSD_LINEARSIZE;
+            surfaces.levels[0][0].len() as u32
+        }
+    };
+    let mut caps = DDSCAPS_TEXTURE;
+    if level_count > 1 {
+        caps |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+    }
+    let (caps2, misc_flag) = if surfaces.faces == 6 {
+        caps |= DDSCAPS_COMPLEX;
+        (DDSCAPS2_CUBEMAP_ALLFACES, D3D10_RESOURCE_MISC_TEXTURECUBE)
+    } else {
+        (0, 0)
+    };
+
+    let data_size: usize = surfaces.levels.iter().flatten().map(Vec::len).sum();
+    let mut out = Vec::with_capacity(HEADER_SIZE + data_size);
+    out.extend_from_slice(b"DDS ");
+    let header = [
+        124,
+        flags,
+        surfaces.height,
+        surfaces.width,
+        pitch_or_linear_size,
+        0,
+        level_count,
+    ];
+    for v in &header {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    out.extend_from_slice(&[0; 11 * 4]);
+    let pixel_format = [32, DDPF_FOURCC, u32::from_le_bytes(*b"DX10"), 0, 0, 0, 0, 0];
+    let caps = [caps, caps2, 0, 0, 0];
+    let dx10 = [
+        dxgi_format,
+        D3D10_RESOURCE_DIMENSION_TEXTURE2D,
+        misc_flag,
+        surfaces.layers,
+        0,
+    ];
+    for v in pixel_format.iter().chain(&caps).chain(&dx10) {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    debug_assert_eq!(out.len(), HEADER_SIZE);
+
+    // Layers, then faces, then mip levels, which is also the order of the images.
+    for level in surfaces.levels.iter().flatten() {
+        out.extend_from_slice(level);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(dxgi_format(OutputFormat::BC7_RGBA, true), Some(99));
+        assert_eq!(dxgi_format(OutputFormat::BC4_R, true), Some(80));
+        assert_eq!(dxgi_format(OutputFormat::ETC1_RGB, false), None);
+    }
+
+    #[test]
+    fn write_header() {
+        let surfaces = Surfaces {
+            width: 8,
+            height: 4,
+            layers: 1,
+            faces: 1,
+            levels: vec![vec![vec![1; 16], vec![2; 8], vec![3; 8]]],
+        };
+        let dds = write(&surfaces, OutputFormat::BC1_RGB, 71);
+        assert_eq!(dds.len(), HEADER_SIZE + 32);
+        assert_eq!(&dds[..4], b"DDS ");
+        assert_eq!(read_u32(&dds, 12), 4);
+        assert_eq!(read_u32(&dds, 16), 8);
+        assert_eq!(read_u32(&dds, 20), 16);
+        assert_eq!(read_u32(&dds, 28), 3);
+        assert_eq!(&dds[84..88], b"DX10");
+        assert_eq!(
+            read_u32(&dds, 108),
+            DDSCAPS_TEXTURE | DDSCAPS_COMPLEX | DDSCAPS_MIPMAP
+        );
+        assert_eq!(read_u32(&dds, 128), 71);
+        assert_eq!(read_u32(&dds, 136), 0);
+        assert_eq!(read_u32(&dds, 140), 1);
+        assert_eq!(dds[HEADER_SIZE..HEADER_SIZE + 16], [1; 16]);
+        assert_eq!(dds[HEADER_SIZE + 16], 2);
+    }
+
+    #[test]
+    fn write_cubemap_header() {
+        let surfaces = Surfaces {
+            width: 4,
+            height: 4,
+            layers: 2,
+            faces: 6,
+            levels: vec![vec![vec![0; 64]]; 12],
+        };
+        let dds = write(&surfaces, OutputFormat::RGBA32, 28);
+        assert_eq!(dds.len(), HEADER_SIZE + 12 * 64);
+        assert_eq!(read_u32(&dds, 8) & DDSD_PITCH, DDSD_PITCH);
+        assert_eq!(read_u32(&dds, 20), 16);
+        assert_eq!(read_u32(&dds, 112), DDSCAPS2_CUBEMAP_ALLFACES);
+        assert_eq!(read_u32(&dds, 136), D3D10_RESOURCE_MISC_TEXTURECUBE);
+        assert_eq!(read_u32(&dds, 140), 2);
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_to_dds() {
+        use crate::{Compressor, CompressorParams};
+
+        let rgba = [128; 8 * 8 * 4];
+        let mut compressor = Compressor::new(CompressorParams::new().mip_gen(true));
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let file = Transcoder::open(&data).unwrap();
+        let dds = transcode_to_dds(&file, OutputFormat::BC7_RGBA, true).unwrap();
+        assert_eq!(read_u32(&dds, 28), 4);
+        assert_eq!(dds.len(), HEADER_SIZE + (4 + 1 + 1 + 1) * 16);
+        assert_eq!(
+            transcode_to_dds(&file, OutputFormat::ETC1_RGB, false),
+            Err(BasisError::UnsupportedFormat(OutputFormat::ETC1_RGB))
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! KTX 1 files, which many OpenGL and mobile pipelines still load.
+
+use alloc::vec::Vec;
+
+use super::Surfaces;
+use crate::{BasisError, OutputFormat, Transcoder};
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const ENDIANNESS: u32 = 0x0403_0201;
+
+const GL_UNSIGNED_BYTE: u32 = 0x1401;
+const GL_RED: u32 = 0x1903;
+const GL_RGB: u32 = 0x1907;
+const GL_RGBA: u32 = 0x1908;
+const GL_RG: u32 = 0x8227;
+
+/// The OpenGL enums describing how a format is stored.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct GlFormat {
+    /// `glType` and `glFormat`, zero for compressed formats.
+    ty: u32,
+    format: u32,
+    internal_format: u32,
+    base_internal_format: u32,
+}
+
+impl GlFormat {
+    fn compressed(internal_format: u32, base_internal_format: u32) -> Self {
+        Self {
+            ty: 0,
+            format: 0,
+            internal_format,
+            base_internal_format,
+        }
+    }
+
+    fn new(format: OutputFormat, srgb: bool) -> Option<Self> {
+        let pick = |linear, srgb_format| if srgb { srgb_format } else { linear };
+        Some(match format {
+            OutputFormat::BC1_RGB => Self::compressed(pick(0x83F0, 0x8C4C), GL_RGB),
+            OutputFormat::BC3_RGBA => Self::compressed(pick(0x83F3, 0x8C4F), GL_RGBA),
+            OutputFormat::BC4_R => Self::compressed(0x8DBB, GL_RED),
+            OutputFormat::BC5_RG => Self::compressed(0x8DBD, GL_RG),
+            OutputFormat::BC7_RGBA => Self::compressed(pick(0x8E8C, 0x8E8D), GL_RGBA),
+            // ETC2 is a superset of ETC1 and the only one of the two with an sRGB variant.
+            OutputFormat::ETC1_RGB => Self::compressed(pick(0x8D64, 0x9275), GL_RGB),
+            OutputFormat::ETC2_RGBA => Self::compressed(pick(0x9278, 0x9279), GL_RGBA),
+            OutputFormat::ETC2_EAC_R11 => Self::compressed(0x9270, GL_RED),
+            OutputFormat::ETC2_EAC_RG11 => Self::compressed(0x9272, GL_RG),
+            OutputFormat::ASTC_4x4_RGBA => Self::compressed(pick(0x93B0, 0x93D0), GL_RGBA),
+            OutputFormat::PVRTC1_4_RGB => Self::compressed(pick(0x8C00, 0x8A55), GL_RGB),
+            OutputFormat::PVRTC1_4_RGBA => Self::compressed(pick(0x8C02, 0x8A57), GL_RGBA),
+            // PVRTC2 blocks carry their own opacity, so both variants use the RGBA format.
+            OutputFormat::PVRTC2_4_RGB | OutputFormat::PVRTC2_4_RGBA => {
+                Self::compressed(pick(0x9138, 0x93F1), GL_RGBA)
+            }
+            OutputFormat::ATC_RGB if !srgb => Self::compressed(0x8C92, GL_RGB),
+            OutputFormat::ATC_RGBA if !srgb => Self::compressed(0x87EE, GL_RGBA),
+            OutputFormat::RGBA32 => Self {
+                ty: GL_UNSIGNED_BYTE,
+                format: GL_RGBA,
+                internal_format: pick(0x8058, 0x8C43),
+                base_internal_format: GL_RGBA,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// The `glInternalFormat` value for `format`, using the sRGB variant if `srgb` is set. Returns
+/// `None` for formats OpenGL has no enum for, and for [ATC](OutputFormat::ATC_RGB) with `srgb`,
+/// which has no sRGB variant.
+pub fn gl_internal_format(format: OutputFormat, srgb: bool) -> Option<u32> {
+    GlFormat::new(format, srgb).map(|gl| gl.internal_format)
+}
+
+/// Transcode every image and mip level of `file` to `format` and store them in a KTX 1 file.
+///
+/// Set `srgb` for color textures so GPUs decode them to linear light. Files with several images
+/// become array textures, with cubemaps written as cubemap arrays; see [`Transcoder`] for how
+/// images are numbered.
+pub fn transcode_to_ktx(
+    file: &Transcoder,
+    format: OutputFormat,
+    srgb: bool,
+) -> Result<Vec<u8>, BasisError> {
+    let gl = GlFormat::new(format, srgb).ok_or(BasisError::UnsupportedFormat(format))?;
+    let surfaces = Surfaces::transcode(file, format)?;
+    Ok(write(&surfaces, gl))
+}
+
+fn pad4(out: &mut Vec<u8>) {
+    out.resize((out.len() + 3) & !3, 0);
+}
+
+fn write(surfaces: &Surfaces, gl: GlFormat) -> Vec<u8> {
+    let level_count = surfaces.level_count();
+    let array_elements = if surfaces.layers > 1 {
+        surfaces.layers
+    } else {
+        0
+    };
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&IDENTIFIER);
+    let header = [
+        ENDIANNESS,
+        gl.ty,
+        1,
+        gl.format,
+        gl.internal_format,
+        gl.base_internal_format,
+        surfaces.width,
+        surfaces.height,
+        0,
+        array_elements,
+        surfaces.faces,
+        level_count,
+        0,
+    ];
+    for v in &header {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+
+    for level_index in 0..level_count as usize {
+        let images = surfaces.levels.iter().map(|levels| &levels[level_index]);
+        // Non-array cubemaps give the size of a single face, everything else the whole level.
+        let image_size: usize = if array_elements == 0 && surfaces.faces == 6 {
+            surfaces.levels[0][level_index].len()
+        } else {
+            images.clone().map(Vec::len).sum()
+        };
+        out.extend_from_slice(&(image_size as u32).to_le_bytes());
+        for image in images {
+            out.extend_from_slice(image);
+            pad4(&mut out);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            gl_internal_format(OutputFormat::ETC1_RGB, false),
+            Some(0x8D64)
+        );
+        assert_eq!(
+            gl_internal_format(OutputFormat::ASTC_4x4_RGBA, true),
+            Some(0x93D0)
+        );
+        assert_eq!(
+            gl_internal_format(OutputFormat::PVRTC2_4_RGB, true),
+            Some(0x93F1)
+        );
+        assert_eq!(gl_internal_format(OutputFormat::ATC_RGB, true), None);
+        assert_eq!(gl_internal_format(OutputFormat::FXT1_RGB, false), None);
+    }
+
+    #[test]
+    fn write_header() {
+        let surfaces = Surfaces {
+            width: 8,
+            height: 4,
+            layers: 1,
+            faces: 1,
+            levels: vec![vec![vec![1; 16], vec![2; 8], vec![3; 8]]],
+        };
+        let gl = GlFormat::new(OutputFormat::ETC1_RGB, false).unwrap();
+        let ktx = write(&surfaces, gl);
+        assert_eq!(ktx[..12], IDENTIFIER);
+        assert_eq!(read_u32(&ktx, 12), ENDIANNESS);
+        assert_eq!(read_u32(&ktx, 16), 0);
+        assert_eq!(read_u32(&ktx, 28), 0x8D64);
+        assert_eq!(read_u32(&ktx, 32), GL_RGB);
+        assert_eq!(read_u32(&ktx, 36), 8);
+        assert_eq!(read_u32(&ktx, 40), 4);
+        assert_eq!(read_u32(&ktx, 48), 0);
+        assert_eq!(read_u32(&ktx, 52), 1);
+        assert_eq!(read_u32(&ktx, 56), 3);
+        assert_eq!(read_u32(&ktx, 64), 16);
+        assert_eq!(ktx.len(), 64 + 3 * 4 + 32);
+    }
+
+    #[test]
+    fn write_cubemap() {
+        let surfaces = Surfaces {
+            width: 1,
+            height: 1,
+            layers: 1,
+            faces: 6,
+            levels: vec![vec![vec![0; 4]]; 6],
+        };
+        let gl = GlFormat::new(OutputFormat::RGBA32, true).unwrap();
+        let ktx = write(&surfaces, gl);
+        assert_eq!(read_u32(&ktx, 16), GL_UNSIGNED_BYTE);
+        assert_eq!(read_u32(&ktx, 28), 0x8C43);
+        assert_eq!(read_u32(&ktx, 52), 6);
+        assert_eq!(read_u32(&ktx, 64), 4);
+        assert_eq!(ktx.len(), 64 + 4 + 6 * 4);
+    }
+
+    #[test]
+    fn pad_images() {
+        let surfaces = Surfaces {
+            width: 1,
+            height: 1,
+            layers: 2,
+            faces: 1,
+            levels: vec![vec![vec![9; 2]]; 2],
+        };
+        let gl = GlFormat::new(OutputFormat::ETC2_EAC_R11, false).unwrap();
+        let ktx = write(&surfaces, gl);
+        assert_eq!(read_u32(&ktx, 48), 2);
+        assert_eq!(read_u32(&ktx, 64), 4);
+        assert_eq!(ktx[68..], [9, 9, 0, 0, 9, 9, 0, 0]);
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_to_ktx() {
+        use crate::{Compressor, CompressorParams};
+
+        let rgba = [128; 8 * 8 * 4];
+        let mut compressor = Compressor::new(CompressorParams::new());
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let file = Transcoder::open(&data).unwrap();
+        let ktx = transcode_to_ktx(&file, OutputFormat::ETC1_RGB, false).unwrap();
+        assert_eq!(read_u32(&ktx, 64), 4 * 8);
+        assert_eq!(ktx.len(), 64 + 4 + 4 * 8);
+    }
+}
@@ -0,0 +1,303 @@
+//! .ktx2 files holding data already transcoded to a GPU format, which can be uploaded without
+//! running the transcoder at load time.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::Surfaces;
+use crate::ktx2::{
+    ColorModel, DataFormatDescriptor, Sample, TransferFunction, HEADER_SIZE, IDENTIFIER,
+    LEVEL_INDEX_ENTRY_SIZE,
+};
+use crate::{BasisError, OutputFormat, Transcoder};
+
+/// `KHR_DF_PRIMARIES_BT709`, the primaries of sRGB.
+const PRIMARIES_BT709: u8 = 1;
+/// Channel id of alpha in most color models.
+const CHANNEL_ALPHA: u8 = 15;
+
+/// The `VkFormat` value for `format`, using the sRGB variant if `srgb` is set and one exists.
+/// Returns `None` for formats Vulkan has no enum for.
+pub fn vk_format(format: OutputFormat, srgb: bool) -> Option<u32> {
+    let pick = |linear, srgb_format| if srgb { srgb_format } else { linear };
+    Some(match format {
+        OutputFormat::BC1_RGB => pick(131, 132),
+        OutputFormat::BC3_RGBA => pick(137, 138),
+        OutputFormat::BC4_R => 139,
+        OutputFormat::BC5_RG => 141,
+        OutputFormat::BC7_RGBA => pick(145, 146),
+        // ETC1 blocks are valid ETC2 blocks, and Vulkan only has the latter.
+        OutputFormat::ETC1_RGB => pick(147, 148),
+        OutputFormat::ETC2_RGBA => pick(151, 152),
+        OutputFormat::ETC2_EAC_R11 => 153,
+        OutputFormat::ETC2_EAC_RG11 => 155,
+        OutputFormat::ASTC_4x4_RGBA => pick(157, 158),
+        OutputFormat::PVRTC1_4_RGB | OutputFormat::PVRTC1_4_RGBA => {
+            pick(1_000_054_001, 1_000_054_005)
+        }
+        OutputFormat::PVRTC2_4_RGB | OutputFormat::PVRTC2_4_RGBA => {
+            pick(1_000_054_003, 1_000_054_007)
+        }
+        OutputFormat::RGBA32 => pick(37, 43),
+        _ => return None,
+    })
+}
+
+/// The data format descriptor of a .ktx2 file holding `format` data, as required alongside
+/// [`vk_format`]. Returns `None` for the same formats.
+pub fn data_format_descriptor(format: OutputFormat, srgb: bool) -> Option<DataFormatDescriptor> {
+    vk_format(format, srgb)?;
+
+    // Block formats have one sample per 64 bit half of the block, covering the full range.
+    let block_sample = |index: u16, channel_id: u8| Sample {
+        bit_offset: index * 64,
+        bit_length: 64,
+        channel_id,
+        // Alpha is never sRGB encoded.
+        linear: srgb && channel_id == CHANNEL_ALPHA,
+        exponent: false,
+        signed: false,
+        float: false,
+        lower: 0,
+        upper: u32::MAX,
+    };
+    let (model, bits, channels): (u8, u16, &[u8]) = match format {
+        OutputFormat::BC1_RGB => (128, 64, &[0]),
+        OutputFormat::BC3_RGBA => (130, 128, &[CHANNEL_ALPHA, 0]),
+        OutputFormat::BC4_R => (131, 64, &[0]),
+        OutputFormat::BC5_RG => (132, 128, &[0, 1]),
+        OutputFormat::BC7_RGBA => (134, 128, &[0]),
+        // ETC1 is a subset of ETC2, which is how KTX2 describes it.
+        OutputFormat::ETC1_RGB => (161, 64, &[2]),
+        OutputFormat::ETC2_RGBA => (161, 128, &[CHANNEL_ALPHA, 2]),
+        OutputFormat::ETC2_EAC_R11 => (161, 64, &[0]),
+        OutputFormat::ETC2_EAC_RG11 => (161, 128, &[0, 1]),
+        OutputFormat::ASTC_4x4_RGBA => (162, 128, &[0]),
+        OutputFormat::PVRTC1_4_RGB | OutputFormat::PVRTC1_4_RGBA => (164, 64, &[0]),
+        OutputFormat::PVRTC2_4_RGB | OutputFormat::PVRTC2_4_RGBA => (165, 64, &[0]),
+        _ => (1, 32, &[0, 1, 2, CHANNEL_ALPHA]),
+    };
+
+    let (block_dimensions, samples) = if model == 1 {
+        // RGBA32 has one byte per channel and 1x1 "blocks".
+        let samples = channels
+            .iter()
+            .enumerate()
+            .map(|(i, &channel_id)| Sample {
+                bit_offset: i as u16 * 8,
+                bit_length: 8,
+                lower: 0,
+                upper: 255,
+                ..block_sample(0, channel_id)
+            })
+            .collect();
+        ([1, 1, 1, 1], samples)
+    } else if bits == 128 && channels.len() == 1 {
+        // BC7 and ASTC describe the whole block with a single sample.
+        let sample = Sample {
+            bit_length: 128,
+            ..block_sample(0, channels[0])
+        };
+        ([4, 4, 1, 1], vec![sample])
+    } else {
+        let samples = (0..)
+            .zip(channels)
+            .map(|(i, &channel_id)| block_sample(i, channel_id))
+            .collect();
+        ([4, 4, 1, 1], samples)
+    };
+
+    let mut bytes_planes = [0; 8];
+    bytes_planes[0] = (bits / 8) as u8;
+    Some(DataFormatDescriptor {
+        color_model: ColorModel::from_raw(model),
+        color_primaries: PRIMARIES_BT709,
+        transfer_function: if srgb {
+            TransferFunction::Srgb
+        } else {
+            TransferFunction::Linear
+        },
+        alpha_premultiplied: false,
+        texel_block_dimensions: block_dimensions,
+        bytes_planes,
+        samples,
+    })
+}
+
+/// Transcode every image and mip level of `file` to `format` and store them in a .ktx2 file
+/// without supercompression.
+///
+/// Set `srgb` for color textures so GPUs decode them to linear light. Files with several images
+/// become array textures, with cubemaps written as cubemap arrays; see [`Transcoder`] for how
+/// images are numbered.
+pub fn transcode_to_ktx2(
+    file: &Transcoder,
+    format: OutputFormat,
+    srgb: bool,
+) -> Result<Vec<u8>, BasisError> {
+    let vk_format = vk_format(format, srgb).ok_or(BasisError::UnsupportedFormat(format))?;
+    let dfd = data_format_descriptor(format, srgb).ok_or(BasisError::UnsupportedFormat(format))?;
+    let surfaces = Surfaces::transcode(file, format)?;
+    Ok(write(&surfaces, vk_format, &dfd))
+}
+
+fn write(surfaces: &Surfaces, vk_format: u32, descriptor: &DataFormatDescriptor) -> Vec<u8> {
+    let level_count = surfaces.level_count() as usize;
+    let dfd = descriptor.to_bytes();
+    let mut kvd = Vec::new();
+    let writer = concat!("KTXwriter\0basisu ", env!("CARGO_PKG_VERSION"), "\0");
+    kvd.extend_from_slice(&(writer.len() as u32).to_le_bytes());
+    kvd.extend_from_slice(writer.as_bytes());
+    kvd.resize((kvd.len() + 3) & !3, 0);
+
+    let dfd_offset = HEADER_SIZE + level_count * LEVEL_INDEX_ENTRY_SIZE;
+    let kvd_offset = dfd_offset + dfd.len();
+    // Levels start at a multiple of the block size, which is 4, 8 or 16 bytes here.
+    let alignment = (descriptor.bytes_planes[0] as usize).max(4);
+    let layer_count = if surfaces.layers > 1 {
+        surfaces.layers
+    } else {
+        0
+    };
+
+    let mut out = IDENTIFIER.to_vec();
+    let header = [
+        vk_format,
+        1,
+        surfaces.width,
+        surfaces.height,
+        0,
+        layer_count,
+        surfaces.faces,
+        level_count as u32,
+        0,
+        dfd_offset as u32,
+        dfd.len() as u32,
+        kvd_offset as u32,
+        kvd.len() as u32,
+    ];
+    for v in &header {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    out.extend_from_slice(&[0; 16]);
+    debug_assert_eq!(out.len(), HEADER_SIZE);
+
+    // The level index lists the largest level first, but the data is stored smallest first.
+    let mut level_index = vec![(0, 0); level_count];
+    let mut data = Vec::new();
+    let mut offset = kvd_offset + kvd.len();
+    for level in (0..level_count).rev() {
+        let padding = (alignment - offset % alignment) % alignment;
+        data.resize(data.len() + padding, 0);
+        offset += padding;
+
+        let start = data.len();
+        for image in &surfaces.levels {
+            data.extend_from_slice(&image[level]);
+        }
+        let length = data.len() - start;
+        level_index[level] = (offset, length);
+        offset += length;
+    }
+    for &(offset, length) in &level_index {
+        for v in &[offset as u64, length as u64, length as u64] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    out.extend_from_slice(&dfd);
+    out.extend_from_slice(&kvd);
+    out.extend_from_slice(&data);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ktx2::ParsedHeader;
+
+    #[test]
+    fn descriptors() {
+        let dfd = data_format_descriptor(OutputFormat::BC3_RGBA, true).unwrap();
+        assert_eq!(dfd.color_model, ColorModel::Other(130));
+        assert!(dfd.is_srgb());
+        assert_eq!(dfd.bytes_planes[0], 16);
+        assert_eq!(dfd.samples.len(), 2);
+        assert!(dfd.samples[0].linear && !dfd.samples[1].linear);
+        assert_eq!(dfd.samples[1].bit_offset, 64);
+
+        let dfd = data_format_descriptor(OutputFormat::ASTC_4x4_RGBA, false).unwrap();
+        assert_eq!(dfd.samples.len(), 1);
+        assert_eq!(dfd.samples[0].bit_length, 128);
+
+        let dfd = data_format_descriptor(OutputFormat::RGBA32, false).unwrap();
+        assert_eq!(dfd.color_model, ColorModel::Rgbsda);
+        assert_eq!(dfd.texel_block_dimensions, [1, 1, 1, 1]);
+        assert_eq!(dfd.samples[3].bit_offset, 24);
+        assert_eq!(dfd.samples[3].upper, 255);
+
+        assert_eq!(vk_format(OutputFormat::ATC_RGB, false), None);
+        assert_eq!(data_format_descriptor(OutputFormat::ATC_RGB, false), None);
+    }
+
+    #[test]
+    fn write_file() {
+        let surfaces = Surfaces {
+            width: 8,
+            height: 4,
+            layers: 2,
+            faces: 1,
+            levels: vec![vec![vec![1; 16], vec![2; 8], vec![3; 8]]; 2],
+        };
+        let dfd = data_format_descriptor(OutputFormat::ETC1_RGB, true).unwrap();
+        assert_eq!(dfd.color_model, ColorModel::Other(161));
+        assert_eq!(dfd.samples[0].channel_id, 2);
+        let ktx2 = write(&surfaces, 148, &dfd);
+
+        let parsed = ParsedHeader::parse(&ktx2).unwrap();
+        assert_eq!(parsed.header.vk_format, 148);
+        assert_eq!(
+            (parsed.header.pixel_width, parsed.header.pixel_height),
+            (8, 4)
+        );
+        assert_eq!(
+            (
+                parsed.total_layers(),
+                parsed.total_faces(),
+                parsed.total_levels()
+            ),
+            (2, 1, 3)
+        );
+        assert_eq!(parsed.dfd(&ktx2), Some(dfd));
+        assert_eq!(
+            parsed.key_values(&ktx2).next().map(|(key, _)| key),
+            Some("KTXwriter")
+        );
+
+        let level = |i: usize| {
+            let index = parsed.levels[i];
+            assert_eq!(index.byte_offset % 8, 0);
+            &ktx2[index.byte_offset as usize..][..index.byte_length as usize]
+        };
+        assert_eq!(level(0), [1; 32]);
+        assert_eq!(level(2), [3; 16]);
+        assert!(parsed.levels[2].byte_offset < parsed.levels[0].byte_offset);
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_to_ktx2() {
+        use crate::{Compressor, CompressorParams};
+
+        let rgba = [128; 8 * 8 * 4];
+        let mut compressor = Compressor::new(CompressorParams::new().mip_gen(true));
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let file = Transcoder::open(&data).unwrap();
+        let ktx2 = transcode_to_ktx2(&file, OutputFormat::ASTC_4x4_RGBA, true).unwrap();
+        let parsed = ParsedHeader::parse(&ktx2).unwrap();
+        assert_eq!(parsed.header.vk_format, 158);
+        assert_eq!(parsed.total_levels(), 4);
+        assert_eq!(parsed.levels[0].byte_length, 4 * 16);
+    }
+}
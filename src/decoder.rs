@@ -0,0 +1,104 @@
+//! Decoding of .basis and .ktx2 files through the `image` crate, available with the `image`
+//! feature.
+
+use std::io::{Cursor, Read};
+
+use image::error::{DecodingError, ImageFormatHint};
+use image::{ColorType, ImageDecoder, ImageError, ImageResult};
+
+use crate::{BasisError, OutputFormat, Transcoder};
+
+/// An [`ImageDecoder`] for .basis and .ktx2 files, which transcodes one mip level of one image to
+/// RGBA8.
+///
+/// `image::DynamicImage::from_decoder(BasisDecoder::new(reader)?)` opens a file like any other
+/// image format.
+pub struct BasisDecoder {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl BasisDecoder {
+    /// Read a whole .basis or .ktx2 file from `reader` and decode the base level of its first
+    /// image.
+    pub fn new<R: Read>(mut reader: R) -> ImageResult<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Self::from_bytes(&data)
+    }
+
+    /// Decode the base level of the first image of a .basis or .ktx2 file.
+    pub fn from_bytes(data: &[u8]) -> ImageResult<Self> {
+        Self::with_image_level(data, 0, 0)
+    }
+
+    /// Decode the indicated `image_index` / `level_index` pair of a .basis or .ktx2 file. Images
+    /// are numbered as by [`Transcoder`].
+    pub fn with_image_level(data: &[u8], image_index: u32, level_index: u32) -> ImageResult<Self> {
+        let decode = || -> Result<Self, BasisError> {
+            let file = Transcoder::open(data)?;
+            let (width, height) = file.level_dimensions(image_index, level_index)?;
+            let rgba =
+                file.transcode_image_level_to_vec(image_index, level_index, OutputFormat::RGBA32)?;
+            Ok(Self {
+                width,
+                height,
+                rgba,
+            })
+        };
+        decode().map_err(|error| {
+            ImageError::Decoding(DecodingError::new(
+                ImageFormatHint::Name("Basis Universal".to_string()),
+                error,
+            ))
+        })
+    }
+}
+
+impl<'a> ImageDecoder<'a> for BasisDecoder {
+    type Reader = Cursor<Vec<u8>>;
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn color_type(&self) -> ColorType {
+        ColorType::Rgba8
+    }
+
+    fn into_reader(self) -> ImageResult<Self::Reader> {
+        Ok(Cursor::new(self.rgba))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_unknown_files() {
+        match BasisDecoder::from_bytes(b"\x89PNG\r\n\x1a\n") {
+            Err(ImageError::Decoding(error)) => assert_eq!(
+                error.format_hint(),
+                ImageFormatHint::Name("Basis Universal".to_string())
+            ),
+            _ => panic!("expected a decoding error"),
+        }
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_and_decode() {
+        use crate::{Compressor, CompressorParams};
+        use image::DynamicImage;
+
+        let rgba = [255; 8 * 4 * 4];
+        let mut compressor = Compressor::new(CompressorParams::new());
+        compressor.add_image_rgba(&rgba, 8, 4).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let image = DynamicImage::from_decoder(BasisDecoder::new(&data[..]).unwrap()).unwrap();
+        assert_eq!(image.to_rgba8().dimensions(), (8, 4));
+    }
+}
@@ -0,0 +1,1505 @@
+//! Encoding of .basis and .ktx2 files, available with the `encoder` feature.
+//!
+//! Source images and settings are collected in Rust and only handed to the C++
+//! `basis_compressor` for the duration of [`Compressor::compress`].
+
+use std::borrow::Cow;
+use std::fmt;
+use std::slice;
+use std::sync::Arc;
+
+use basisu_sys::{basist, shim};
+#[cfg(feature = "image")]
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+use crate::ktx2::SupercompressionScheme;
+use crate::{global_codebook, init_encoder, BasisError, Container, SelectorCodebook, TextureType};
+
+/// The stage at which encoding failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EncodeError {
+    /// The source images could not be read.
+    ReadingSourceImages,
+    /// The compressor rejected its parameters.
+    Validating,
+    /// UASTC encoding failed.
+    EncodeUastc,
+    /// The ETC1S front end failed.
+    FrontEnd,
+    /// Extracting the ETC1S front end data failed.
+    FrontEndExtract,
+    /// The ETC1S back end failed.
+    Backend,
+    /// Assembling the .basis file failed.
+    CreateBasisFile,
+    /// Writing the output failed.
+    WritingOutput,
+    /// UASTC rate-distortion optimization failed.
+    UastcRdoPostProcess,
+    /// Assembling the .ktx2 file failed.
+    CreateKtx2File,
+    /// The compressor reported an error code this crate doesn't know.
+    Unknown(i32),
+}
+
+impl EncodeError {
+    /// Map a `basis_compressor::error_code` to an error, or `None` on success.
+    fn from_raw(code: i32) -> Option<Self> {
+        Some(match code {
+            0 => return None,
+            1 => EncodeError::ReadingSourceImages,
+            2 => EncodeError::Validating,
+            3 => EncodeError::EncodeUastc,
+            4 => EncodeError::FrontEnd,
+            5 => EncodeError::FrontEndExtract,
+            6 => EncodeError::Backend,
+            7 => EncodeError::CreateBasisFile,
+            8 => EncodeError::WritingOutput,
+            9 => EncodeError::UastcRdoPostProcess,
+            10 => EncodeError::CreateKtx2File,
+            _ => EncodeError::Unknown(code),
+        })
+    }
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodeError::ReadingSourceImages => write!(f, "failed to read source images"),
+            EncodeError::Validating => write!(f, "invalid compressor parameters"),
+            EncodeError::EncodeUastc => write!(f, "UASTC encoding failed"),
+            EncodeError::FrontEnd => write!(f, "ETC1S front end failed"),
+            EncodeError::FrontEndExtract => write!(f, "ETC1S front end data extraction failed"),
+            EncodeError::Backend => write!(f, "ETC1S back end failed"),
+            EncodeError::CreateBasisFile => write!(f, "failed to create .basis file"),
+            EncodeError::WritingOutput => write!(f, "failed to write output"),
+            EncodeError::UastcRdoPostProcess => write!(f, "UASTC RDO post-processing failed"),
+            EncodeError::CreateKtx2File => write!(f, "failed to create .ktx2 file"),
+            EncodeError::Unknown(code) => write!(f, "unknown compressor error {}", code),
+        }
+    }
+}
+
+/// How much effort the UASTC encoder spends searching for the best encoding of each block.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum UastcLevel {
+    /// Level 0, the fastest and lowest quality.
+    Fastest = 0,
+    /// Level 1.
+    Faster = 1,
+    /// Level 2, the default.
+    #[default]
+    Default = 2,
+    /// Level 3.
+    Slower = 3,
+    /// Level 4, the slowest and highest quality.
+    VerySlow = 4,
+}
+
+/// Compression used for the encoded images.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// ETC1S, which gives small files at moderate quality. Its quality is set with
+    /// [`CompressorParams::quality_level`].
+    Etc1s,
+    /// UASTC, which gives much higher quality files that are several times larger.
+    Uastc(UastcLevel),
+}
+
+/// Whether the encoded file gets an alpha channel.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AlphaMode {
+    /// Keep alpha only if some source pixel isn't fully opaque.
+    #[default]
+    Detect,
+    /// Always keep alpha, even if every source pixel is opaque.
+    Force,
+    /// Ignore the source alpha and encode the images as opaque.
+    Opaque,
+}
+
+/// How the color channels of the source images are encoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// sRGB encoded colors, as in most color textures.
+    Srgb,
+    /// Linear data, like normal, roughness or occlusion maps.
+    Linear,
+}
+
+/// Filter kernel used to downsample the source images when generating mipmaps.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MipFilter {
+    /// Box filter.
+    Box,
+    /// Tent (triangle) filter.
+    Tent,
+    /// Bell filter.
+    Bell,
+    /// Cubic B-spline filter.
+    BSpline,
+    /// Mitchell-Netravali filter.
+    Mitchell,
+    /// Blackman filter.
+    Blackman,
+    /// Lanczos filter with 3 lobes.
+    Lanczos3,
+    /// Lanczos filter with 4 lobes.
+    Lanczos4,
+    /// Lanczos filter with 6 lobes.
+    Lanczos6,
+    /// Lanczos filter with 12 lobes.
+    Lanczos12,
+    /// Kaiser filter, the default.
+    #[default]
+    Kaiser,
+    /// Gaussian filter.
+    Gaussian,
+    /// Catmull-Rom spline filter.
+    CatmullRom,
+    /// Interpolating quadratic filter.
+    QuadraticInterp,
+    /// Approximating quadratic filter.
+    QuadraticApprox,
+    /// Mix of the interpolating and approximating quadratic filters.
+    QuadraticMix,
+}
+
+impl MipFilter {
+    /// Name of the filter as understood by the C++ resampler, NUL terminated.
+    fn name(&self) -> &'static [u8] {
+        match self {
+            MipFilter::Box => b"box\0",
+            MipFilter::Tent => b"tent\0",
+            MipFilter::Bell => b"bell\0",
+            MipFilter::BSpline => b"b-spline\0",
+            MipFilter::Mitchell => b"mitchell\0",
+            MipFilter::Blackman => b"blackman\0",
+            MipFilter::Lanczos3 => b"lanczos3\0",
+            MipFilter::Lanczos4 => b"lanczos4\0",
+            MipFilter::Lanczos6 => b"lanczos6\0",
+            MipFilter::Lanczos12 => b"lanczos12\0",
+            MipFilter::Kaiser => b"kaiser\0",
+            MipFilter::Gaussian => b"gaussian\0",
+            MipFilter::CatmullRom => b"catmullrom\0",
+            MipFilter::QuadraticInterp => b"quadratic_interp\0",
+            MipFilter::QuadraticApprox => b"quadratic_approx\0",
+            MipFilter::QuadraticMix => b"quadratic_mix\0",
+        }
+    }
+}
+
+/// Rate-distortion optimization of UASTC data, which trades quality for blocks that compress
+/// better with a lossless compressor such as Zstandard.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UastcRdo {
+    /// Lambda of the optimization. Higher values give smaller files of lower quality. Must be
+    /// positive.
+    pub quality_scalar: f32,
+    /// Size in bytes of the dictionary the optimizer searches for matches, from 64 to 65536.
+    /// Larger dictionaries compress better but encode more slowly.
+    pub dict_size: u32,
+    /// How much more error is tolerated in smooth blocks, where artifacts are most visible, from
+    /// 1 to 300. A value of one gives smooth blocks no special treatment.
+    pub max_smooth_block_error_scale: f32,
+    /// Standard deviation below which a block counts as smooth. Must be positive.
+    pub max_smooth_block_std_dev: f32,
+}
+
+impl Default for UastcRdo {
+    fn default() -> Self {
+        Self {
+            quality_scalar: 1.0,
+            dict_size: 4096,
+            max_smooth_block_error_scale: 10.0,
+            max_smooth_block_std_dev: 18.0,
+        }
+    }
+}
+
+impl UastcRdo {
+    fn is_valid(&self) -> bool {
+        self.quality_scalar > 0.0
+            && self.quality_scalar.is_finite()
+            && (64..=65536).contains(&self.dict_size)
+            && (1.0..=300.0).contains(&self.max_smooth_block_error_scale)
+            && self.max_smooth_block_std_dev > 0.0
+            && self.max_smooth_block_std_dev.is_finite()
+    }
+}
+
+/// Settings for a [`Compressor`].
+///
+/// Settings are chosen with chained setters and checked by [`validate`](Self::validate) once
+/// compression starts:
+///
+/// ```
+/// use basisu::encoder::{CompressorParams, UastcLevel};
+///
+/// let params = CompressorParams::new()
+///     .uastc(UastcLevel::Slower)
+///     .mip_gen(true)
+///     .thread_count(4);
+/// assert!(params.validate().is_ok());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CompressorParams {
+    texture_type: TextureType,
+    us_per_frame: u32,
+    mode: Mode,
+    quality_level: u32,
+    uastc_rdo: Option<UastcRdo>,
+    perceptual: bool,
+    mip_gen: bool,
+    mip_filter: MipFilter,
+    mip_srgb: bool,
+    mip_wrapping: bool,
+    mip_renormalize: bool,
+    ktx2_srgb: bool,
+    y_flip: bool,
+    alpha_mode: AlphaMode,
+    swizzle: [u8; 4],
+    max_dimension: Option<u32>,
+    power_of_two: bool,
+    etc1s_rdo: bool,
+    global_selector_palette: bool,
+    container: Container,
+    ktx2_supercompression: SupercompressionScheme,
+    ktx2_zstd_level: u32,
+    thread_count: u32,
+}
+
+impl CompressorParams {
+    /// Default settings: a .basis file of 2D images using ETC1S at quality level 128 with
+    /// perceptual error metrics, no mipmap generation and a single thread. Generated mipmaps use a
+    /// Kaiser filter in sRGB space with clamped sampling.
+    pub fn new() -> Self {
+        Self {
+            texture_type: TextureType::Texture2D,
+            us_per_frame: 0,
+            mode: Mode::Etc1s,
+            quality_level: 128,
+            uastc_rdo: None,
+            perceptual: true,
+            mip_gen: false,
+            mip_filter: MipFilter::Kaiser,
+            mip_srgb: true,
+            mip_wrapping: false,
+            mip_renormalize: false,
+            ktx2_srgb: true,
+            y_flip: false,
+            alpha_mode: AlphaMode::Detect,
+            swizzle: [0, 1, 2, 3],
+            max_dimension: None,
+            power_of_two: false,
+            etc1s_rdo: true,
+            global_selector_palette: false,
+            container: Container::Basis,
+            ktx2_supercompression: SupercompressionScheme::None,
+            ktx2_zstd_level: 6,
+            thread_count: 1,
+        }
+    }
+
+    /// How the source images should be interpreted. For
+    /// [`CubemapArray`](TextureType::CubemapArray) files, the number of images must be a multiple
+    /// of six, with the faces of each cubemap in X+, X-, Y+, Y-, Z+, Z- order, and all faces must
+    /// have the same dimensions.
+    ///
+    /// The layers of a [`Texture2DArray`](TextureType::Texture2DArray) or
+    /// [`Volume`](TextureType::Volume) must all have the same dimensions too. Every image of a
+    /// file shares one set of ETC1S codebooks, so encoding related images as layers of one array
+    /// gives much smaller output than encoding them as separate files.
+    ///
+    /// The images of a [`VideoFrames`](TextureType::VideoFrames) file are its frames in playback
+    /// order, again all with the same dimensions. With ETC1S, frames after the first are encoded
+    /// as P-frames that reuse blocks of the previous frame where they haven't changed.
+    pub fn texture_type(mut self, texture_type: TextureType) -> Self {
+        self.texture_type = texture_type;
+        self
+    }
+
+    /// Display time of each video frame in microseconds, below 2<sup>24</sup>. Only recorded in
+    /// video files.
+    pub fn us_per_frame(mut self, us_per_frame: u32) -> Self {
+        self.us_per_frame = us_per_frame;
+        self
+    }
+
+    /// Select whether to encode with ETC1S or UASTC.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Encode with UASTC at the given level. Shorthand for `mode(Mode::Uastc(level))`.
+    pub fn uastc(self, level: UastcLevel) -> Self {
+        self.mode(Mode::Uastc(level))
+    }
+
+    /// Set the ETC1S quality level, from 1 to 255. Higher levels give larger files that are closer
+    /// to the source images. Ignored in UASTC mode.
+    pub fn quality_level(mut self, quality_level: u32) -> Self {
+        self.quality_level = quality_level;
+        self
+    }
+
+    /// Enable rate-distortion optimization of UASTC data with the given settings, or disable it
+    /// with `None`. Ignored in ETC1S mode.
+    pub fn uastc_rdo(mut self, uastc_rdo: Option<UastcRdo>) -> Self {
+        self.uastc_rdo = uastc_rdo;
+        self
+    }
+
+    /// Whether the source images hold sRGB colors, so errors should be measured perceptually.
+    /// Disable for linear data like normal maps, or use [`color_space`](Self::color_space) to
+    /// also filter mipmaps and tag .ktx2 files accordingly.
+    pub fn perceptual(mut self, perceptual: bool) -> Self {
+        self.perceptual = perceptual;
+        self
+    }
+
+    /// Set every color space dependent setting at once: [`perceptual`](Self::perceptual),
+    /// [`mip_srgb`](Self::mip_srgb) and [`ktx2_srgb`](Self::ktx2_srgb). Defaults to
+    /// [`ColorSpace::Srgb`].
+    pub fn color_space(self, color_space: ColorSpace) -> Self {
+        let srgb = color_space == ColorSpace::Srgb;
+        self.perceptual(srgb).mip_srgb(srgb).ktx2_srgb(srgb)
+    }
+
+    /// Treat the source images as linear data rather than sRGB colors, as for roughness,
+    /// metallic and occlusion maps. Errors are measured per channel, mipmaps are filtered without
+    /// sRGB conversion and .ktx2 files are tagged as linear.
+    pub fn linear(self) -> Self {
+        self.color_space(ColorSpace::Linear)
+    }
+
+    /// Tune the settings for tangent space normal maps: like [`linear`](Self::linear), but
+    /// generated mipmaps are renormalized and ETC1S rate-distortion optimization, which causes
+    /// visible artifacts in lighting, is disabled.
+    pub fn normal_map(self) -> Self {
+        self.linear().mip_renormalize(true).etc1s_rdo(false)
+    }
+
+    /// Whether to flip the source images vertically before encoding, for images stored bottom row
+    /// first as OpenGL expects.
+    pub fn y_flip(mut self, y_flip: bool) -> Self {
+        self.y_flip = y_flip;
+        self
+    }
+
+    /// Downscale source images whose width or height exceeds `max_dimension`, keeping their aspect
+    /// ratio. Resampling uses the [`mip_filter`](Self::mip_filter), [`mip_srgb`](Self::mip_srgb)
+    /// and [`mip_wrapping`](Self::mip_wrapping) settings. Can't be combined with supplied mipmaps.
+    pub fn max_dimension(mut self, max_dimension: Option<u32>) -> Self {
+        self.max_dimension = max_dimension;
+        self
+    }
+
+    /// Whether to downscale source images to the next smaller power of two in each direction,
+    /// after applying [`max_dimension`](Self::max_dimension), for renderers that need power of two
+    /// textures. Can't be combined with supplied mipmaps.
+    pub fn power_of_two(mut self, power_of_two: bool) -> Self {
+        self.power_of_two = power_of_two;
+        self
+    }
+
+    /// Whether the encoded file gets an alpha channel. Defaults to [`AlphaMode::Detect`].
+    pub fn alpha_mode(mut self, alpha_mode: AlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+
+    /// Reorder the channels of the source images before encoding: output channel `i` is taken
+    /// from source channel `swizzle[i]`, where 0 to 3 stand for red, green, blue and alpha. For
+    /// example `[2, 1, 0, 3]` reads BGRA input and `[0, 1, 2, 0]` copies red into alpha. Defaults
+    /// to `[0, 1, 2, 3]`.
+    pub fn swizzle(mut self, swizzle: [u8; 4]) -> Self {
+        self.swizzle = swizzle;
+        self
+    }
+
+    /// Encode the red channel of the source images as color and the green channel as alpha, as
+    /// for two channel data like the XY components of tangent space normal maps. Stored that way,
+    /// each channel gets its own ETC1S slice and so much better quality.
+    pub fn separate_rg_to_color_alpha(mut self) -> Self {
+        self.swizzle = [0, 0, 0, 1];
+        self.alpha_mode(AlphaMode::Force)
+    }
+
+    /// Replace the alpha channel with the red channel, which holds the luminance of grayscale
+    /// images. Turns single channel masks into their own alpha, for renderers that sample the
+    /// mask from alpha.
+    pub fn alpha_from_luminance(mut self) -> Self {
+        self.swizzle[3] = 0;
+        self.alpha_mode(AlphaMode::Force)
+    }
+
+    /// Whether ETC1S selectors are coded as references into the global selector codebook, which
+    /// can shrink files with repetitive content. Disabled by default.
+    pub fn global_selector_palette(mut self, global_selector_palette: bool) -> Self {
+        self.global_selector_palette = global_selector_palette;
+        self
+    }
+
+    /// Whether to renormalize the texels of generated mipmaps, treating RGB as XYZ vectors.
+    pub fn mip_renormalize(mut self, mip_renormalize: bool) -> Self {
+        self.mip_renormalize = mip_renormalize;
+        self
+    }
+
+    /// Whether ETC1S endpoints and selectors are rate-distortion optimized, giving smaller files
+    /// at slightly lower quality. Enabled by default.
+    pub fn etc1s_rdo(mut self, etc1s_rdo: bool) -> Self {
+        self.etc1s_rdo = etc1s_rdo;
+        self
+    }
+
+    /// Whether to generate a full mipmap chain for each source image.
+    pub fn mip_gen(mut self, mip_gen: bool) -> Self {
+        self.mip_gen = mip_gen;
+        self
+    }
+
+    /// Filter kernel used for mipmap generation.
+    pub fn mip_filter(mut self, mip_filter: MipFilter) -> Self {
+        self.mip_filter = mip_filter;
+        self
+    }
+
+    /// Whether to convert sRGB source images to linear light before filtering mipmaps, and back
+    /// afterwards. Disable for images that don't hold sRGB colors.
+    pub fn mip_srgb(mut self, mip_srgb: bool) -> Self {
+        self.mip_srgb = mip_srgb;
+        self
+    }
+
+    /// Whether mipmap filtering wraps around the image edges, as for tiling textures, rather than
+    /// clamping to them.
+    pub fn mip_wrapping(mut self, mip_wrapping: bool) -> Self {
+        self.mip_wrapping = mip_wrapping;
+        self
+    }
+
+    /// Whether to produce a .basis or a .ktx2 file.
+    pub fn container(mut self, container: Container) -> Self {
+        self.container = container;
+        self
+    }
+
+    /// Whether the data format descriptor of .ktx2 files uses the sRGB transfer function rather
+    /// than the linear one, so GPUs decode the texture to linear light when sampling it. Enabled
+    /// by default.
+    pub fn ktx2_srgb(mut self, ktx2_srgb: bool) -> Self {
+        self.ktx2_srgb = ktx2_srgb;
+        self
+    }
+
+    /// Supercompression applied to UASTC data in .ktx2 files: either
+    /// [`SupercompressionScheme::None`] or [`SupercompressionScheme::Zstandard`], which requires
+    /// the `zstd` feature. ETC1S data always uses BasisLZ.
+    pub fn ktx2_supercompression(mut self, scheme: SupercompressionScheme) -> Self {
+        self.ktx2_supercompression = scheme;
+        self
+    }
+
+    /// Zstandard compression level used with [`SupercompressionScheme::Zstandard`], from 1 to 22.
+    /// Higher levels give smaller files but take longer to encode; transcoding speed is about the
+    /// same for all of them. Defaults to 6.
+    pub fn ktx2_zstd_level(mut self, level: u32) -> Self {
+        self.ktx2_zstd_level = level;
+        self
+    }
+
+    /// Number of threads to encode with, at least one. A good choice for large textures is
+    /// [`std::thread::available_parallelism`]. Ignored by
+    /// [`Compressor::compress_with_pool`], which uses the threads of its pool instead.
+    pub fn thread_count(mut self, thread_count: u32) -> Self {
+        self.thread_count = thread_count;
+        self
+    }
+
+    /// Check that every setting is within its valid range.
+    pub fn validate(&self) -> Result<(), BasisError> {
+        if !(1..=255).contains(&self.quality_level)
+            || self.thread_count == 0
+            || self.us_per_frame >= 1 << 24
+            || self.max_dimension == Some(0)
+            || self.swizzle.iter().any(|&channel| channel > 3)
+        {
+            return Err(BasisError::InvalidArgument);
+        }
+        if self.uastc_rdo.map_or(false, |rdo| !rdo.is_valid()) {
+            return Err(BasisError::InvalidArgument);
+        }
+        match self.ktx2_supercompression {
+            SupercompressionScheme::None => {}
+            SupercompressionScheme::Zstandard if !(1..=22).contains(&self.ktx2_zstd_level) => {
+                return Err(BasisError::InvalidArgument)
+            }
+            SupercompressionScheme::Zstandard if cfg!(feature = "zstd") => {}
+            scheme => return Err(BasisError::UnsupportedSupercompression(scheme)),
+        }
+        Ok(())
+    }
+
+    /// Dimensions a `width` x `height` source image is resized to before encoding.
+    fn resized_dimensions(&self, width: u32, height: u32) -> (u32, u32) {
+        let (mut width, mut height) = (width, height);
+        if let Some(max) = self.max_dimension {
+            let largest = width.max(height);
+            if largest > max {
+                let scale =
+                    |size: u32| (u64::from(size) * u64::from(max) / u64::from(largest)) as u32;
+                width = scale(width).max(1);
+                height = scale(height).max(1);
+            }
+        }
+        if self.power_of_two {
+            let round_down = |size: u32| 1 << (31 - size.leading_zeros());
+            width = round_down(width);
+            height = round_down(height);
+        }
+        (width, height)
+    }
+
+    /// Pass the settings on to a C++ compressor.
+    unsafe fn apply(&self, handle: &Handle) {
+        shim::compressor_set_tex_type(handle.0, self.texture_type as basist::basis_texture_type);
+        shim::compressor_set_us_per_frame(handle.0, self.us_per_frame);
+        shim::compressor_set_quality_level(handle.0, self.quality_level as i32);
+        match self.mode {
+            Mode::Etc1s => shim::compressor_set_uastc(handle.0, false, 0),
+            Mode::Uastc(level) => shim::compressor_set_uastc(handle.0, true, level as u32),
+        }
+        match self.uastc_rdo {
+            Some(rdo) => shim::compressor_set_uastc_rdo(
+                handle.0,
+                true,
+                rdo.quality_scalar,
+                rdo.dict_size as i32,
+                rdo.max_smooth_block_error_scale,
+                rdo.max_smooth_block_std_dev,
+            ),
+            None => shim::compressor_set_uastc_rdo(handle.0, false, 0.0, 0, 0.0, 0.0),
+        }
+        shim::compressor_set_perceptual(handle.0, self.perceptual);
+        shim::compressor_set_y_flip(handle.0, self.y_flip);
+        shim::compressor_set_alpha(
+            handle.0,
+            self.alpha_mode == AlphaMode::Detect,
+            self.alpha_mode == AlphaMode::Force,
+        );
+        let [r, g, b, a] = self.swizzle;
+        shim::compressor_set_swizzle(handle.0, r, g, b, a);
+        shim::compressor_set_etc1s_rdo(handle.0, self.etc1s_rdo);
+        shim::compressor_set_global_sel_pal(handle.0, self.global_selector_palette);
+        shim::compressor_set_mip_gen(handle.0, self.mip_gen);
+        shim::compressor_set_mip_filter(handle.0, self.mip_filter.name().as_ptr() as *const _);
+        shim::compressor_set_mip_srgb(handle.0, self.mip_srgb);
+        shim::compressor_set_mip_wrapping(handle.0, self.mip_wrapping);
+        shim::compressor_set_mip_renormalize(handle.0, self.mip_renormalize);
+        shim::compressor_set_ktx2(
+            handle.0,
+            self.container == Container::Ktx2,
+            self.ktx2_supercompression == SupercompressionScheme::Zstandard,
+            self.ktx2_zstd_level as i32,
+            self.ktx2_srgb,
+        );
+    }
+}
+
+impl Default for CompressorParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// RGBA8 pixels of one level of a source image, borrowed from the caller where possible.
+struct SourceLevel<'a> {
+    rgba: Cow<'a, [u8]>,
+    width: u32,
+    height: u32,
+}
+
+impl<'a> SourceLevel<'a> {
+    fn new(rgba: Cow<'a, [u8]>, width: u32, height: u32) -> Result<Self, BasisError> {
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4));
+        if width == 0 || height == 0 || len != Some(rgba.len()) {
+            return Err(BasisError::InvalidArgument);
+        }
+        Ok(Self {
+            rgba,
+            width,
+            height,
+        })
+    }
+}
+
+/// A source image together with any mipmaps supplied for it.
+struct SourceImage<'a> {
+    base: SourceLevel<'a>,
+    mipmaps: Vec<SourceLevel<'a>>,
+}
+
+/// Owner of a C++ compressor, which holds a copy of the parameters and source images.
+struct Handle(*mut shim::compressor);
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe { shim::delete_compressor(self.0) }
+    }
+}
+
+/// A pool of encoder worker threads.
+///
+/// [`Compressor::compress`] starts new threads for every file. Encoding many files with
+/// [`Compressor::compress_with_pool`] and one pool instead reuses the same threads.
+pub struct JobPool {
+    pool: *mut shim::job_pool,
+    thread_count: u32,
+}
+
+// The pool's threads are only ever driven by the thread holding a mutable reference to it.
+unsafe impl Send for JobPool {}
+
+impl JobPool {
+    /// Create a pool of `thread_count` threads, counting the thread that runs each compression.
+    /// `thread_count` must be at least one.
+    pub fn new(thread_count: u32) -> Result<Self, BasisError> {
+        if thread_count == 0 {
+            return Err(BasisError::InvalidArgument);
+        }
+        Ok(Self {
+            pool: unsafe { shim::new_job_pool(thread_count) },
+            thread_count,
+        })
+    }
+
+    /// Number of threads in the pool.
+    pub fn thread_count(&self) -> u32 {
+        self.thread_count
+    }
+}
+
+impl Drop for JobPool {
+    fn drop(&mut self) {
+        unsafe { shim::delete_job_pool(self.pool) }
+    }
+}
+
+/// Encodes RGBA8 images into a .basis or .ktx2 file.
+pub struct Compressor<'a> {
+    images: Vec<SourceImage<'a>>,
+    params: CompressorParams,
+    selector_codebook: Option<Arc<SelectorCodebook>>,
+}
+
+impl<'a> Compressor<'a> {
+    /// Create a compressor without any source images.
+    pub fn new(params: CompressorParams) -> Self {
+        Self {
+            images: Vec::new(),
+            params,
+            selector_codebook: None,
+        }
+    }
+
+    /// Use `codebook` instead of the built-in global selector codebook when the global selector
+    /// palette is enabled with [`CompressorParams::global_selector_palette`]. The resulting files
+    /// must be transcoded with the same codebook.
+    pub fn set_selector_codebook(&mut self, codebook: Arc<SelectorCodebook>) {
+        self.selector_codebook = Some(codebook);
+    }
+
+    /// Return the settings the compressor encodes with.
+    pub fn params(&self) -> &CompressorParams {
+        &self.params
+    }
+
+    /// Replace the settings the compressor encodes with.
+    pub fn set_params(&mut self, params: CompressorParams) {
+        self.params = params;
+    }
+
+    /// Add a `width` x `height` image of tightly packed RGBA8 pixels. Each image added becomes one
+    /// image of the resulting file.
+    pub fn add_image_rgba(
+        &mut self,
+        rgba: &'a [u8],
+        width: u32,
+        height: u32,
+    ) -> Result<(), BasisError> {
+        self.push_image(SourceLevel::new(Cow::Borrowed(rgba), width, height)?);
+        Ok(())
+    }
+
+    /// Add the next mipmap of the most recently added image, instead of having the encoder
+    /// generate them. Each mipmap must be half the size of the previous level, rounded down but at
+    /// least one pixel. Can't be combined with [`CompressorParams::mip_gen`].
+    pub fn add_mipmap_rgba(
+        &mut self,
+        rgba: &'a [u8],
+        width: u32,
+        height: u32,
+    ) -> Result<(), BasisError> {
+        self.push_mipmap(SourceLevel::new(Cow::Borrowed(rgba), width, height)?)
+    }
+
+    fn push_image(&mut self, base: SourceLevel<'a>) {
+        self.images.push(SourceImage {
+            base,
+            mipmaps: Vec::new(),
+        });
+    }
+
+    fn push_mipmap(&mut self, level: SourceLevel<'a>) -> Result<(), BasisError> {
+        let image = self.images.last_mut().ok_or(BasisError::InvalidArgument)?;
+        let previous = image.mipmaps.last().unwrap_or(&image.base);
+        let expected = ((previous.width / 2).max(1), (previous.height / 2).max(1));
+        if (previous.width, previous.height) == (1, 1) || (level.width, level.height) != expected {
+            return Err(BasisError::InvalidArgument);
+        }
+        image.mipmaps.push(level);
+        Ok(())
+    }
+
+    /// Check that the source images fit the texture type.
+    fn check_images(&self) -> Result<(), BasisError> {
+        let first = &self.images.first().ok_or(BasisError::InvalidArgument)?.base;
+        let same_dimensions = self
+            .images
+            .iter()
+            .all(|image| (image.base.width, image.base.height) == (first.width, first.height));
+        let has_mipmaps = self.images.iter().any(|image| !image.mipmaps.is_empty());
+        let resizes = self.params.max_dimension.is_some() || self.params.power_of_two;
+        if has_mipmaps && (self.params.mip_gen || resizes) {
+            return Err(BasisError::InvalidArgument);
+        }
+
+        let valid = match self.params.texture_type {
+            TextureType::Texture2DArray | TextureType::VideoFrames | TextureType::Volume => {
+                same_dimensions
+            }
+            TextureType::CubemapArray => self.images.len() % 6 == 0 && same_dimensions,
+            _ => true,
+        };
+        if !valid {
+            return Err(BasisError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    /// Encode the source images and return the contents of the resulting file.
+    pub fn compress(&self) -> Result<Vec<u8>, BasisError> {
+        self.params.validate()?;
+        self.compress_with_pool(&mut JobPool::new(self.params.thread_count)?)
+    }
+
+    /// Like [`compress`](Self::compress), but encode on the threads of `pool`.
+    pub fn compress_with_pool(&self, pool: &mut JobPool) -> Result<Vec<u8>, BasisError> {
+        self.params.validate()?;
+        self.check_images()?;
+
+        init_encoder();
+
+        unsafe {
+            let codebook = match &self.selector_codebook {
+                Some(codebook) => codebook.as_ptr(),
+                None => global_codebook(),
+            };
+            let handle = Handle(shim::new_compressor(codebook));
+            for (index, image) in self.images.iter().enumerate() {
+                let base = &image.base;
+                shim::compressor_add_image(handle.0, base.rgba.as_ptr(), base.width, base.height);
+                for level in &image.mipmaps {
+                    shim::compressor_add_mipmap(
+                        handle.0,
+                        index as u32,
+                        level.rgba.as_ptr(),
+                        level.width,
+                        level.height,
+                    );
+                }
+            }
+            self.params.apply(&handle);
+            for (index, image) in self.images.iter().enumerate() {
+                let (width, height) = (image.base.width, image.base.height);
+                let resized = self.params.resized_dimensions(width, height);
+                if resized != (width, height) {
+                    shim::compressor_resize_image(handle.0, index as u32, resized.0, resized.1);
+                }
+            }
+
+            let result = shim::compressor_process(handle.0, pool.pool);
+            if let Some(error) = EncodeError::from_raw(result) {
+                return Err(BasisError::EncodeFailed(error));
+            }
+            let mut size = 0;
+            let output = shim::compressor_get_output(handle.0, &mut size);
+            Ok(slice::from_raw_parts(output, size as usize).to_vec())
+        }
+    }
+}
+
+/// Input from the `image` crate, available with the `image` feature. Images that aren't already
+/// RGBA8 are converted to it.
+#[cfg(feature = "image")]
+impl<'a> Compressor<'a> {
+    /// Add an image. Each image added becomes one image of the resulting file.
+    pub fn add_image(&mut self, image: &'a DynamicImage) -> Result<(), BasisError> {
+        self.push_image(dynamic_level(image)?);
+        Ok(())
+    }
+
+    /// Add an RGBA8 image. Each image added becomes one image of the resulting file.
+    pub fn add_rgba_image(&mut self, image: &'a RgbaImage) -> Result<(), BasisError> {
+        self.add_image_rgba(image.as_raw(), image.width(), image.height())
+    }
+
+    /// Add several images, like the layers of an array or the faces of a cubemap.
+    pub fn add_images<I>(&mut self, images: I) -> Result<(), BasisError>
+    where
+        I: IntoIterator<Item = &'a DynamicImage>,
+    {
+        images
+            .into_iter()
+            .try_for_each(|image| self.add_image(image))
+    }
+
+    /// Add an image given as its complete mipmap chain, starting with the full size level. See
+    /// [`add_mipmap_rgba`](Self::add_mipmap_rgba) for the requirements on the mipmaps. Nothing is
+    /// added if any level is rejected.
+    pub fn add_image_with_mipmaps<I>(&mut self, levels: I) -> Result<(), BasisError>
+    where
+        I: IntoIterator<Item = &'a DynamicImage>,
+    {
+        let mut levels = levels.into_iter();
+        let base = levels.next().ok_or(BasisError::InvalidArgument)?;
+        self.add_image(base)?;
+        let result = levels.try_for_each(|level| self.push_mipmap(dynamic_level(level)?));
+        if result.is_err() {
+            self.images.pop();
+        }
+        result
+    }
+}
+
+/// Borrow the pixels of an RGBA8 image, or convert any other image to RGBA8.
+#[cfg(feature = "image")]
+fn dynamic_level(image: &DynamicImage) -> Result<SourceLevel<'_>, BasisError> {
+    let (width, height) = image.dimensions();
+    let rgba = match image {
+        DynamicImage::ImageRgba8(rgba) => Cow::Borrowed(&rgba.as_raw()[..]),
+        _ => Cow::Owned(image.to_rgba8().into_raw()),
+    };
+    SourceLevel::new(rgba, width, height)
+}
+
+impl<'a> Default for Compressor<'a> {
+    fn default() -> Self {
+        Self::new(CompressorParams::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ktx2::ColorModel;
+    use crate::{BasisTranscoder, CubeFace, OutputFormat, TexFormat, Transcoder};
+
+    #[test]
+    fn reject_invalid_images() {
+        let mut compressor = Compressor::default();
+        assert_eq!(
+            compressor.add_image_rgba(&[0; 15], 2, 2),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(
+            compressor.add_image_rgba(&[], 0, 0),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(compressor.compress(), Err(BasisError::InvalidArgument));
+    }
+
+    #[test]
+    fn reject_invalid_cubemaps() {
+        let face = [0; 4 * 4 * 4];
+        let small_face = [0; 2 * 2 * 4];
+        let params = CompressorParams::new().texture_type(TextureType::CubemapArray);
+
+        let mut compressor = Compressor::new(params);
+        for _ in 0..5 {
+            compressor.add_image_rgba(&face, 4, 4).unwrap();
+        }
+        assert_eq!(compressor.compress(), Err(BasisError::InvalidArgument));
+
+        compressor.add_image_rgba(&small_face, 2, 2).unwrap();
+        assert_eq!(compressor.compress(), Err(BasisError::InvalidArgument));
+    }
+
+    #[test]
+    fn reject_invalid_mipmaps() {
+        let base = [0; 8 * 4 * 4];
+        let mip = [0; 4 * 2 * 4];
+        let mut compressor = Compressor::default();
+        assert_eq!(
+            compressor.add_mipmap_rgba(&mip, 4, 2),
+            Err(BasisError::InvalidArgument)
+        );
+
+        compressor.add_image_rgba(&base, 8, 4).unwrap();
+        assert_eq!(
+            compressor.add_mipmap_rgba(&[0; 2 * 4], 2, 1),
+            Err(BasisError::InvalidArgument)
+        );
+        compressor.add_mipmap_rgba(&mip, 4, 2).unwrap();
+        compressor.add_mipmap_rgba(&[0; 2 * 4], 2, 1).unwrap();
+        compressor.add_mipmap_rgba(&[0; 4], 1, 1).unwrap();
+        assert_eq!(
+            compressor.add_mipmap_rgba(&[0; 4], 1, 1),
+            Err(BasisError::InvalidArgument)
+        );
+
+        compressor.set_params(CompressorParams::new().mip_gen(true));
+        assert_eq!(compressor.compress(), Err(BasisError::InvalidArgument));
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn image_input() {
+        let gray = DynamicImage::ImageLuma8(image::GrayImage::from_raw(2, 1, vec![7, 9]).unwrap());
+        let rgba = DynamicImage::ImageRgba8(RgbaImage::from_raw(1, 1, vec![1, 2, 3, 4]).unwrap());
+
+        let mut compressor = Compressor::default();
+        compressor.add_images(vec![&gray, &rgba]).unwrap();
+        let levels: Vec<_> = compressor.images.iter().map(|image| &image.base).collect();
+        assert_eq!(&levels[0].rgba[..], &[7, 7, 7, 255, 9, 9, 9, 255]);
+        assert!(matches!(levels[1].rgba, Cow::Borrowed(&[1, 2, 3, 4])));
+
+        assert_eq!(
+            compressor.add_image_with_mipmaps(vec![&gray, &gray]),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(compressor.images.len(), 2);
+        compressor
+            .add_image_with_mipmaps(vec![&gray, &rgba])
+            .unwrap();
+        assert_eq!(compressor.images.len(), 3);
+        assert_eq!(compressor.images[2].mipmaps.len(), 1);
+    }
+
+    #[test]
+    fn job_pool() {
+        fn assert_send<T: Send>() {}
+        assert_send::<JobPool>();
+        assert!(JobPool::new(0).is_err());
+    }
+
+    #[test]
+    fn linear_presets() {
+        let params = CompressorParams::new().linear();
+        assert!(!params.perceptual && !params.mip_srgb && !params.ktx2_srgb);
+        assert!(!params.mip_renormalize && params.etc1s_rdo);
+        assert_eq!(
+            params.color_space(ColorSpace::Srgb),
+            CompressorParams::new()
+        );
+
+        let params = CompressorParams::new().normal_map();
+        assert!(!params.perceptual && !params.mip_srgb && !params.ktx2_srgb);
+        assert!(params.mip_renormalize && !params.etc1s_rdo);
+    }
+
+    #[test]
+    fn alpha_presets() {
+        let params = CompressorParams::new().separate_rg_to_color_alpha();
+        assert_eq!(params.swizzle, [0, 0, 0, 1]);
+        assert_eq!(params.alpha_mode, AlphaMode::Force);
+
+        let params = CompressorParams::new().alpha_from_luminance();
+        assert_eq!(params.swizzle, [0, 1, 2, 0]);
+        assert_eq!(params.alpha_mode, AlphaMode::Force);
+    }
+
+    #[test]
+    fn resized_dimensions() {
+        let params = CompressorParams::new();
+        assert_eq!(params.resized_dimensions(1000, 300), (1000, 300));
+        let params = params.max_dimension(Some(512));
+        assert_eq!(params.resized_dimensions(1000, 300), (512, 153));
+        assert_eq!(params.resized_dimensions(300, 400), (300, 400));
+        assert_eq!(params.resized_dimensions(4096, 1), (512, 1));
+        let params = params.power_of_two(true);
+        assert_eq!(params.resized_dimensions(1000, 300), (512, 128));
+        assert_eq!(params.resized_dimensions(300, 400), (256, 256));
+
+        let level = [0; 4 * 4 * 4];
+        let mut compressor = Compressor::new(params);
+        compressor.add_image_rgba(&level, 4, 4).unwrap();
+        compressor.add_mipmap_rgba(&level[..4 * 4], 2, 2).unwrap();
+        assert_eq!(compressor.compress(), Err(BasisError::InvalidArgument));
+        assert_eq!(
+            CompressorParams::new().max_dimension(Some(0)).validate(),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(
+            CompressorParams::new().swizzle([2, 1, 0, 3]).validate(),
+            Ok(())
+        );
+        assert_eq!(
+            CompressorParams::new().swizzle([0, 1, 2, 4]).validate(),
+            Err(BasisError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn reject_invalid_arrays() {
+        let layer = [0; 4 * 4 * 4];
+        let params = CompressorParams::new().texture_type(TextureType::Texture2DArray);
+        let mut compressor = Compressor::new(params);
+        compressor.add_image_rgba(&layer, 4, 4).unwrap();
+        compressor.add_image_rgba(&layer, 8, 2).unwrap();
+        assert_eq!(compressor.compress(), Err(BasisError::InvalidArgument));
+    }
+
+    #[test]
+    fn validate_params() {
+        assert_eq!(CompressorParams::new().validate(), Ok(()));
+        assert_eq!(
+            CompressorParams::new().quality_level(0).validate(),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(
+            CompressorParams::new().quality_level(256).validate(),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(
+            CompressorParams::new().thread_count(0).validate(),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(
+            CompressorParams::new().us_per_frame(1 << 24).validate(),
+            Err(BasisError::InvalidArgument)
+        );
+
+        assert_eq!(
+            CompressorParams::new()
+                .ktx2_supercompression(SupercompressionScheme::Zlib)
+                .validate(),
+            Err(BasisError::UnsupportedSupercompression(
+                SupercompressionScheme::Zlib
+            ))
+        );
+        assert_eq!(
+            CompressorParams::new()
+                .ktx2_supercompression(SupercompressionScheme::Zstandard)
+                .ktx2_zstd_level(23)
+                .validate(),
+            Err(BasisError::InvalidArgument)
+        );
+
+        let rdo = UastcRdo {
+            dict_size: 32,
+            ..UastcRdo::default()
+        };
+        assert_eq!(
+            CompressorParams::new().uastc_rdo(Some(rdo)).validate(),
+            Err(BasisError::InvalidArgument)
+        );
+        let rdo = UastcRdo {
+            quality_scalar: f32::NAN,
+            ..UastcRdo::default()
+        };
+        assert_eq!(
+            CompressorParams::new().uastc_rdo(Some(rdo)).validate(),
+            Err(BasisError::InvalidArgument)
+        );
+        let rdo = UastcRdo::default();
+        assert_eq!(
+            CompressorParams::new().uastc_rdo(Some(rdo)).validate(),
+            Ok(())
+        );
+
+        let mut compressor = Compressor::new(CompressorParams::new().thread_count(0));
+        compressor.add_image_rgba(&[0; 16], 2, 2).unwrap();
+        assert_eq!(compressor.compress(), Err(BasisError::InvalidArgument));
+    }
+
+    /// Encode a 16x16 gradient with `params`, check that the result decodes and return it.
+    fn round_trip(params: CompressorParams, tex_format: TexFormat) -> Vec<u8> {
+        let rgba: Vec<u8> = (0..16 * 16).flat_map(|i| [i as u8, 0, 255, 255]).collect();
+        let mut compressor = Compressor::new(params);
+        compressor.add_image_rgba(&rgba, 16, 16).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding(&data).unwrap();
+        assert_eq!(file.get_tex_format(), tex_format);
+        assert_eq!(file.get_total_images(), 1);
+        assert_eq!(file.level_dimensions(0, 0), Ok((16, 16)));
+        let pixels = file
+            .transcode_image_level_to_vec(0, 0, OutputFormat::RGBA32)
+            .unwrap();
+        assert_eq!(pixels.len(), rgba.len());
+        data
+    }
+
+    #[test]
+    fn encode_while_transcoding_from_other_threads() {
+        use std::sync::Barrier;
+
+        // The encoder initializes the transcoder tables too, which must not race with
+        // transcoders being created elsewhere.
+        let barrier = Arc::new(Barrier::new(8));
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    let rgba = [i as u8 * 30; 8 * 8 * 4];
+                    let params = if i % 2 == 0 {
+                        CompressorParams::new()
+                    } else {
+                        CompressorParams::new().uastc(UastcLevel::Fastest)
+                    };
+                    let mut compressor = Compressor::new(params);
+                    compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+                    let data = compressor.compress().unwrap();
+                    let mut transcoder = BasisTranscoder::new();
+                    let file = transcoder.start_transcoding(&data).unwrap();
+                    assert_eq!(file.level_dimensions(0, 0), Ok((8, 8)));
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn encode_etc1s() {
+        round_trip(CompressorParams::new(), TexFormat::ETC1S);
+    }
+
+    #[test]
+    fn encode_mipmaps() {
+        let params = CompressorParams::new()
+            .mip_gen(true)
+            .mip_filter(MipFilter::Box)
+            .mip_srgb(false)
+            .mip_wrapping(true);
+        let data = round_trip(params, TexFormat::ETC1S);
+
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding(&data).unwrap();
+        assert_eq!(file.get_total_image_levels(0), 5);
+        assert_eq!(file.level_dimensions(0, 4), Ok((1, 1)));
+
+        let images: Vec<_> = file.images().collect();
+        assert_eq!(images.len(), 1);
+        let widths: Vec<_> = images[0].levels().map(|level| level.info().width).collect();
+        assert_eq!(widths, [16, 8, 4, 2, 1]);
+    }
+
+    #[test]
+    fn encode_uastc() {
+        let params = CompressorParams::new().uastc(UastcLevel::Fastest);
+        round_trip(params, TexFormat::UASTC4x4);
+    }
+
+    #[test]
+    fn encode_uastc_rdo() {
+        let params = CompressorParams::new()
+            .uastc(UastcLevel::Fastest)
+            .uastc_rdo(Some(UastcRdo::default()));
+        round_trip(params, TexFormat::UASTC4x4);
+    }
+
+    #[test]
+    fn encode_ktx2() {
+        let rgba = [255; 8 * 8 * 4];
+        let params = CompressorParams::new()
+            .uastc(UastcLevel::Fastest)
+            .container(Container::Ktx2);
+        let mut compressor = Compressor::new(params);
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let file = Transcoder::open(&data).unwrap();
+        assert_eq!(file.container(), Container::Ktx2);
+        assert_eq!(file.get_tex_format(), TexFormat::UASTC4x4);
+        let dfd = file.as_ktx2().unwrap().dfd().unwrap();
+        assert_eq!(dfd.color_model, ColorModel::Uastc);
+        assert!(dfd.is_srgb());
+        let pixels = file
+            .transcode_image_level_to_vec(0, 0, OutputFormat::RGBA32)
+            .unwrap();
+        assert_eq!(pixels.len(), rgba.len());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn encode_ktx2_zstd_levels() {
+        let rgba: Vec<u8> = (0..64 * 64 * 4).map(|i| (i % 251) as u8).collect();
+        let encode = |level| {
+            let params = CompressorParams::new()
+                .uastc(UastcLevel::Fastest)
+                .container(Container::Ktx2)
+                .ktx2_supercompression(SupercompressionScheme::Zstandard)
+                .ktx2_zstd_level(level);
+            let mut compressor = Compressor::new(params);
+            compressor.add_image_rgba(&rgba, 64, 64).unwrap();
+            compressor.compress().unwrap()
+        };
+        let fast = encode(1);
+        let small = encode(22);
+        assert!(small.len() <= fast.len());
+
+        for data in [fast, small] {
+            let file = Transcoder::open(&data).unwrap();
+            let pixels = file
+                .transcode_image_level_to_vec(0, 0, OutputFormat::RGBA32)
+                .unwrap();
+            assert_eq!(pixels.len(), rgba.len());
+        }
+    }
+
+    #[test]
+    fn encode_ktx2_linear() {
+        let rgba = [128; 8 * 8 * 4];
+        let params = CompressorParams::new().linear().container(Container::Ktx2);
+        let mut compressor = Compressor::new(params);
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let file = Transcoder::open(&data).unwrap();
+        let dfd = file.as_ktx2().unwrap().dfd().unwrap();
+        assert_eq!(dfd.color_model, ColorModel::Etc1s);
+        assert!(!dfd.is_srgb());
+    }
+
+    #[test]
+    fn encode_y_flip() {
+        // Red top half, blue bottom half.
+        let rgba: Vec<u8> = (0..8 * 8)
+            .flat_map(|i| {
+                if i < 32 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 255]
+                }
+            })
+            .collect();
+        let params = CompressorParams::new()
+            .uastc(UastcLevel::Fastest)
+            .y_flip(true);
+        let mut compressor = Compressor::new(params);
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding(&data).unwrap();
+        let pixels = file
+            .transcode_image_level_to_vec(0, 0, OutputFormat::RGBA32)
+            .unwrap();
+        assert!(pixels[2] > 200 && pixels[0] < 50);
+        assert!(pixels[pixels.len() - 4] > 200 && pixels[pixels.len() - 2] < 50);
+    }
+
+    #[test]
+    fn encode_alpha_modes() {
+        let opaque = [255; 8 * 8 * 4];
+        let transparent = [128; 8 * 8 * 4];
+        for (rgba, mode, has_alpha) in [
+            (&opaque, AlphaMode::Detect, false),
+            (&transparent, AlphaMode::Detect, true),
+            (&opaque, AlphaMode::Force, true),
+            (&transparent, AlphaMode::Opaque, false),
+        ] {
+            let mut compressor = Compressor::new(CompressorParams::new().alpha_mode(mode));
+            compressor.add_image_rgba(rgba, 8, 8).unwrap();
+            let data = compressor.compress().unwrap();
+
+            let mut transcoder = BasisTranscoder::new();
+            let file = transcoder.start_transcoding(&data).unwrap();
+            assert_eq!(file.get_file_info().has_alpha_slices, has_alpha);
+        }
+    }
+
+    #[test]
+    fn encode_resized() {
+        let rgba = [200; 40 * 24 * 4];
+        let params = CompressorParams::new()
+            .max_dimension(Some(20))
+            .power_of_two(true);
+        let mut compressor = Compressor::new(params);
+        compressor.add_image_rgba(&rgba, 40, 24).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding(&data).unwrap();
+        assert_eq!(file.level_dimensions(0, 0), Ok((16, 8)));
+    }
+
+    #[test]
+    fn encode_swizzled() {
+        let bgra: Vec<u8> = (0..8 * 8).flat_map(|_| [255, 0, 0, 255]).collect();
+        let params = CompressorParams::new()
+            .uastc(UastcLevel::Fastest)
+            .swizzle([2, 1, 0, 3]);
+        let mut compressor = Compressor::new(params);
+        compressor.add_image_rgba(&bgra, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding(&data).unwrap();
+        let pixels = file
+            .transcode_image_level_to_vec(0, 0, OutputFormat::RGBA32)
+            .unwrap();
+        assert!(pixels[0] < 50 && pixels[2] > 200);
+    }
+
+    #[test]
+    fn encode_eac_a8() {
+        let rgba: Vec<u8> = (0..8 * 8).flat_map(|_| [255, 255, 255, 0]).collect();
+        let mut compressor = Compressor::new(CompressorParams::new());
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let file = Transcoder::open(&data).unwrap();
+        let alpha = file.transcode_image_level_to_eac_a8(0, 0).unwrap();
+        assert_eq!(alpha.len(), 4 * 8);
+        let etc2 = file
+            .transcode_image_level_to_vec(0, 0, OutputFormat::ETC2_RGBA)
+            .unwrap();
+        let etc2_alpha: Vec<u8> = etc2
+            .chunks(16)
+            .flat_map(|block| &block[..8])
+            .copied()
+            .collect();
+        assert_eq!(alpha, etc2_alpha);
+
+        let mut compressor = Compressor::new(CompressorParams::new().uastc(UastcLevel::Fastest));
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+        let file = Transcoder::open(&data).unwrap();
+        assert_eq!(
+            file.transcode_image_level_to_eac_a8(0, 0).unwrap().len(),
+            4 * 8
+        );
+    }
+
+    #[test]
+    fn encode_cubemap() {
+        let faces: Vec<Vec<u8>> = (0..6).map(|i| vec![i * 40; 8 * 8 * 4]).collect();
+        let params = CompressorParams::new().texture_type(TextureType::CubemapArray);
+        let mut compressor = Compressor::new(params);
+        for face in &faces {
+            compressor.add_image_rgba(face, 8, 8).unwrap();
+        }
+        let data = compressor.compress().unwrap();
+
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding(&data).unwrap();
+        assert_eq!(file.get_texture_type(), TextureType::CubemapArray);
+        assert_eq!(file.total_cubemaps(), Ok(1));
+        assert_eq!(file.face(0, CubeFace::NegativeY).unwrap().index(), 3);
+        assert_eq!(
+            file.face(1, CubeFace::PositiveX).err(),
+            Some(BasisError::LayerIndexOutOfRange { index: 1, total: 1 })
+        );
+        assert_eq!(file.layers().count(), 0);
+    }
+
+    #[test]
+    fn encode_array() {
+        let layers: Vec<Vec<u8>> = (0..3).map(|i| vec![i * 80; 8 * 8 * 4]).collect();
+        let params = CompressorParams::new().texture_type(TextureType::Texture2DArray);
+        let mut compressor = Compressor::new(params);
+        for layer in &layers {
+            compressor.add_image_rgba(layer, 8, 8).unwrap();
+        }
+        let data = compressor.compress().unwrap();
+
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding(&data).unwrap();
+        assert_eq!(file.get_texture_type(), TextureType::Texture2DArray);
+        assert_eq!(file.total_layers(), Ok(3));
+        let indices: Vec<_> = file.layers().map(|layer| layer.index()).collect();
+        assert_eq!(indices, [0, 1, 2]);
+        assert_eq!(
+            file.layer(3).err(),
+            Some(BasisError::LayerIndexOutOfRange { index: 3, total: 3 })
+        );
+    }
+
+    #[test]
+    fn encode_video() {
+        let frames: Vec<Vec<u8>> = (0..4).map(|i| vec![i * 60; 8 * 8 * 4]).collect();
+        let params = CompressorParams::new()
+            .texture_type(TextureType::VideoFrames)
+            .us_per_frame(33_333);
+        let mut compressor = Compressor::new(params);
+        for frame in &frames {
+            compressor.add_image_rgba(frame, 8, 8).unwrap();
+        }
+        let data = compressor.compress().unwrap();
+
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding(&data).unwrap();
+        assert_eq!(file.get_texture_type(), TextureType::VideoFrames);
+        assert_eq!(file.get_file_info().us_per_frame, 33_333);
+
+        let mut decoder = file.video_decoder(0).unwrap();
+        let mut decoded = 0;
+        while let Some(frame) = decoder.next_frame(OutputFormat::RGBA32).unwrap() {
+            assert_eq!(frame.len(), 8 * 8 * 4);
+            decoded += 1;
+        }
+        assert_eq!(decoded, 4);
+    }
+
+    #[test]
+    fn encode_with_pool() {
+        let rgba = [128; 64 * 64 * 4];
+        let mut pool = JobPool::new(4).unwrap();
+        assert_eq!(pool.thread_count(), 4);
+        for params in &[
+            CompressorParams::new(),
+            CompressorParams::new().uastc(UastcLevel::Fastest),
+        ] {
+            let mut compressor = Compressor::new(*params);
+            compressor.add_image_rgba(&rgba, 64, 64).unwrap();
+            let data = compressor.compress_with_pool(&mut pool).unwrap();
+            assert!(Transcoder::open(&data).is_ok());
+        }
+    }
+
+    #[test]
+    fn encode_with_selector_codebook() {
+        let entries = unsafe {
+            slice::from_raw_parts(
+                &basist::g_global_selector_cb as *const _ as *const u32,
+                basist::g_global_selector_cb_size as usize,
+            )
+        };
+        let codebook = Arc::new(SelectorCodebook::new(entries).unwrap());
+
+        let rgba: Vec<u8> = (0..32 * 32 * 4).map(|i| (i * 7) as u8).collect();
+        let mut compressor = Compressor::new(CompressorParams::new().global_selector_palette(true));
+        compressor.set_selector_codebook(codebook.clone());
+        compressor.add_image_rgba(&rgba, 32, 32).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let mut transcoder = BasisTranscoder::with_selector_codebook(codebook);
+        let file = transcoder.start_transcoding(&data).unwrap();
+        let pixels = file
+            .transcode_image_level_to_vec(0, 0, OutputFormat::RGBA32)
+            .unwrap();
+        assert_eq!(pixels.len(), rgba.len());
+    }
+}
@@ -0,0 +1,149 @@
+//! Owned handles to .basis and .ktx2 files, which keep the file data alongside its transcoder.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{BasisError, Transcoder};
+
+/// A .basis or .ktx2 file that owns both its data and the [`Transcoder`] for it, so it can be
+/// stored without borrowing from anything.
+///
+/// `BasisFile` is `'static` and `Send`, which makes it suitable for asset caches and async
+/// tasks. [`from_arc`](Self::from_arc) shares data that other parts of a program also hold.
+pub struct BasisFile {
+    /// Borrows from `data`, which is why it must be declared, and so dropped, first. The
+    /// `'static` lifetime never escapes: [`transcoder`](Self::transcoder) shortens it to the
+    /// lifetime of `self`.
+    transcoder: Transcoder<'static>,
+    data: Data,
+}
+
+/// Storage of the file contents. The bytes stay at the same address when the storage is moved.
+enum Data {
+    Buffer(Vec<u8>),
+    Shared(Arc<[u8]>),
+    #[cfg(feature = "mmap")]
+    Mmap(memmap2::Mmap),
+}
+
+impl Data {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Data::Buffer(buffer) => buffer,
+            Data::Shared(shared) => shared,
+            #[cfg(feature = "mmap")]
+            Data::Mmap(mmap) => mmap,
+        }
+    }
+}
+
+impl BasisFile {
+    /// Open the file at `path`. With the `mmap` feature the file is memory mapped, and
+    /// otherwise read into memory.
+    ///
+    /// A mapped file must not be modified while it is open, much like with any other memory
+    /// mapping; use [`read`](Self::read) when that can't be guaranteed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, BasisError> {
+        #[cfg(feature = "mmap")]
+        {
+            let file = File::open(path)?;
+            let mmap = unsafe { memmap2::Mmap::map(&file)? };
+            Self::with_data(Data::Mmap(mmap))
+        }
+        #[cfg(not(feature = "mmap"))]
+        {
+            Self::read(path)
+        }
+    }
+
+    /// Read the whole file at `path` into memory and open it.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, BasisError> {
+        let mut data = Vec::new();
+        File::open(path)?.read_to_end(&mut data)?;
+        Self::from_vec(data)
+    }
+
+    /// Open a file that is already in memory.
+    pub fn from_vec(data: Vec<u8>) -> Result<Self, BasisError> {
+        Self::with_data(Data::Buffer(data))
+    }
+
+    /// Open a file in memory shared through an [`Arc`], without copying it.
+    pub fn from_arc(data: Arc<[u8]>) -> Result<Self, BasisError> {
+        Self::with_data(Data::Shared(data))
+    }
+
+    fn with_data(data: Data) -> Result<Self, BasisError> {
+        let bytes = data.bytes();
+        // Moving `data` into the returned value doesn't move the bytes, and the transcoder is
+        // dropped before them.
+        let bytes: &'static [u8] = unsafe { &*(bytes as *const [u8]) };
+        Ok(Self {
+            transcoder: Transcoder::open(bytes)?,
+            data,
+        })
+    }
+
+    /// Return the transcoder for the file.
+    pub fn transcoder(&self) -> &Transcoder<'_> {
+        &self.transcoder
+    }
+
+    /// Return the contents of the file.
+    pub fn data(&self) -> &[u8] {
+        self.data.bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_missing_file() {
+        match BasisFile::open("does/not/exist.basis") {
+            Err(BasisError::Io(kind)) => assert_eq!(kind, std::io::ErrorKind::NotFound),
+            _ => panic!("expected an I/O error"),
+        }
+        assert_eq!(
+            BasisFile::from_vec(b"not a texture".to_vec()).err(),
+            Some(BasisError::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn basis_file_is_send_and_static() {
+        fn assert_send_static<T: Send + 'static>() {}
+        assert_send_static::<BasisFile>();
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_and_open() {
+        use crate::{Compressor, CompressorParams};
+
+        let rgba = [64; 8 * 8 * 4];
+        let mut compressor = Compressor::new(CompressorParams::new());
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let path = std::env::temp_dir().join(format!("basisu-open-{}.basis", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let file = BasisFile::open(&path).unwrap();
+        let read = BasisFile::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(file.data(), &data[..]);
+        assert_eq!(read.data(), &data[..]);
+        assert_eq!(file.transcoder().get_total_images(), 1);
+        assert_eq!(file.transcoder().level_dimensions(0, 0), Ok((8, 8)));
+
+        let shared: Arc<[u8]> = data.into();
+        let file = BasisFile::from_arc(shared.clone()).unwrap();
+        assert_eq!(file.data().as_ptr(), shared.as_ptr());
+        let handle = std::thread::spawn(move || file.transcoder().get_total_images());
+        assert_eq!(handle.join().unwrap(), 1);
+    }
+}
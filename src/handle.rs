@@ -0,0 +1,248 @@
+//! Typed handles to the images and levels of a file being transcoded.
+//!
+//! These carry their indices and cached metadata, so code that walks a file doesn't need to pass
+//! around `(image_index, level_index)` pairs that are easy to mix up.
+
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+use core::ops::Range;
+
+use crate::{
+    BasisError, BasisFileTranscoder, DecodeFlags, ImageInfo, LevelInfo, OutputFormat, TextureType,
+    TranscodeParams,
+};
+
+/// An image of a file being transcoded, along with its metadata.
+#[derive(Copy, Clone)]
+pub struct Image<'a> {
+    file: &'a BasisFileTranscoder<'a>,
+    info: ImageInfo,
+}
+
+/// A mip level of an image, along with its metadata.
+#[derive(Copy, Clone)]
+pub struct Level<'a> {
+    file: &'a BasisFileTranscoder<'a>,
+    info: LevelInfo,
+}
+
+/// A face of a cubemap. Cubemap array files store the faces of each cubemap as consecutive
+/// images in this order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CubeFace {
+    /// The +X face.
+    PositiveX,
+    /// The -X face.
+    NegativeX,
+    /// The +Y face.
+    PositiveY,
+    /// The -Y face.
+    NegativeY,
+    /// The +Z face.
+    PositiveZ,
+    /// The -Z face.
+    NegativeZ,
+}
+
+impl CubeFace {
+    /// All faces, in the order they are stored.
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+}
+
+impl<'a> BasisFileTranscoder<'a> {
+    /// Return an iterator over handles to all images in the file, in order.
+    pub fn images(&self) -> impl Iterator<Item = Image<'_>> + '_ {
+        self.metadata
+            .images
+            .iter()
+            .map(move |&info| Image { file: self, info })
+    }
+
+    /// Return a handle to the image with index `image_index`.
+    pub fn image(&self, image_index: u32) -> Result<Image<'_>, BasisError> {
+        Ok(Image {
+            file: self,
+            info: self.get_image_info(image_index)?,
+        })
+    }
+
+    /// Return the number of layers in a 2D array file.
+    ///
+    /// Fails with [`BasisError::InvalidArgument`] if the file isn't a 2D array.
+    pub fn total_layers(&self) -> Result<u32, BasisError> {
+        if self.get_texture_type() != TextureType::Texture2DArray {
+            return Err(BasisError::InvalidArgument);
+        }
+        Ok(self.get_total_images())
+    }
+
+    /// Return a handle to the layer with index `layer_index` of a 2D array file. Each layer is
+    /// stored as one image, so its mip levels are available through [`Image::level`].
+    pub fn layer(&self, layer_index: u32) -> Result<Image<'_>, BasisError> {
+        let total = self.total_layers()?;
+        if layer_index >= total {
+            return Err(BasisError::LayerIndexOutOfRange {
+                index: layer_index,
+                total,
+            });
+        }
+        self.image(layer_index)
+    }
+
+    /// Return an iterator over handles to all layers of a 2D array file, in order. Yields nothing
+    /// if the file isn't a 2D array.
+    pub fn layers(&self) -> impl Iterator<Item = Image<'_>> + '_ {
+        let is_array = self.get_texture_type() == TextureType::Texture2DArray;
+        self.images().filter(move |_| is_array)
+    }
+
+    /// Return the number of cubemaps in a cubemap array file.
+    ///
+    /// Fails with [`BasisError::InvalidArgument`] if the file doesn't hold cubemaps, and with
+    /// [`BasisError::InvalidFileContents`] if its image count isn't a multiple of six.
+    pub fn total_cubemaps(&self) -> Result<u32, BasisError> {
+        if self.get_texture_type() != TextureType::CubemapArray {
+            return Err(BasisError::InvalidArgument);
+        }
+        let total_images = self.get_total_images();
+        if total_images % 6 != 0 {
+            return Err(BasisError::InvalidFileContents);
+        }
+        Ok(total_images / 6)
+    }
+
+    /// Return a handle to the image holding `face` of the cubemap with index `cubemap_index`.
+    ///
+    /// As in .ktx2 files, each cubemap counts as one array layer, so an out of range
+    /// `cubemap_index` fails with [`BasisError::LayerIndexOutOfRange`].
+    pub fn face(&self, cubemap_index: u32, face: CubeFace) -> Result<Image<'_>, BasisError> {
+        let total = self.total_cubemaps()?;
+        if cubemap_index >= total {
+            return Err(BasisError::LayerIndexOutOfRange {
+                index: cubemap_index,
+                total,
+            });
+        }
+        self.image(cubemap_index * 6 + face as u32)
+    }
+
+    /// Return handles to all six faces of the cubemap with index `cubemap_index`, in
+    /// [`CubeFace::ALL`] order.
+    pub fn faces(&self, cubemap_index: u32) -> Result<[Image<'_>; 6], BasisError> {
+        let mut faces = [self.face(cubemap_index, CubeFace::PositiveX)?; 6];
+        for (face, image) in CubeFace::ALL.iter().zip(faces.iter_mut()).skip(1) {
+            *image = self.face(cubemap_index, *face)?;
+        }
+        Ok(faces)
+    }
+}
+
+impl<'a> Image<'a> {
+    /// Index of the image within the file.
+    pub fn index(&self) -> u32 {
+        self.info.image_index
+    }
+
+    /// Metadata of the image.
+    pub fn info(&self) -> &ImageInfo {
+        &self.info
+    }
+
+    /// Return an iterator over handles to all mip levels of the image, starting with the largest.
+    pub fn levels(&self) -> impl Iterator<Item = Level<'a>> {
+        let file = self.file;
+        file.metadata.levels[self.info.image_index as usize]
+            .iter()
+            .map(move |&info| Level { file, info })
+    }
+
+    /// Return a handle to the mip level with index `level_index`.
+    pub fn level(&self, level_index: u32) -> Result<Level<'a>, BasisError> {
+        Ok(Level {
+            file: self.file,
+            info: self.file.level_info(self.info.image_index, level_index)?,
+        })
+    }
+}
+
+impl<'a> Level<'a> {
+    /// Index of the image the level belongs to.
+    pub fn image_index(&self) -> u32 {
+        self.info.image_index
+    }
+
+    /// Index of the level within its image.
+    pub fn level_index(&self) -> u32 {
+        self.info.level_index
+    }
+
+    /// Metadata of the level.
+    pub fn info(&self) -> &LevelInfo {
+        &self.info
+    }
+
+    /// Return a handle to the image the level belongs to.
+    pub fn image(&self) -> Result<Image<'a>, BasisError> {
+        self.file.image(self.info.image_index)
+    }
+
+    /// Range of file bytes holding the compressed data of this level.
+    pub fn data_range(&self) -> Result<Range<usize>, BasisError> {
+        self.file
+            .level_data_range(self.info.image_index, self.info.level_index)
+    }
+
+    /// Number of bytes needed to hold this level when transcoded to `output_format`.
+    pub fn required_output_size(&self, output_format: OutputFormat) -> Result<usize, BasisError> {
+        output_format.image_size(self.info.orig_width, self.info.orig_height)
+    }
+
+    /// Transcode this level into a newly allocated buffer.
+    pub fn transcode(&self, output_format: OutputFormat) -> Result<Vec<u8>, BasisError> {
+        self.file.transcode_image_level_to_vec(
+            self.info.image_index,
+            self.info.level_index,
+            output_format,
+        )
+    }
+
+    /// Transcode this level into the provided output buffer.
+    pub fn transcode_into(
+        &self,
+        output: &mut [u8],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+    ) -> Result<(), BasisError> {
+        self.file.transcode_image_level_with_params(
+            self.info.image_index,
+            self.info.level_index,
+            output,
+            output_format,
+            params,
+        )
+    }
+
+    /// Transcode this level into possibly uninitialized memory, returning the number of bytes
+    /// written. See [`BasisFileTranscoder::transcode_image_level_uninit`].
+    pub fn transcode_uninit(
+        &self,
+        output: &mut [MaybeUninit<u8>],
+        output_format: OutputFormat,
+        decode_flags: DecodeFlags,
+    ) -> Result<usize, BasisError> {
+        self.file.transcode_image_level_uninit(
+            self.info.image_index,
+            self.info.level_index,
+            output,
+            output_format,
+            decode_flags,
+        )
+    }
+}
@@ -0,0 +1,475 @@
+//! Parser for the .basis file header and slice descriptors, written in safe Rust.
+//!
+//! Nothing in this module calls into the C++ transcoder, which makes it usable for inspecting
+//! files where the transcoder isn't available and keeps validation of untrusted headers auditable.
+
+use crate::BasisError;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use core::ops::Range;
+
+/// Size in bytes of the file header.
+pub const HEADER_SIZE: usize = 77;
+/// Size in bytes of each slice descriptor.
+pub const SLICE_DESCRIPTOR_SIZE: usize = 23;
+
+const SIGNATURE: u32 = ((b'B' as u32) << 8) | b's' as u32;
+const FIRST_VERSION: u16 = 0x10;
+const LATEST_VERSION: u16 = 0x13;
+const TOTAL_TEXTURE_TYPES: u8 = 5;
+
+bitflags! {
+    /// Flags stored in the file header.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct HeaderFlags: u16 {
+        /// The file uses ETC1S compression, as opposed to UASTC.
+        const ETC1S = 1;
+        /// The images were vertically flipped when the file was encoded.
+        const Y_FLIPPED = 2;
+        /// Each ETC1S image level is stored as a color slice followed by an alpha slice.
+        const HAS_ALPHA_SLICES = 4;
+    }
+}
+
+bitflags! {
+    /// Flags stored in each slice descriptor.
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct SliceFlags: u8 {
+        /// The slice holds alpha data.
+        const HAS_ALPHA = 1;
+        /// The slice is a video I-frame.
+        const FRAME_IS_I_FRAME = 2;
+    }
+}
+
+/// The fixed size header at the start of every .basis file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Header {
+    /// File format version.
+    pub version: u16,
+    /// Size of this header in bytes.
+    pub header_size: u16,
+    /// CRC16 of the header bytes following this field.
+    pub header_crc16: u16,
+    /// Size of the data following the header.
+    pub data_size: u32,
+    /// CRC16 of the data following the header.
+    pub data_crc16: u16,
+    /// Total number of slices.
+    pub total_slices: u32,
+    /// Total number of images.
+    pub total_images: u32,
+    /// Raw `basis_tex_format`: 0 for ETC1S, 1 for UASTC 4x4.
+    pub tex_format: u8,
+    /// Header flags.
+    pub flags: HeaderFlags,
+    /// Raw `basis_texture_type` value.
+    pub tex_type: u8,
+    /// Microseconds per frame, only meaningful for video files.
+    pub us_per_frame: u32,
+    /// First user defined field.
+    pub userdata0: u32,
+    /// Second user defined field.
+    pub userdata1: u32,
+    /// Number of entries in the ETC1S endpoint codebook.
+    pub total_endpoints: u16,
+    /// File offset of the endpoint codebook.
+    pub endpoint_cb_file_ofs: u32,
+    /// Size of the endpoint codebook.
+    pub endpoint_cb_file_size: u32,
+    /// Number of entries in the ETC1S selector codebook.
+    pub total_selectors: u16,
+    /// File offset of the selector codebook.
+    pub selector_cb_file_ofs: u32,
+    /// Size of the selector codebook.
+    pub selector_cb_file_size: u32,
+    /// File offset of the Huffman tables.
+    pub tables_file_ofs: u32,
+    /// Size of the Huffman tables.
+    pub tables_file_size: u32,
+    /// File offset of the slice descriptors.
+    pub slice_desc_file_ofs: u32,
+    /// File offset of the extended data, if any.
+    pub extended_file_ofs: u32,
+    /// Size of the extended data.
+    pub extended_file_size: u32,
+}
+
+/// Describes where the compressed data of one slice is stored.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SliceDescriptor {
+    /// Index of the image the slice belongs to.
+    pub image_index: u32,
+    /// Mip level of the slice within its image.
+    pub level_index: u8,
+    /// Slice flags.
+    pub flags: SliceFlags,
+    /// Width of the level before padding to a multiple of 4.
+    pub orig_width: u16,
+    /// Height of the level before padding to a multiple of 4.
+    pub orig_height: u16,
+    /// Number of 4x4 blocks in each row.
+    pub num_blocks_x: u16,
+    /// Number of 4x4 blocks in each column.
+    pub num_blocks_y: u16,
+    /// File offset of the compressed slice data.
+    pub file_ofs: u32,
+    /// Size of the compressed slice data.
+    pub file_size: u32,
+    /// CRC16 of the decoded slice data.
+    pub slice_data_crc16: u16,
+}
+
+/// The slices holding one mip level of an image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelDescriptor {
+    /// Mip level index.
+    pub level_index: u32,
+    /// Index of the slice holding the color data, or all data for UASTC files.
+    pub slice_index: u32,
+    /// Index of the separate alpha slice of ETC1S files with alpha.
+    pub alpha_slice_index: Option<u32>,
+}
+
+/// A .basis header together with its slice descriptors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParsedHeader {
+    /// The file header.
+    pub header: Header,
+    /// All slice descriptors, in file order.
+    pub slices: Vec<SliceDescriptor>,
+}
+
+impl ParsedHeader {
+    /// Parse and validate the header and slice descriptors of a .basis file.
+    pub fn parse(data: &[u8]) -> Result<Self, BasisError> {
+        let header = Header::parse(data)?;
+
+        let slice_descs_start = header.slice_desc_file_ofs as usize;
+        let slice_descs_end = (header.total_slices as usize)
+            .checked_mul(SLICE_DESCRIPTOR_SIZE)
+            .and_then(|size| size.checked_add(slice_descs_start))
+            .ok_or(BasisError::InvalidHeader)?;
+        if slice_descs_start < HEADER_SIZE || slice_descs_end > data.len() {
+            return Err(BasisError::InvalidHeader);
+        }
+
+        let slices = data[slice_descs_start..slice_descs_end]
+            .chunks_exact(SLICE_DESCRIPTOR_SIZE)
+            .map(|bytes| {
+                let slice = SliceDescriptor::parse(bytes);
+                let end = slice.file_ofs as u64 + slice.file_size as u64;
+                if slice.image_index >= header.total_images || end > data.len() as u64 {
+                    return Err(BasisError::InvalidHeader);
+                }
+                Ok(slice)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let parsed = Self { header, slices };
+        if parsed.has_alpha_slices() && parsed.header.total_slices % 2 != 0 {
+            return Err(BasisError::InvalidHeader);
+        }
+        Ok(parsed)
+    }
+
+    /// Whether each image level is stored as a separate color and alpha slice.
+    pub fn has_alpha_slices(&self) -> bool {
+        self.header
+            .flags
+            .contains(HeaderFlags::ETC1S | HeaderFlags::HAS_ALPHA_SLICES)
+    }
+
+    /// Return the mip levels of the indicated image, in order.
+    pub fn image_levels(&self, image_index: u32) -> Vec<LevelDescriptor> {
+        let slices_per_level = if self.has_alpha_slices() { 2 } else { 1 };
+        let mut levels = Vec::new();
+        for (i, slice) in self.slices.iter().enumerate().step_by(slices_per_level) {
+            if slice.image_index != image_index {
+                continue;
+            }
+            levels.push(LevelDescriptor {
+                level_index: slice.level_index as u32,
+                slice_index: i as u32,
+                alpha_slice_index: if slices_per_level == 2 {
+                    Some(i as u32 + 1)
+                } else {
+                    None
+                },
+            });
+        }
+        levels
+    }
+
+    /// Return the slices holding the indicated mip level.
+    pub fn level(&self, image_index: u32, level_index: u32) -> Option<LevelDescriptor> {
+        self.image_levels(image_index)
+            .into_iter()
+            .find(|level| level.level_index == level_index)
+    }
+
+    /// Return the range of file bytes holding the compressed data of the indicated mip level. For
+    /// ETC1S files with alpha, this covers both the color and the alpha slice.
+    ///
+    /// Only these bytes (along with the header, slice descriptors and, for ETC1S, the codebooks and
+    /// tables) are needed to transcode the level, so streaming loaders can fetch just the levels
+    /// they intend to decode.
+    pub fn level_data_range(&self, image_index: u32, level_index: u32) -> Option<Range<usize>> {
+        let level = self.level(image_index, level_index)?;
+        let mut range = self.slices[level.slice_index as usize].data_range();
+        if let Some(alpha_slice_index) = level.alpha_slice_index {
+            let alpha = self.slices[alpha_slice_index as usize].data_range();
+            range = range.start.min(alpha.start)..range.end.max(alpha.end);
+        }
+        Some(range)
+    }
+
+    /// Check the header and data CRCs. This must scan the full file data.
+    pub fn verify_checksums(&self, data: &[u8]) -> bool {
+        let header_size = self.header.header_size as usize;
+        let data_end = match header_size.checked_add(self.header.data_size as usize) {
+            Some(end) if end <= data.len() => end,
+            _ => return false,
+        };
+        crc16(&data[8..header_size], 0) == self.header.header_crc16
+            && crc16(&data[header_size..data_end], 0) == self.header.data_crc16
+    }
+}
+
+impl Header {
+    /// Parse and validate the header at the start of a .basis file.
+    pub fn parse(data: &[u8]) -> Result<Self, BasisError> {
+        if data.len() < HEADER_SIZE {
+            return Err(BasisError::InvalidHeader);
+        }
+
+        let mut r = Reader(&data[..HEADER_SIZE]);
+        if r.uint(2) != SIGNATURE {
+            return Err(BasisError::InvalidHeader);
+        }
+        let version = r.uint(2) as u16;
+        let header_size = r.uint(2) as u16;
+        let header_crc16 = r.uint(2) as u16;
+        let data_size = r.uint(4);
+        let data_crc16 = r.uint(2) as u16;
+        let total_slices = r.uint(3);
+        let total_images = r.uint(3);
+        let tex_format = r.uint(1) as u8;
+        let flags = HeaderFlags::from_bits_truncate(r.uint(2) as u16);
+        let tex_type = r.uint(1) as u8;
+        let us_per_frame = r.uint(3);
+        let _reserved = r.uint(4);
+        let header = Header {
+            version,
+            header_size,
+            header_crc16,
+            data_size,
+            data_crc16,
+            total_slices,
+            total_images,
+            tex_format,
+            flags,
+            tex_type,
+            us_per_frame,
+            userdata0: r.uint(4),
+            userdata1: r.uint(4),
+            total_endpoints: r.uint(2) as u16,
+            endpoint_cb_file_ofs: r.uint(4),
+            endpoint_cb_file_size: r.uint(3),
+            total_selectors: r.uint(2) as u16,
+            selector_cb_file_ofs: r.uint(4),
+            selector_cb_file_size: r.uint(3),
+            tables_file_ofs: r.uint(4),
+            tables_file_size: r.uint(4),
+            slice_desc_file_ofs: r.uint(4),
+            extended_file_ofs: r.uint(4),
+            extended_file_size: r.uint(4),
+        };
+
+        if header.version < FIRST_VERSION
+            || header.version > LATEST_VERSION
+            || header.header_size as usize != HEADER_SIZE
+            || header_size as u64 + header.data_size as u64 > data.len() as u64
+            || header.total_slices == 0
+            || header.total_images == 0
+            || header.total_images > header.total_slices
+            || header.tex_format > 1
+            || header.tex_type >= TOTAL_TEXTURE_TYPES
+        {
+            return Err(BasisError::InvalidHeader);
+        }
+
+        Ok(header)
+    }
+}
+
+impl SliceDescriptor {
+    /// Range of file bytes holding the compressed data of the slice.
+    pub fn data_range(&self) -> Range<usize> {
+        self.file_ofs as usize..self.file_ofs as usize + self.file_size as usize
+    }
+
+    fn parse(bytes: &[u8]) -> Self {
+        let mut r = Reader(bytes);
+        SliceDescriptor {
+            image_index: r.uint(3),
+            level_index: r.uint(1) as u8,
+            flags: SliceFlags::from_bits_truncate(r.uint(1) as u8),
+            orig_width: r.uint(2) as u16,
+            orig_height: r.uint(2) as u16,
+            num_blocks_x: r.uint(2) as u16,
+            num_blocks_y: r.uint(2) as u16,
+            file_ofs: r.uint(4),
+            file_size: r.uint(4),
+            slice_data_crc16: r.uint(2) as u16,
+        }
+    }
+}
+
+/// The CRC16 variant used by .basis files.
+pub fn crc16(data: &[u8], crc: u16) -> u16 {
+    let mut crc = !crc;
+    for &byte in data {
+        let q = (byte as u16) ^ (crc >> 8);
+        let k = (q >> 4) ^ q;
+        crc = (((crc << 8) ^ k) ^ (k << 5)) ^ (k << 12);
+    }
+    !crc
+}
+
+/// Sequential reader of the packed little endian integers used in the header.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn uint(&mut self, len: usize) -> u32 {
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| (value << 8) | byte as u32)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn push(out: &mut Vec<u8>, value: u32, len: usize) {
+        out.extend_from_slice(&value.to_le_bytes()[..len]);
+    }
+
+    /// Build a minimal UASTC file with one image of `levels` mip levels, each with 16 bytes of
+    /// (meaningless) slice data.
+    pub(crate) fn test_file(levels: u32) -> Vec<u8> {
+        let slice_descs_ofs = HEADER_SIZE as u32;
+        let slice_data_ofs = slice_descs_ofs + levels * SLICE_DESCRIPTOR_SIZE as u32;
+        let data_size = slice_data_ofs + levels * 16 - HEADER_SIZE as u32;
+
+        let mut file = Vec::new();
+        push(&mut file, SIGNATURE, 2);
+        push(&mut file, LATEST_VERSION as u32, 2);
+        push(&mut file, HEADER_SIZE as u32, 2);
+        push(&mut file, 0, 2);
+        push(&mut file, data_size, 4);
+        push(&mut file, 0, 2);
+        push(&mut file, levels, 3);
+        push(&mut file, 1, 3);
+        push(&mut file, 1, 1);
+        push(&mut file, HeaderFlags::Y_FLIPPED.bits() as u32, 2);
+        push(&mut file, 0, 1);
+        push(&mut file, 0, 3);
+        push(&mut file, 0, 4);
+        push(&mut file, 0xdead, 4);
+        push(&mut file, 0xbeef, 4);
+        for len in &[2, 4, 3, 2, 4, 3, 4, 4] {
+            push(&mut file, 0, *len);
+        }
+        push(&mut file, slice_descs_ofs, 4);
+        push(&mut file, 0, 4);
+        push(&mut file, 0, 4);
+        assert_eq!(file.len(), HEADER_SIZE);
+
+        for level in 0..levels {
+            let size = 64 >> level;
+            push(&mut file, 0, 3);
+            push(&mut file, level, 1);
+            push(&mut file, 0, 1);
+            push(&mut file, size, 2);
+            push(&mut file, size, 2);
+            push(&mut file, size / 4, 2);
+            push(&mut file, size / 4, 2);
+            push(&mut file, slice_data_ofs + level * 16, 4);
+            push(&mut file, 16, 4);
+            push(&mut file, 0, 2);
+        }
+        file.resize(file.len() + levels as usize * 16, 0);
+
+        let data_crc16 = crc16(&file[HEADER_SIZE..], 0);
+        file[12..14].copy_from_slice(&data_crc16.to_le_bytes());
+        let header_crc16 = crc16(&file[8..HEADER_SIZE], 0);
+        file[6..8].copy_from_slice(&header_crc16.to_le_bytes());
+        file
+    }
+
+    #[test]
+    fn parse_header() {
+        let file = test_file(3);
+        let parsed = ParsedHeader::parse(&file).unwrap();
+        assert_eq!(parsed.header.total_images, 1);
+        assert_eq!(parsed.header.total_slices, 3);
+        assert_eq!(parsed.header.tex_format, 1);
+        assert_eq!(parsed.header.flags, HeaderFlags::Y_FLIPPED);
+        assert_eq!(
+            (parsed.header.userdata0, parsed.header.userdata1),
+            (0xdead, 0xbeef)
+        );
+        assert_eq!(parsed.slices[1].orig_width, 32);
+        assert_eq!(parsed.slices[2].num_blocks_y, 4);
+        assert_eq!(
+            parsed.level(0, 2),
+            Some(LevelDescriptor {
+                level_index: 2,
+                slice_index: 2,
+                alpha_slice_index: None
+            })
+        );
+        let data_start = HEADER_SIZE + 3 * SLICE_DESCRIPTOR_SIZE;
+        assert_eq!(
+            parsed.level_data_range(0, 1),
+            Some(data_start + 16..data_start + 32)
+        );
+        assert_eq!(parsed.level_data_range(0, 3), None);
+        assert!(parsed.verify_checksums(&file));
+    }
+
+    #[test]
+    fn reject_invalid_headers() {
+        let file = test_file(1);
+        assert_eq!(
+            ParsedHeader::parse(&file[..40]),
+            Err(BasisError::InvalidHeader)
+        );
+        assert_eq!(
+            ParsedHeader::parse(&file[..file.len() - 1]),
+            Err(BasisError::InvalidHeader)
+        );
+
+        let mut bad_signature = file.clone();
+        bad_signature[0] = b'x';
+        assert_eq!(
+            ParsedHeader::parse(&bad_signature),
+            Err(BasisError::InvalidHeader)
+        );
+
+        let mut corrupt = file;
+        *corrupt.last_mut().unwrap() ^= 1;
+        assert!(!ParsedHeader::parse(&corrupt)
+            .unwrap()
+            .verify_checksums(&corrupt));
+    }
+}
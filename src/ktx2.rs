@@ -0,0 +1,1304 @@
+//! Support for .ktx2 files holding Basis Universal texture data, the container used by glTF.
+//!
+//! The container header and level index are parsed in safe Rust, while decoding the image data
+//! is left to the C++ `ktx2_transcoder`.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem::{self, MaybeUninit};
+use core::time::Duration;
+
+use basisu_sys::*;
+
+use crate::{
+    data_len, eac_a8_blocks, format_supported, global_codebook, global_codebook_ptr, init,
+    output_blocks, postprocess, BasisError, DecodeFlags, MaybeOwned, OutputFormat,
+    SelectorCodebook, TexFormat, TranscodeParams, Workspace,
+};
+
+/// The identifier every .ktx2 file starts with.
+pub const IDENTIFIER: [u8; 12] = [
+    0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n',
+];
+/// Size in bytes of the fixed part of the header, which is followed by the level index.
+pub const HEADER_SIZE: usize = 80;
+/// Size in bytes of each level index entry.
+pub const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+
+/// How the mip level data of a .ktx2 file is supercompressed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SupercompressionScheme {
+    /// No supercompression. Used for UASTC.
+    None,
+    /// BasisLZ, the supercompression of ETC1S data.
+    BasisLZ,
+    /// Zstandard, commonly applied on top of UASTC. Decoding it requires the `zstd` feature.
+    Zstandard,
+    /// Zlib.
+    Zlib,
+}
+
+impl SupercompressionScheme {
+    fn from_raw(raw: u32) -> Option<Self> {
+        Some(match raw {
+            0 => SupercompressionScheme::None,
+            1 => SupercompressionScheme::BasisLZ,
+            2 => SupercompressionScheme::Zstandard,
+            3 => SupercompressionScheme::Zlib,
+            _ => return None,
+        })
+    }
+}
+
+/// The fixed size part of a .ktx2 header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Header {
+    /// Vulkan format of the texture data. Zero for Basis Universal data, which has no fixed format.
+    pub vk_format: u32,
+    /// Size of the data type in bytes, used for endianness conversion.
+    pub type_size: u32,
+    /// Width of the base level in pixels.
+    pub pixel_width: u32,
+    /// Height of the base level in pixels.
+    pub pixel_height: u32,
+    /// Depth of the base level in pixels, or zero for 2D textures.
+    pub pixel_depth: u32,
+    /// Number of array layers, or zero for non-array textures.
+    pub layer_count: u32,
+    /// Number of cubemap faces: six for cubemaps, one otherwise.
+    pub face_count: u32,
+    /// Number of mip levels, or zero to request that they be generated at load time.
+    pub level_count: u32,
+    /// Supercompression applied to the level data.
+    pub supercompression_scheme: SupercompressionScheme,
+    /// File offset of the data format descriptor.
+    pub dfd_byte_offset: u32,
+    /// Size in bytes of the data format descriptor.
+    pub dfd_byte_length: u32,
+    /// File offset of the key/value data.
+    pub kvd_byte_offset: u32,
+    /// Size in bytes of the key/value data.
+    pub kvd_byte_length: u32,
+    /// File offset of the supercompression global data.
+    pub sgd_byte_offset: u64,
+    /// Size in bytes of the supercompression global data.
+    pub sgd_byte_length: u64,
+}
+
+/// Location of one mip level within a .ktx2 file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelIndex {
+    /// File offset of the level data.
+    pub byte_offset: u64,
+    /// Size in bytes of the level data as stored in the file.
+    pub byte_length: u64,
+    /// Size in bytes of the level data once supercompression is removed.
+    pub uncompressed_byte_length: u64,
+}
+
+/// A .ktx2 header together with its level index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParsedHeader {
+    /// The fixed size header.
+    pub header: Header,
+    /// Location of each mip level, starting with the largest.
+    pub levels: Vec<LevelIndex>,
+}
+
+impl ParsedHeader {
+    /// Parse and validate the header and level index of a .ktx2 file.
+    pub fn parse(data: &[u8]) -> Result<Self, BasisError> {
+        if data.len() < HEADER_SIZE || data[..IDENTIFIER.len()] != IDENTIFIER {
+            return Err(BasisError::InvalidHeader);
+        }
+
+        let mut r = Reader(&data[IDENTIFIER.len()..HEADER_SIZE]);
+        let header = Header {
+            vk_format: r.u32(),
+            type_size: r.u32(),
+            pixel_width: r.u32(),
+            pixel_height: r.u32(),
+            pixel_depth: r.u32(),
+            layer_count: r.u32(),
+            face_count: r.u32(),
+            level_count: r.u32(),
+            supercompression_scheme: SupercompressionScheme::from_raw(r.u32())
+                .ok_or(BasisError::InvalidHeader)?,
+            dfd_byte_offset: r.u32(),
+            dfd_byte_length: r.u32(),
+            kvd_byte_offset: r.u32(),
+            kvd_byte_length: r.u32(),
+            sgd_byte_offset: r.u64(),
+            sgd_byte_length: r.u64(),
+        };
+        if header.pixel_width == 0 || (header.face_count != 1 && header.face_count != 6) {
+            return Err(BasisError::InvalidHeader);
+        }
+        // Every layer and face is an image, and the image count has to fit in a `u32`.
+        header
+            .layer_count
+            .max(1)
+            .checked_mul(header.face_count)
+            .ok_or(BasisError::InvalidHeader)?;
+
+        let check_range = |offset: u64, length: u64| match offset.checked_add(length) {
+            Some(end) if end <= data.len() as u64 => Ok(()),
+            _ => Err(BasisError::InvalidHeader),
+        };
+        check_range(header.dfd_byte_offset as u64, header.dfd_byte_length as u64)?;
+        check_range(header.kvd_byte_offset as u64, header.kvd_byte_length as u64)?;
+        check_range(header.sgd_byte_offset, header.sgd_byte_length)?;
+
+        let total_levels = header.level_count.max(1) as usize;
+        let level_index_end = total_levels
+            .checked_mul(LEVEL_INDEX_ENTRY_SIZE)
+            .and_then(|size| size.checked_add(HEADER_SIZE))
+            .filter(|&end| end <= data.len())
+            .ok_or(BasisError::InvalidHeader)?;
+        let levels = data[HEADER_SIZE..level_index_end]
+            .chunks_exact(LEVEL_INDEX_ENTRY_SIZE)
+            .map(|bytes| {
+                let mut r = Reader(bytes);
+                let level = LevelIndex {
+                    byte_offset: r.u64(),
+                    byte_length: r.u64(),
+                    uncompressed_byte_length: r.u64(),
+                };
+                check_range(level.byte_offset, level.byte_length)?;
+                Ok(level)
+            })
+            .collect::<Result<Vec<_>, BasisError>>()?;
+
+        Ok(Self { header, levels })
+    }
+
+    /// Number of mip levels stored in the file.
+    pub fn total_levels(&self) -> u32 {
+        self.header.level_count.max(1)
+    }
+
+    /// Number of array layers, counting a non-array texture as a single layer.
+    pub fn total_layers(&self) -> u32 {
+        self.header.layer_count.max(1)
+    }
+
+    /// Number of cubemap faces: six for cubemaps, one otherwise.
+    pub fn total_faces(&self) -> u32 {
+        self.header.face_count
+    }
+
+    /// Return an iterator over the key/value data of the file `data` this header was parsed from.
+    pub fn key_values<'a>(&self, data: &'a [u8]) -> KeyValues<'a> {
+        let start = self.header.kvd_byte_offset as usize;
+        let end = start + self.header.kvd_byte_length as usize;
+        KeyValues(data.get(start..end).unwrap_or(&[]))
+    }
+
+    /// Return the animation data of the file `data` this header was parsed from, or `None` if the
+    /// file isn't animated.
+    pub fn anim_data(&self, data: &[u8]) -> Option<AnimData> {
+        self.key_values(data)
+            .find(|(key, _)| *key == "KTXanimData")
+            .and_then(|(_, value)| AnimData::parse(value))
+    }
+
+    /// Return the basic data format descriptor of the file `data` this header was parsed from, or
+    /// `None` if it is missing or malformed.
+    pub fn dfd(&self, data: &[u8]) -> Option<DataFormatDescriptor> {
+        let start = self.header.dfd_byte_offset as usize;
+        let end = start + self.header.dfd_byte_length as usize;
+        DataFormatDescriptor::parse(data.get(start..end)?)
+    }
+
+    /// Whether the file holds ETC1S or UASTC data, or `None` if it doesn't hold Basis Universal
+    /// data at all.
+    pub fn tex_format(&self) -> Option<TexFormat> {
+        if self.header.vk_format != 0 {
+            return None;
+        }
+        match self.header.supercompression_scheme {
+            SupercompressionScheme::BasisLZ => Some(TexFormat::ETC1S),
+            SupercompressionScheme::None | SupercompressionScheme::Zstandard => {
+                Some(TexFormat::UASTC4x4)
+            }
+            SupercompressionScheme::Zlib => None,
+        }
+    }
+}
+
+/// Playback information of an animated .ktx2 file, stored under the `KTXanimData` key. Each
+/// array layer is one frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnimData {
+    /// Duration of each frame, in units of `1 / timescale` seconds.
+    pub duration: u32,
+    /// Number of time units per second.
+    pub timescale: u32,
+    /// Number of times to play the animation, or zero to loop forever.
+    pub loop_count: u32,
+}
+
+impl AnimData {
+    fn parse(value: &[u8]) -> Option<Self> {
+        if value.len() != 12 {
+            return None;
+        }
+        let mut r = Reader(value);
+        Some(AnimData {
+            duration: r.u32(),
+            timescale: r.u32(),
+            loop_count: r.u32(),
+        })
+    }
+
+    /// Time each frame should be displayed for, or `None` if the timescale is zero.
+    pub fn frame_duration(&self) -> Option<Duration> {
+        if self.timescale == 0 {
+            return None;
+        }
+        let nanos = self.duration as u64 * 1_000_000_000 / self.timescale as u64;
+        Some(Duration::from_nanos(nanos))
+    }
+}
+
+/// Color model of a data format descriptor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorModel {
+    /// No color model is specified.
+    Unspecified,
+    /// Red, green, blue, stencil, depth and alpha channels.
+    Rgbsda,
+    /// ETC1S data. Its channels are RGB, RRR, GGG or AAA.
+    Etc1s,
+    /// UASTC data. Its channels are RGB, RGBA, RRR, RRRG or RG.
+    Uastc,
+    /// Any other `KHR_DF_MODEL_*` value.
+    Other(u8),
+}
+
+impl ColorModel {
+    pub(crate) fn from_raw(raw: u8) -> Self {
+        match raw {
+            0 => ColorModel::Unspecified,
+            1 => ColorModel::Rgbsda,
+            163 => ColorModel::Etc1s,
+            166 => ColorModel::Uastc,
+            _ => ColorModel::Other(raw),
+        }
+    }
+
+    fn to_raw(self) -> u8 {
+        match self {
+            ColorModel::Unspecified => 0,
+            ColorModel::Rgbsda => 1,
+            ColorModel::Etc1s => 163,
+            ColorModel::Uastc => 166,
+            ColorModel::Other(raw) => raw,
+        }
+    }
+}
+
+/// Transfer function of a data format descriptor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TransferFunction {
+    /// No transfer function is specified.
+    Unspecified,
+    /// The color channels are linear.
+    Linear,
+    /// The color channels use the sRGB transfer function, so the texture should be viewed through
+    /// an sRGB format to be decoded correctly.
+    Srgb,
+    /// Any other `KHR_DF_TRANSFER_*` value.
+    Other(u8),
+}
+
+impl TransferFunction {
+    fn from_raw(raw: u8) -> Self {
+        match raw {
+            0 => TransferFunction::Unspecified,
+            1 => TransferFunction::Linear,
+            2 => TransferFunction::Srgb,
+            _ => TransferFunction::Other(raw),
+        }
+    }
+
+    fn to_raw(self) -> u8 {
+        match self {
+            TransferFunction::Unspecified => 0,
+            TransferFunction::Linear => 1,
+            TransferFunction::Srgb => 2,
+            TransferFunction::Other(raw) => raw,
+        }
+    }
+}
+
+/// ETC1S channel id of the alpha slice.
+const ETC1S_CHANNEL_AAA: u8 = 15;
+/// UASTC channel id of RGBA data.
+const UASTC_CHANNEL_RGBA: u8 = 3;
+/// UASTC channel id of RRRG data, whose alpha channel holds the second channel.
+const UASTC_CHANNEL_RRRG: u8 = 5;
+
+/// One sample of a data format descriptor, describing the bits of a texel block that hold a
+/// channel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sample {
+    /// Offset of the first bit of the sample within the texel block.
+    pub bit_offset: u16,
+    /// Number of bits in the sample.
+    pub bit_length: u32,
+    /// Channel id, interpreted according to the color model.
+    pub channel_id: u8,
+    /// The channel is linear even though the descriptor uses a non-linear transfer function.
+    pub linear: bool,
+    /// The channel holds an exponent.
+    pub exponent: bool,
+    /// The channel holds signed values.
+    pub signed: bool,
+    /// The channel holds floating point values.
+    pub float: bool,
+    /// Value of the sample that maps to the lower end of the channel range.
+    pub lower: u32,
+    /// Value of the sample that maps to the upper end of the channel range.
+    pub upper: u32,
+}
+
+/// The basic block of a .ktx2 data format descriptor, which says how to interpret decoded texels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DataFormatDescriptor {
+    /// Color model the samples are interpreted in.
+    pub color_model: ColorModel,
+    /// Color primaries as a `KHR_DF_PRIMARIES_*` value. 1 is BT.709, which sRGB also uses.
+    pub color_primaries: u8,
+    /// Transfer function applied to the color channels.
+    pub transfer_function: TransferFunction,
+    /// Whether the color channels are premultiplied by alpha.
+    pub alpha_premultiplied: bool,
+    /// Size of a texel block in pixels, as width, height, depth and layers.
+    pub texel_block_dimensions: [u32; 4],
+    /// Number of bytes in each plane of a texel block.
+    pub bytes_planes: [u8; 8],
+    /// Channel layout of a texel block.
+    pub samples: Vec<Sample>,
+}
+
+impl DataFormatDescriptor {
+    /// Size in bytes of the basic block before its samples.
+    const BASIC_BLOCK_HEADER_SIZE: usize = 24;
+    /// Size in bytes of each sample.
+    const SAMPLE_SIZE: usize = 16;
+
+    fn parse(dfd: &[u8]) -> Option<Self> {
+        if dfd.len() < 4 + Self::BASIC_BLOCK_HEADER_SIZE {
+            return None;
+        }
+        let mut r = Reader(&dfd[4..]);
+        let id = r.u32();
+        let (vendor_id, descriptor_type) = (id & 0x1FFFF, id >> 17);
+        let block_size = (r.u32() >> 16) as usize;
+        // Only the basic descriptor block of Khronos is understood.
+        if vendor_id != 0 || descriptor_type != 0 {
+            return None;
+        }
+        if block_size < Self::BASIC_BLOCK_HEADER_SIZE || 4 + block_size > dfd.len() {
+            return None;
+        }
+
+        let block = &dfd[4..4 + block_size];
+        let samples = block[Self::BASIC_BLOCK_HEADER_SIZE..]
+            .chunks_exact(Self::SAMPLE_SIZE)
+            .map(|bytes| {
+                let mut r = Reader(bytes);
+                let first = r.u32();
+                let channel_type = (first >> 24) as u8;
+                let _sample_position = r.u32();
+                Sample {
+                    bit_offset: first as u16,
+                    bit_length: (first >> 16 & 0xFF) + 1,
+                    channel_id: channel_type & 0xF,
+                    linear: channel_type & 0x10 != 0,
+                    exponent: channel_type & 0x20 != 0,
+                    signed: channel_type & 0x40 != 0,
+                    float: channel_type & 0x80 != 0,
+                    lower: r.u32(),
+                    upper: r.u32(),
+                }
+            })
+            .collect();
+
+        let mut bytes_planes = [0; 8];
+        bytes_planes.copy_from_slice(&block[16..24]);
+        Some(DataFormatDescriptor {
+            color_model: ColorModel::from_raw(block[8]),
+            color_primaries: block[9],
+            transfer_function: TransferFunction::from_raw(block[10]),
+            alpha_premultiplied: block[11] & 1 != 0,
+            texel_block_dimensions: [
+                block[12] as u32 + 1,
+                block[13] as u32 + 1,
+                block[14] as u32 + 1,
+                block[15] as u32 + 1,
+            ],
+            bytes_planes,
+            samples,
+        })
+    }
+
+    /// Serialize the descriptor as it is stored in a .ktx2 file: the total size followed by the
+    /// basic block. Sample positions are written as zero.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let block_size = Self::BASIC_BLOCK_HEADER_SIZE + self.samples.len() * Self::SAMPLE_SIZE;
+        let mut dfd = Vec::with_capacity(4 + block_size);
+        dfd.extend_from_slice(&(4 + block_size as u32).to_le_bytes());
+        // Khronos vendor id and basic descriptor type, then version 1.3 of the block.
+        dfd.extend_from_slice(&0u32.to_le_bytes());
+        dfd.extend_from_slice(&(2 | (block_size as u32) << 16).to_le_bytes());
+        dfd.extend_from_slice(&[
+            self.color_model.to_raw(),
+            self.color_primaries,
+            self.transfer_function.to_raw(),
+            self.alpha_premultiplied as u8,
+        ]);
+        dfd.extend(self.texel_block_dimensions.iter().map(|&d| (d - 1) as u8));
+        dfd.extend_from_slice(&self.bytes_planes);
+        for sample in &self.samples {
+            let channel_type = sample.channel_id
+                | (sample.linear as u8) << 4
+                | (sample.exponent as u8) << 5
+                | (sample.signed as u8) << 6
+                | (sample.float as u8) << 7;
+            let first = sample.bit_offset as u32
+                | (sample.bit_length - 1) << 16
+                | (channel_type as u32) << 24;
+            for value in &[first, 0, sample.lower, sample.upper] {
+                dfd.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        dfd
+    }
+
+    /// Whether the color channels are sRGB encoded. Such textures should be transcoded to a
+    /// format that is viewed as sRGB, like `VK_FORMAT_BC7_SRGB_BLOCK`.
+    pub fn is_srgb(&self) -> bool {
+        self.transfer_function == TransferFunction::Srgb
+    }
+
+    /// Whether the ETC1S or UASTC data described has an alpha channel.
+    pub fn has_alpha(&self) -> bool {
+        let has_channel = |channel_id| self.samples.iter().any(|s| s.channel_id == channel_id);
+        match self.color_model {
+            ColorModel::Etc1s => has_channel(ETC1S_CHANNEL_AAA),
+            ColorModel::Uastc => has_channel(UASTC_CHANNEL_RGBA) || has_channel(UASTC_CHANNEL_RRRG),
+            _ => false,
+        }
+    }
+}
+
+/// Iterator over the key/value pairs of a .ktx2 file, returned by [`ParsedHeader::key_values`].
+///
+/// Values are raw bytes. Most standard values, like `KTXwriter` and `KTXorientation`, are strings
+/// that include their NUL terminator. Iteration ends early at the first malformed entry, and
+/// entries whose key isn't valid UTF-8 are skipped.
+#[derive(Clone, Debug)]
+pub struct KeyValues<'a>(&'a [u8]);
+
+impl<'a> Iterator for KeyValues<'a> {
+    type Item = (&'a str, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.0.len() < 4 {
+                return None;
+            }
+            let (len, rest) = self.0.split_at(4);
+            let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+            if len > rest.len() {
+                self.0 = &[];
+                return None;
+            }
+            let (entry, rest) = rest.split_at(len);
+            // Entries are padded to a multiple of 4 bytes.
+            let padding = (4 - len % 4) % 4;
+            self.0 = rest.get(padding..).unwrap_or(&[]);
+
+            let nul = match entry.iter().position(|&b| b == 0) {
+                Some(nul) => nul,
+                None => {
+                    self.0 = &[];
+                    return None;
+                }
+            };
+            if let Ok(key) = core::str::from_utf8(&entry[..nul]) {
+                return Some((key, &entry[nul + 1..]));
+            }
+        }
+    }
+}
+
+/// Sequential reader of the little endian integers used in the header.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn u32(&mut self) -> u32 {
+        let (bytes, rest) = self.0.split_at(4);
+        self.0 = rest;
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn u64(&mut self) -> u64 {
+        self.u32() as u64 | (self.u32() as u64) << 32
+    }
+}
+
+/// Information about a single mip level of one layer and face of a .ktx2 file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ktx2LevelInfo {
+    /// Index of the mip level.
+    pub level_index: u32,
+    /// Index of the array layer.
+    pub layer_index: u32,
+    /// Index of the cubemap face.
+    pub face_index: u32,
+    /// Width of the level before padding to a multiple of the block size.
+    pub orig_width: u32,
+    /// Height of the level before padding to a multiple of the block size.
+    pub orig_height: u32,
+    /// Width of the level rounded up to a multiple of 4.
+    pub width: u32,
+    /// Height of the level rounded up to a multiple of 4.
+    pub height: u32,
+    /// Number of 4x4 blocks in each row of the level.
+    pub num_blocks_x: u32,
+    /// Number of 4x4 blocks in each column of the level.
+    pub num_blocks_y: u32,
+    /// Total number of 4x4 blocks in the level.
+    pub total_blocks: u32,
+    /// Whether the level has alpha data.
+    pub alpha_flag: bool,
+    /// Whether the level is an I-frame (video files only).
+    pub iframe_flag: bool,
+}
+
+/// A transcoder for converting .ktx2 files holding Basis Universal data to GPU texture formats.
+///
+/// The second field keeps a custom selector codebook alive for as long as the transcoder uses it.
+pub struct Ktx2Transcoder(*mut basist::ktx2_transcoder, Option<Arc<SelectorCodebook>>);
+
+/// A .ktx2 file that is ready to be transcoded, returned by [`Ktx2Transcoder::start_transcoding`].
+///
+/// Like [`BasisFileTranscoder`](crate::BasisFileTranscoder), every transcode call uses its own
+/// fresh decoder state, so video P-frames will not decode correctly.
+pub struct Ktx2FileTranscoder<'a> {
+    transcoder: MaybeOwned<&'a mut Ktx2Transcoder, Ktx2Transcoder>,
+    header: ParsedHeader,
+    tex_format: TexFormat,
+    /// At most `u32::MAX` bytes long, which `start_transcoding` checks.
+    data: &'a [u8],
+}
+
+/// Scratch state used by the decoder, which also holds the last level it decompressed.
+pub(crate) struct Ktx2TranscoderState(*mut basist::ktx2_transcoder_state);
+
+// The state is uniquely owned heap memory that is only accessed through this handle.
+unsafe impl Send for Ktx2TranscoderState {}
+
+impl Ktx2TranscoderState {
+    pub(crate) fn new() -> Self {
+        Self(unsafe { shim::new_ktx2_transcoder_state() })
+    }
+
+    /// Make the next transcode decompress its level again, so the state can be used with a
+    /// different file.
+    pub(crate) fn reset(&mut self) {
+        unsafe { shim::reset_ktx2_transcoder_state(self.0) }
+    }
+}
+
+impl Drop for Ktx2TranscoderState {
+    fn drop(&mut self) {
+        unsafe { shim::delete_ktx2_transcoder_state(self.0) }
+    }
+}
+
+impl Ktx2Transcoder {
+    /// Create a new transcoder. The first time this (or [`BasisTranscoder::new`]) is called, it
+    /// does some library wide initialization. The built-in global selector codebook is only built
+    /// once a transcoder starts on an ETC1S file.
+    ///
+    /// [`BasisTranscoder::new`]: crate::BasisTranscoder::new
+    pub fn new() -> Self {
+        init();
+
+        Self(
+            unsafe { shim::new_ktx2_transcoder(global_codebook_ptr()) },
+            None,
+        )
+    }
+
+    /// Create a new transcoder that decodes ETC1S files using `codebook` instead of the built-in
+    /// global selector codebook.
+    pub fn with_selector_codebook(codebook: Arc<SelectorCodebook>) -> Self {
+        init();
+
+        Self(
+            unsafe { shim::new_ktx2_transcoder(codebook.as_ptr()) },
+            Some(codebook),
+        )
+    }
+
+    /// Return the custom selector codebook this transcoder was created with, if any.
+    pub fn get_selector_codebook(&self) -> Option<&Arc<SelectorCodebook>> {
+        self.1.as_ref()
+    }
+
+    /// Parse the provided .ktx2 file and prepare to transcode it.
+    ///
+    /// As with [`BasisTranscoder::start_transcoding`](crate::BasisTranscoder::start_transcoding),
+    /// a transcoder can be reused for any number of files.
+    pub fn start_transcoding<'a>(
+        &'a mut self,
+        data: &'a [u8],
+    ) -> Result<Ktx2FileTranscoder<'a>, BasisError> {
+        let (header, tex_format) = self.start(data)?;
+        Ok(Ktx2FileTranscoder {
+            transcoder: MaybeOwned::Borrowed(self),
+            header,
+            tex_format,
+            data,
+        })
+    }
+
+    /// Like `start_transcoding`, but the returned file transcoder takes ownership of `self`.
+    pub(crate) fn into_file_transcoder(
+        mut self,
+        data: &[u8],
+    ) -> Result<Ktx2FileTranscoder<'_>, BasisError> {
+        let (header, tex_format) = self.start(data)?;
+        Ok(Ktx2FileTranscoder {
+            transcoder: MaybeOwned::Owned(self),
+            header,
+            tex_format,
+            data,
+        })
+    }
+
+    fn start(&mut self, data: &[u8]) -> Result<(ParsedHeader, TexFormat), BasisError> {
+        let len = data_len(data)?;
+        let header = ParsedHeader::parse(data)?;
+        let scheme = header.header.supercompression_scheme;
+        if scheme == SupercompressionScheme::Zlib
+            || (scheme == SupercompressionScheme::Zstandard && !cfg!(feature = "zstd"))
+        {
+            return Err(BasisError::UnsupportedSupercompression(scheme));
+        }
+        let tex_format = header.tex_format().ok_or(BasisError::InvalidHeader)?;
+        if tex_format == TexFormat::ETC1S && self.1.is_none() {
+            global_codebook();
+        }
+
+        unsafe {
+            if !(*self.0).init(data.as_ptr() as *const _, len) || !(*self.0).start_transcoding() {
+                return Err(BasisError::InvalidFileContents);
+            }
+        }
+        Ok((header, tex_format))
+    }
+}
+
+// The C++ transcoder is uniquely owned heap memory. It is not `Sync` because its transcode
+// methods are non-const, so `Ktx2FileTranscoder` only ever calls them from one thread at a time.
+unsafe impl Send for Ktx2Transcoder {}
+
+impl Drop for Ktx2Transcoder {
+    fn drop(&mut self) {
+        unsafe { shim::delete_ktx2_transcoder(self.0) }
+    }
+}
+
+impl Default for Ktx2Transcoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Ktx2FileTranscoder<'a> {
+    /// Return the parsed container header.
+    pub fn header(&self) -> &ParsedHeader {
+        &self.header
+    }
+
+    /// Return the file data and the custom selector codebook of the transcoder, which is
+    /// everything needed to start an equivalent file transcoder on another thread.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn parts(&self) -> (&'a [u8], Option<Arc<SelectorCodebook>>) {
+        (self.data, self.transcoder.get_selector_codebook().cloned())
+    }
+
+    /// Return the bytes of the indicated mip level as stored in the file, still supercompressed.
+    pub fn level_data(&self, level_index: u32) -> Result<&'a [u8], BasisError> {
+        let level = self.header.levels.get(level_index as usize).ok_or(
+            BasisError::LevelIndexOutOfRange {
+                index: level_index,
+                total: self.header.total_levels(),
+            },
+        )?;
+        // `ParsedHeader::parse` checked that the range is within the file.
+        let start = level.byte_offset as usize;
+        Ok(&self.data[start..start + level.byte_length as usize])
+    }
+
+    /// Return an iterator over the key/value data of the file.
+    pub fn key_values(&self) -> KeyValues<'a> {
+        self.header.key_values(self.data)
+    }
+
+    /// Return the value stored for `key`, if any.
+    pub fn key_value(&self, key: &str) -> Option<&'a [u8]> {
+        self.key_values().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    /// Return the animation data of the file, or `None` if it isn't animated.
+    pub fn anim_data(&self) -> Option<AnimData> {
+        self.header.anim_data(self.data)
+    }
+
+    /// Return the data format descriptor of the file, or `None` if it is missing or malformed.
+    pub fn dfd(&self) -> Option<DataFormatDescriptor> {
+        self.header.dfd(self.data)
+    }
+
+    /// Return whether the file uses ETC1S or UASTC compression.
+    pub fn get_tex_format(&self) -> TexFormat {
+        self.tex_format
+    }
+
+    /// Return whether the file can be transcoded to `output_format`. This depends both on the
+    /// compression used by the file and on which formats the transcoder was built with.
+    pub fn can_transcode(&self, output_format: OutputFormat) -> bool {
+        format_supported(output_format, self.get_tex_format())
+    }
+
+    fn check_indices(
+        &self,
+        level_index: u32,
+        layer_index: u32,
+        face_index: u32,
+    ) -> Result<(), BasisError> {
+        let total = self.header.total_levels();
+        if level_index >= total {
+            return Err(BasisError::LevelIndexOutOfRange {
+                index: level_index,
+                total,
+            });
+        }
+        let total = self.header.total_layers();
+        if layer_index >= total {
+            return Err(BasisError::LayerIndexOutOfRange {
+                index: layer_index,
+                total,
+            });
+        }
+        let total = self.header.total_faces();
+        if face_index >= total {
+            return Err(BasisError::FaceIndexOutOfRange {
+                index: face_index,
+                total,
+            });
+        }
+        Ok(())
+    }
+
+    /// Return information about the indicated mip level, array layer and cubemap face.
+    pub fn level_info(
+        &self,
+        level_index: u32,
+        layer_index: u32,
+        face_index: u32,
+    ) -> Result<Ktx2LevelInfo, BasisError> {
+        self.check_indices(level_index, layer_index, face_index)?;
+
+        let mut info: basist::ktx2_image_level_info = unsafe { mem::zeroed() };
+        unsafe {
+            if !(*self.transcoder.0).get_image_level_info(
+                &mut info as *mut _,
+                level_index,
+                layer_index,
+                face_index,
+            ) {
+                return Err(BasisError::InvalidFileContents);
+            }
+        }
+
+        Ok(Ktx2LevelInfo {
+            level_index: info.m_level_index,
+            layer_index: info.m_layer_index,
+            face_index: info.m_face_index,
+            orig_width: info.m_orig_width,
+            orig_height: info.m_orig_height,
+            width: info.m_width,
+            height: info.m_height,
+            num_blocks_x: info.m_num_blocks_x,
+            num_blocks_y: info.m_num_blocks_y,
+            total_blocks: info.m_total_blocks,
+            alpha_flag: info.m_alpha_flag,
+            iframe_flag: info.m_iframe_flag,
+        })
+    }
+
+    /// Return the number of bytes needed to hold the indicated level when transcoded to
+    /// `output_format` with tightly packed rows.
+    pub fn required_output_size(
+        &self,
+        level_index: u32,
+        layer_index: u32,
+        face_index: u32,
+        output_format: OutputFormat,
+    ) -> Result<usize, BasisError> {
+        let info = self.level_info(level_index, layer_index, face_index)?;
+        output_format.image_size(info.orig_width, info.orig_height)
+    }
+
+    /// Transcode the indicated level into a newly allocated buffer.
+    pub fn transcode_image_level_to_vec(
+        &self,
+        level_index: u32,
+        layer_index: u32,
+        face_index: u32,
+        output_format: OutputFormat,
+    ) -> Result<Vec<u8>, BasisError> {
+        let size =
+            self.required_output_size(level_index, layer_index, face_index, output_format)?;
+        let mut output = Vec::with_capacity(size);
+        let len = self.transcode_image_level_uninit(
+            level_index,
+            layer_index,
+            face_index,
+            output.spare_capacity_mut(),
+            output_format,
+            DecodeFlags::empty(),
+        )?;
+        unsafe { output.set_len(len) };
+        Ok(output)
+    }
+
+    /// Transcode the alpha channel of the indicated level to ETC2 EAC A8 blocks, like the
+    /// [.basis version](crate::BasisFileTranscoder::transcode_image_level_to_eac_a8).
+    pub fn transcode_image_level_to_eac_a8(
+        &self,
+        level_index: u32,
+        layer_index: u32,
+        face_index: u32,
+    ) -> Result<Vec<u8>, BasisError> {
+        // The KTX2 transcoder only produces whole texture formats, so split the alpha blocks off
+        // ETC2 RGBA output.
+        let etc2 = self.transcode_image_level_to_vec(
+            level_index,
+            layer_index,
+            face_index,
+            OutputFormat::ETC2_RGBA,
+        )?;
+        Ok(eac_a8_blocks(&etc2))
+    }
+
+    /// Transcode the indicated level into the provided output buffer. The resulting data will be
+    /// in format `output_format`.
+    pub fn transcode_image_level(
+        &self,
+        level_index: u32,
+        layer_index: u32,
+        face_index: u32,
+        output: &mut [u8],
+        output_format: OutputFormat,
+    ) -> Result<(), BasisError> {
+        self.transcode_image_level_with_params(
+            level_index,
+            layer_index,
+            face_index,
+            output,
+            output_format,
+            TranscodeParams::default(),
+        )
+    }
+
+    /// Like [`transcode_image_level`](Self::transcode_image_level), but with additional
+    /// parameters controlling the transcode.
+    pub fn transcode_image_level_with_params(
+        &self,
+        level_index: u32,
+        layer_index: u32,
+        face_index: u32,
+        output: &mut [u8],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+    ) -> Result<(), BasisError> {
+        // The transcoder only ever writes initialized bytes, so viewing `output` as uninitialized
+        // memory for the duration of the call is sound.
+        let output = unsafe { &mut *(output as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        self.transcode(
+            level_index,
+            layer_index,
+            face_index,
+            output,
+            output_format,
+            params,
+            &mut Ktx2TranscoderState::new(),
+        )?;
+        Ok(())
+    }
+
+    /// Like [`transcode_image_level`](Self::transcode_image_level), but writes into possibly
+    /// uninitialized memory with tightly packed rows. On success, returns the number of bytes at
+    /// the start of `output` that are now initialized.
+    pub fn transcode_image_level_uninit(
+        &self,
+        level_index: u32,
+        layer_index: u32,
+        face_index: u32,
+        output: &mut [MaybeUninit<u8>],
+        output_format: OutputFormat,
+        decode_flags: DecodeFlags,
+    ) -> Result<usize, BasisError> {
+        let params = TranscodeParams {
+            decode_flags,
+            ..Default::default()
+        };
+        self.transcode(
+            level_index,
+            layer_index,
+            face_index,
+            output,
+            output_format,
+            params,
+            &mut Ktx2TranscoderState::new(),
+        )
+    }
+
+    /// Like [`transcode_image_level_with_params`](Self::transcode_image_level_with_params), but
+    /// decodes using the scratch memory of `workspace` so that it can be reused across calls.
+    #[allow(clippy::too_many_arguments)]
+    pub fn transcode_image_level_with_workspace(
+        &self,
+        level_index: u32,
+        layer_index: u32,
+        face_index: u32,
+        output: &mut [u8],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+        workspace: &mut Workspace,
+    ) -> Result<(), BasisError> {
+        // See `transcode_image_level_with_params`.
+        let output = unsafe { &mut *(output as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        // The workspace may hold a level decompressed from another file.
+        workspace.ktx2.reset();
+        self.transcode(
+            level_index,
+            layer_index,
+            face_index,
+            output,
+            output_format,
+            params,
+            &mut workspace.ktx2,
+        )?;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn transcode(
+        &self,
+        level_index: u32,
+        layer_index: u32,
+        face_index: u32,
+        output: &mut [MaybeUninit<u8>],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+        state: &mut Ktx2TranscoderState,
+    ) -> Result<usize, BasisError> {
+        let info = self.level_info(level_index, layer_index, face_index)?;
+
+        if !self.can_transcode(output_format) {
+            return Err(BasisError::UnsupportedFormat(output_format));
+        }
+        postprocess::check(output_format, &params)?;
+
+        let needed = output_format.output_size(info.orig_width, info.orig_height, &params)?;
+        if output.len() < needed {
+            return Err(BasisError::OutputBufferTooSmall {
+                needed,
+                got: output.len(),
+            });
+        }
+
+        unsafe {
+            if !(*self.transcoder.0).transcode_image_level(
+                level_index,
+                layer_index,
+                face_index,
+                output.as_mut_ptr() as *mut _,
+                output_blocks(output.len(), output_format.bytes_per_block()),
+                output_format as basist::transcoder_texture_format,
+                params.decode_flags.bits(),
+                params.output_row_pitch,
+                params.output_rows,
+                -1,
+                -1,
+                state.0,
+            ) {
+                return Err(BasisError::SliceDecodeFailed);
+            }
+        }
+        postprocess::apply(
+            output,
+            output_format,
+            info.orig_width,
+            info.orig_height,
+            &params,
+        );
+
+        Ok(needed)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Build a minimal UASTC .ktx2 file with a single `width` x `height` level holding `data_len`
+    /// bytes of (meaningless) data. `kvd` is stored as the key/value data.
+    pub(crate) fn test_file(width: u32, height: u32, data_len: usize, kvd: &[u8]) -> Vec<u8> {
+        let kvd_offset = (HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE) as u32;
+        let level_offset = kvd_offset as u64 + kvd.len() as u64;
+
+        let mut file = IDENTIFIER.to_vec();
+        for value in &[0, 1, width, height, 0, 0, 1, 1, 0, 0, 0] {
+            file.extend_from_slice(&u32::to_le_bytes(*value));
+        }
+        file.extend_from_slice(&kvd_offset.to_le_bytes());
+        file.extend_from_slice(&(kvd.len() as u32).to_le_bytes());
+        file.extend_from_slice(&[0; 16]);
+        assert_eq!(file.len(), HEADER_SIZE);
+
+        for value in &[level_offset, data_len as u64, data_len as u64] {
+            file.extend_from_slice(&value.to_le_bytes());
+        }
+        file.extend_from_slice(kvd);
+        file.resize(file.len() + data_len, 0);
+        file
+    }
+
+    #[test]
+    fn transcoder_types_are_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Ktx2Transcoder>();
+        assert_send::<Ktx2FileTranscoder>();
+    }
+
+    #[test]
+    fn parse_header() {
+        let file = test_file(64, 32, 128, &[]);
+        let parsed = ParsedHeader::parse(&file).unwrap();
+        assert_eq!(
+            (parsed.header.pixel_width, parsed.header.pixel_height),
+            (64, 32)
+        );
+        assert_eq!(
+            parsed.header.supercompression_scheme,
+            SupercompressionScheme::None
+        );
+        assert_eq!(
+            (
+                parsed.total_levels(),
+                parsed.total_layers(),
+                parsed.total_faces()
+            ),
+            (1, 1, 1)
+        );
+        assert_eq!(parsed.tex_format(), Some(TexFormat::UASTC4x4));
+        assert_eq!(
+            parsed.levels,
+            vec![LevelIndex {
+                byte_offset: (HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE) as u64,
+                byte_length: 128,
+                uncompressed_byte_length: 128,
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_pathological_headers() {
+        let file = test_file(64, 32, 128, &[]);
+        let patch = |offset: usize, value: &[u8]| {
+            let mut file = file.clone();
+            file[offset..offset + value.len()].copy_from_slice(value);
+            ParsedHeader::parse(&file).err()
+        };
+        let invalid = Some(BasisError::InvalidHeader);
+        // Layer count of a cubemap array
+        let layers_and_faces = [u32::MAX.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        assert_eq!(patch(32, &layers_and_faces), invalid);
+        // Level count
+        assert_eq!(patch(40, &u32::MAX.to_le_bytes()), invalid);
+        // Key/value data offset
+        assert_eq!(patch(56, &u32::MAX.to_le_bytes()), invalid);
+        // Supercompression global data offset and length
+        assert_eq!(patch(64, &u64::MAX.to_le_bytes()), invalid);
+        assert_eq!(patch(72, &u64::MAX.to_le_bytes()), invalid);
+        // Level offset and length
+        assert_eq!(patch(HEADER_SIZE, &u64::MAX.to_le_bytes()), invalid);
+        assert_eq!(patch(HEADER_SIZE + 8, &u64::MAX.to_le_bytes()), invalid);
+    }
+
+    pub(crate) fn kv_entry(key: &str, value: &[u8]) -> Vec<u8> {
+        let len = key.len() + 1 + value.len();
+        let mut entry = (len as u32).to_le_bytes().to_vec();
+        entry.extend_from_slice(key.as_bytes());
+        entry.push(0);
+        entry.extend_from_slice(value);
+        entry.resize(4 + (len + 3) / 4 * 4, 0);
+        entry
+    }
+
+    #[test]
+    fn key_values() {
+        let material = kv_entry("material", &[1, 2, 3, 4]);
+        let mut kvd = kv_entry("KTXorientation", b"rd\0");
+        kvd.extend(kv_entry("KTXwriter", b"basisu test\0"));
+        kvd.extend(&material);
+        let file = test_file(4, 4, 16, &kvd);
+        let parsed = ParsedHeader::parse(&file).unwrap();
+
+        let key_values: Vec<_> = parsed.key_values(&file).collect();
+        assert_eq!(
+            key_values,
+            vec![
+                ("KTXorientation", &b"rd\0"[..]),
+                ("KTXwriter", &b"basisu test\0"[..]),
+                ("material", &[1, 2, 3, 4][..]),
+            ]
+        );
+
+        // A truncated entry ends iteration rather than reading past the key/value data.
+        let mut truncated = kvd.clone();
+        let last = kvd.len() - material.len();
+        truncated[last..last + 4].copy_from_slice(&100u32.to_le_bytes());
+        assert_eq!(KeyValues(&truncated).count(), 2);
+    }
+
+    #[test]
+    fn anim_data() {
+        let mut value = Vec::new();
+        for field in &[1001u32, 30000, 0] {
+            value.extend_from_slice(&field.to_le_bytes());
+        }
+        let file = test_file(4, 4, 16, &kv_entry("KTXanimData", &value));
+        let parsed = ParsedHeader::parse(&file).unwrap();
+
+        let anim = parsed.anim_data(&file).unwrap();
+        assert_eq!(
+            anim,
+            AnimData {
+                duration: 1001,
+                timescale: 30000,
+                loop_count: 0,
+            }
+        );
+        assert_eq!(
+            anim.frame_duration(),
+            Some(Duration::from_nanos(33_366_666))
+        );
+
+        let file = test_file(4, 4, 16, &[]);
+        assert_eq!(ParsedHeader::parse(&file).unwrap().anim_data(&file), None);
+    }
+
+    /// Build the data format descriptor of an ETC1S file, with an alpha slice if `alpha` is set.
+    fn etc1s_dfd(transfer_function: u8, alpha: bool) -> Vec<u8> {
+        let samples: &[u8] = if alpha { &[0, 15] } else { &[0] };
+        let block_size = 24 + 16 * samples.len();
+        let mut dfd = ((4 + block_size) as u32).to_le_bytes().to_vec();
+        dfd.extend_from_slice(&0u32.to_le_bytes());
+        dfd.extend_from_slice(&((block_size as u32) << 16 | 2).to_le_bytes());
+        dfd.extend_from_slice(&[163, 1, transfer_function, 0, 3, 3, 0, 0]);
+        dfd.extend_from_slice(&[0; 8]);
+        for (i, &channel_id) in samples.iter().enumerate() {
+            let first = (i as u32 * 64) | 63 << 16 | (channel_id as u32) << 24;
+            dfd.extend_from_slice(&first.to_le_bytes());
+            dfd.extend_from_slice(&[0; 8]);
+            dfd.extend_from_slice(&u32::MAX.to_le_bytes());
+        }
+        dfd
+    }
+
+    #[test]
+    fn dfd() {
+        let dfd = DataFormatDescriptor::parse(&etc1s_dfd(2, true)).unwrap();
+        assert_eq!(dfd.color_model, ColorModel::Etc1s);
+        assert_eq!(dfd.color_primaries, 1);
+        assert!(dfd.is_srgb());
+        assert!(dfd.has_alpha());
+        assert_eq!(dfd.texel_block_dimensions, [4, 4, 1, 1]);
+        assert_eq!(
+            dfd.samples[1],
+            Sample {
+                bit_offset: 64,
+                bit_length: 64,
+                channel_id: 15,
+                linear: false,
+                exponent: false,
+                signed: false,
+                float: false,
+                lower: 0,
+                upper: u32::MAX,
+            }
+        );
+
+        let dfd = DataFormatDescriptor::parse(&etc1s_dfd(1, false)).unwrap();
+        assert_eq!(dfd.transfer_function, TransferFunction::Linear);
+        assert!(!dfd.is_srgb());
+        assert!(!dfd.has_alpha());
+
+        // A block size larger than the descriptor is rejected.
+        let dfd = etc1s_dfd(2, true);
+        assert_eq!(DataFormatDescriptor::parse(&dfd[..dfd.len() - 1]), None);
+        // Files without a descriptor have none to parse.
+        let file = test_file(4, 4, 16, &[]);
+        assert_eq!(ParsedHeader::parse(&file).unwrap().dfd(&file), None);
+    }
+
+    #[test]
+    fn dfd_round_trip() {
+        for dfd in [etc1s_dfd(2, true), etc1s_dfd(1, false)] {
+            let parsed = DataFormatDescriptor::parse(&dfd).unwrap();
+            assert_eq!(parsed.to_bytes(), dfd);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_headers() {
+        let file = test_file(64, 32, 128, &[]);
+        assert_eq!(
+            ParsedHeader::parse(&file[..HEADER_SIZE]),
+            Err(BasisError::InvalidHeader)
+        );
+        assert_eq!(
+            ParsedHeader::parse(&file[..file.len() - 1]),
+            Err(BasisError::InvalidHeader)
+        );
+
+        let mut bad_identifier = file;
+        bad_identifier[1] = b'X';
+        assert_eq!(
+            ParsedHeader::parse(&bad_identifier),
+            Err(BasisError::InvalidHeader)
+        );
+    }
+}
@@ -1,34 +1,220 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::borrow::{Cow, ToOwned};
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
 use basisu_sys::*;
-use lazy_static::lazy_static;
-use std::convert::TryInto;
-use std::mem;
-use std::sync::Once;
+use bitflags::bitflags;
+use core::convert::TryInto;
+use core::fmt;
+use core::mem::{self, MaybeUninit};
+use core::ops::{Deref, Range};
+use core::ptr;
+use core::str::FromStr;
+use once::Once;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+mod codebook;
+pub mod container;
+#[cfg(feature = "image")]
+mod decoder;
+#[cfg(feature = "encoder")]
+pub mod encoder;
+#[cfg(feature = "std")]
+mod file;
+mod handle;
+pub mod header;
+pub mod ktx2;
+pub mod lowlevel;
+pub mod metadata;
+mod once;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod postprocess;
+mod transcoder;
+mod video;
+#[cfg(feature = "wgpu")]
+pub mod wgpu;
+mod workspace;
+
+pub use codebook::SelectorCodebook;
+#[cfg(feature = "image")]
+pub use decoder::BasisDecoder;
+#[cfg(feature = "encoder")]
+pub use encoder::{Compressor, CompressorParams, JobPool};
+#[cfg(feature = "std")]
+pub use file::BasisFile;
+pub use handle::{CubeFace, Image, Level};
+pub use ktx2::{Ktx2FileTranscoder, Ktx2Transcoder};
+pub use postprocess::ColorConversion;
+pub use transcoder::{Container, Transcoder};
+pub use video::VideoDecoder;
+pub use workspace::Workspace;
 
+// All global state of the C++ library is set up through `init`, `init_encoder` and
+// `global_codebook`, each of which waits for any other thread already running it.
 static INIT: Once = Once::new();
+#[cfg(feature = "encoder")]
+static ENCODER_INIT: Once = Once::new();
+static CODEBOOK_INIT: Once = Once::new();
+static mut CODEBOOK: MaybeUninit<basist::etc1_global_selector_codebook> = MaybeUninit::uninit();
 
-lazy_static! {
-    static ref CODEBOOK: basist::etc1_global_selector_codebook = unsafe {
-        let mut cb: basist::etc1_global_selector_codebook = mem::zeroed();
-        basist::etc1_global_selector_codebook_init(
-            &mut cb as *mut _,
-            basist::g_global_selector_cb_size,
-            &basist::g_global_selector_cb as *const _,
-        );
-        cb
-    };
+/// Initialize the global tables of the transcoder. Must be called before creating a transcoder.
+///
+/// `basisu_transcoder_init` only guards against being run twice with a plain flag, so it must
+/// never be called anywhere else.
+pub(crate) fn init() {
+    INIT.call_once(|| unsafe {
+        basist::basisu_transcoder_init();
+    });
+}
+
+/// Initialize the global tables of the encoder. Must be called before compressing anything.
+///
+/// `basisu_encoder_init` also initializes the transcoder, so that is done first and through
+/// [`init`], leaving the flag set by the time the encoder checks it.
+#[cfg(feature = "encoder")]
+pub(crate) fn init_encoder() {
+    init();
+    ENCODER_INIT.call_once(|| unsafe {
+        shim::encoder_init();
+    });
 }
 
+/// The address of the built-in global selector codebook, without creating it.
+///
+/// The codebook is only read while starting to transcode ETC1S data, so transcoders hold on to
+/// this pointer and call [`global_codebook`] once they see an ETC1S file. Applications that only
+/// use UASTC never pay for building it.
+fn global_codebook_ptr() -> *const basist::etc1_global_selector_codebook {
+    ptr::addr_of!(CODEBOOK) as *const basist::etc1_global_selector_codebook
+}
+
+/// The built-in global selector codebook, which is created on first use and never freed.
+fn global_codebook() -> *const basist::etc1_global_selector_codebook {
+    init();
+    unsafe {
+        let codebook = ptr::addr_of_mut!(CODEBOOK) as *mut basist::etc1_global_selector_codebook;
+        CODEBOOK_INIT.call_once(|| {
+            codebook.write(mem::zeroed());
+            basist::etc1_global_selector_codebook_init(
+                codebook,
+                basist::g_global_selector_cb_size,
+                &basist::g_global_selector_cb as *const _,
+            );
+        });
+        codebook
+    }
+}
+
+/// An error from transcoding or encoding. Some variants only exist with the `encoder` or `std`
+/// features, so matches on it need a catch-all arm.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BasisError {
 	/// The basis file is corrupt.
     InvalidFileContents,
 	/// An invalid argument was provided.
     InvalidArgument,
+    /// The file header is malformed or does not describe a .basis file.
+    InvalidHeader,
+    /// This file cannot be transcoded to the requested output format.
+    UnsupportedFormat(OutputFormat),
+    /// The output buffer cannot hold the transcoded data.
+    OutputBufferTooSmall { needed: usize, got: usize },
+    /// Decoding the compressed slice data failed.
+    SliceDecodeFailed,
+    /// The requested image does not exist in the file.
+    ImageIndexOutOfRange { index: u32, total: u32 },
+    /// The requested mip level does not exist in the image.
+    LevelIndexOutOfRange { index: u32, total: u32 },
+    /// The file is larger than the 4 GiB the transcoder can address.
+    FileTooLarge,
+    /// The requested array layer does not exist in the file.
+    LayerIndexOutOfRange { index: u32, total: u32 },
+    /// The requested cubemap face does not exist in the file.
+    FaceIndexOutOfRange { index: u32, total: u32 },
+    /// The transcoded image would be larger than the address space.
+    ImageTooLarge,
+    /// The .ktx2 file uses a supercompression scheme this build can't decode. Zstandard requires
+    /// the `zstd` feature.
+    UnsupportedSupercompression(ktx2::SupercompressionScheme),
+    /// The encoder failed.
+    #[cfg(feature = "encoder")]
+    EncodeFailed(encoder::EncodeError),
+    /// Reading the file failed.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for BasisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BasisError::InvalidFileContents => write!(f, "invalid basis file contents"),
+            BasisError::InvalidArgument => write!(f, "invalid argument"),
+            BasisError::InvalidHeader => write!(f, "invalid basis file header"),
+            BasisError::UnsupportedFormat(format) => {
+                write!(f, "file cannot be transcoded to {}", format)
+            }
+            BasisError::OutputBufferTooSmall { needed, got } => write!(
+                f,
+                "output buffer too small: {} bytes needed but only {} provided",
+                needed, got
+            ),
+            BasisError::SliceDecodeFailed => write!(f, "failed to decode slice data"),
+            BasisError::ImageIndexOutOfRange { index, total } => write!(
+                f,
+                "image index {} out of range (file has {} images)",
+                index, total
+            ),
+            BasisError::LevelIndexOutOfRange { index, total } => write!(
+                f,
+                "level index {} out of range (image has {} levels)",
+                index, total
+            ),
+            BasisError::FileTooLarge => write!(f, "file larger than 4 GiB"),
+            BasisError::LayerIndexOutOfRange { index, total } => write!(
+                f,
+                "layer index {} out of range (file has {} layers)",
+                index, total
+            ),
+            BasisError::FaceIndexOutOfRange { index, total } => write!(
+                f,
+                "face index {} out of range (file has {} faces)",
+                index, total
+            ),
+            BasisError::ImageTooLarge => write!(f, "transcoded image too large"),
+            BasisError::UnsupportedSupercompression(scheme) => {
+                write!(f, "unsupported supercompression scheme {:?}", scheme)
+            }
+            #[cfg(feature = "encoder")]
+            BasisError::EncodeFailed(error) => write!(f, "encoding failed: {}", error),
+            #[cfg(feature = "std")]
+            BasisError::Io(kind) => write!(f, "read failed: {}", kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BasisError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for BasisError {
+    fn from(error: std::io::Error) -> Self {
+        BasisError::Io(error.kind())
+    }
 }
 
 #[repr(i32)]
 #[allow(non_camel_case_types)]
 #[non_exhaustive]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// GPU texture format that basis files can be transcoded to.
 pub enum OutputFormat {
     // BC formats
@@ -66,6 +252,10 @@ pub enum OutputFormat {
     // PVRTC formats
     /// Opaque only, RGB or alpha if cDecodeFlagsTranscodeAlphaDataToOpaqueFormats flag is
     /// specified, nearly lowest quality of any texture format.
+    ///
+    /// PVRTC1 interpolates colors across neighboring blocks, and the transcoder treats the texture
+    /// as wrapping around at its edges when doing so, so tiled textures have no seams. There is no
+    /// option for clamp addressing.
     PVRTC1_4_RGB = basist::transcoder_texture_format_cTFPVRTC1_4_RGB,
     /// Opaque+alpha, most useful for simple opacity maps. If .basis file doesn't have alpha
     /// cTFPVRTC1_4_RGB will be used instead. Lowest quality of any supported texture format.
@@ -103,121 +293,1184 @@ pub enum OutputFormat {
     BGR565 = basist::transcoder_texture_format_cTFBGR565,
 }
 impl OutputFormat {
+    /// Every output format, ordered by their raw values.
+    pub const ALL: [OutputFormat; 21] = [
+        OutputFormat::ETC1_RGB,
+        OutputFormat::ETC2_RGBA,
+        OutputFormat::BC1_RGB,
+        OutputFormat::BC3_RGBA,
+        OutputFormat::BC4_R,
+        OutputFormat::BC5_RG,
+        OutputFormat::BC7_RGBA,
+        OutputFormat::PVRTC1_4_RGB,
+        OutputFormat::PVRTC1_4_RGBA,
+        OutputFormat::ASTC_4x4_RGBA,
+        OutputFormat::ATC_RGB,
+        OutputFormat::ATC_RGBA,
+        OutputFormat::RGBA32,
+        OutputFormat::RGB565,
+        OutputFormat::BGR565,
+        OutputFormat::RGBA4444,
+        OutputFormat::FXT1_RGB,
+        OutputFormat::PVRTC2_4_RGB,
+        OutputFormat::PVRTC2_4_RGBA,
+        OutputFormat::ETC2_EAC_R11,
+        OutputFormat::ETC2_EAC_RG11,
+    ];
+
+    /// Number of bytes in each block. Uncompressed formats are treated as having 1x1 blocks, so
+    /// for them this is the same as [`bytes_per_pixel`](Self::bytes_per_pixel).
     pub fn bytes_per_block(&self) -> u32 {
         unsafe {
             basist::basis_get_bytes_per_block_or_pixel(*self as basist::transcoder_texture_format)
         }
     }
-    pub fn block_width(&self) -> u32 {
-        if unsafe {
+    /// Number of bytes in each pixel, or `None` for block compressed formats.
+    pub fn bytes_per_pixel(&self) -> Option<u32> {
+        if !self.is_uncompressed() {
+            return None;
+        }
+        Some(unsafe {
+            basist::basis_get_uncompressed_bytes_per_pixel(
+                *self as basist::transcoder_texture_format,
+            )
+        })
+    }
+    /// Whether this format can store an alpha channel.
+    pub fn has_alpha(&self) -> bool {
+        unsafe {
+            basist::basis_transcoder_format_has_alpha(*self as basist::transcoder_texture_format)
+        }
+    }
+    /// Whether this format stores raw pixels rather than compressed blocks.
+    pub fn is_uncompressed(&self) -> bool {
+        unsafe {
             basist::basis_block_format_is_uncompressed(*self as basist::transcoder_texture_format)
-        } {
+        }
+    }
+    /// Whether the transcoder was built with support for this format, which the `bc7`, `astc`,
+    /// `pvrtc1`, `pvrtc2`, `atc`, `fxt1` and `etc2-eac` features control. Files can't be
+    /// transcoded to disabled formats.
+    ///
+    /// This reflects the `BASISD_SUPPORT_*` options `basisu-sys` was actually compiled with, so a
+    /// format is also enabled when another crate in the build turns on its `basisu-sys` feature.
+    pub fn is_enabled(&self) -> bool {
+        unsafe { shim::transcoder_format_enabled(*self as basist::transcoder_texture_format) }
+    }
+    pub fn block_width(&self) -> u32 {
+        if self.is_uncompressed() {
             return 1;
         }
         unsafe { basist::basis_get_block_width(*self as basist::transcoder_texture_format) }
     }
     pub fn block_height(&self) -> u32 {
-        if unsafe {
-            basist::basis_block_format_is_uncompressed(*self as basist::transcoder_texture_format)
-        } {
+        if self.is_uncompressed() {
             return 1;
         }
         unsafe { basist::basis_get_block_height(*self as basist::transcoder_texture_format) }
     }
+
+    /// Name of the format, matching the variant name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::BC1_RGB => "BC1_RGB",
+            OutputFormat::BC3_RGBA => "BC3_RGBA",
+            OutputFormat::BC4_R => "BC4_R",
+            OutputFormat::BC5_RG => "BC5_RG",
+            OutputFormat::BC7_RGBA => "BC7_RGBA",
+            OutputFormat::ETC1_RGB => "ETC1_RGB",
+            OutputFormat::ETC2_RGBA => "ETC2_RGBA",
+            OutputFormat::ETC2_EAC_R11 => "ETC2_EAC_R11",
+            OutputFormat::ETC2_EAC_RG11 => "ETC2_EAC_RG11",
+            OutputFormat::ASTC_4x4_RGBA => "ASTC_4x4_RGBA",
+            OutputFormat::PVRTC1_4_RGB => "PVRTC1_4_RGB",
+            OutputFormat::PVRTC1_4_RGBA => "PVRTC1_4_RGBA",
+            OutputFormat::PVRTC2_4_RGB => "PVRTC2_4_RGB",
+            OutputFormat::PVRTC2_4_RGBA => "PVRTC2_4_RGBA",
+            OutputFormat::ATC_RGB => "ATC_RGB",
+            OutputFormat::ATC_RGBA => "ATC_RGBA",
+            OutputFormat::FXT1_RGB => "FXT1_RGB",
+            OutputFormat::RGBA32 => "RGBA32",
+            OutputFormat::RGB565 => "RGB565",
+            OutputFormat::RGBA4444 => "RGBA4444",
+            OutputFormat::BGR565 => "BGR565",
+        }
+    }
+
+    /// Number of bytes needed to store a `width` x `height` image in this format.
+    pub(crate) fn image_size(&self, width: u32, height: u32) -> Result<usize, BasisError> {
+        self.output_size(width, height, &TranscodeParams::default())
+    }
+
+    /// Number of bytes the transcoder writes for a `width` x `height` image in this format, taking
+    /// the row pitch and row count of `params` into account. Fails with
+    /// [`BasisError::InvalidArgument`] if the row pitch is shorter than a row, and with
+    /// [`BasisError::ImageTooLarge`] if the size doesn't fit in a `usize`.
+    pub(crate) fn output_size(
+        &self,
+        width: u32,
+        height: u32,
+        params: &TranscodeParams,
+    ) -> Result<usize, BasisError> {
+        params.check_row_pitch(*self, width)?;
+        let row_pitch = match params.output_row_pitch {
+            0 => div_ceil(width, self.block_width()),
+            pitch => pitch,
+        };
+        let rows = match params.output_rows {
+            rows if rows != 0 && self.is_uncompressed() => rows,
+            _ => div_ceil(height, self.block_height()),
+        };
+        (row_pitch as usize)
+            .checked_mul(rows as usize)
+            .and_then(|size| size.checked_mul(self.bytes_per_block() as usize))
+            .ok_or(BasisError::ImageTooLarge)
+    }
+}
+
+/// Return the output formats the transcoder was built with support for, as reported by
+/// [`OutputFormat::is_enabled`]. Whether a particular file can be transcoded to them also depends
+/// on its [`TexFormat`].
+pub fn supported_formats() -> Vec<OutputFormat> {
+    OutputFormat::ALL
+        .iter()
+        .copied()
+        .filter(OutputFormat::is_enabled)
+        .collect()
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
-/// 
-pub struct BasisTranscoder(basist::basisu_transcoder);
+/// Error returned when parsing an [`OutputFormat`] from an unrecognized name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseOutputFormatError(String);
+
+impl fmt::Display for ParseOutputFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown output format \"{}\"", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseOutputFormatError {}
+
+impl FromStr for OutputFormat {
+    type Err = ParseOutputFormatError;
 
+    /// Parse a format name. Matching ignores case, `_` and `-`, and accepts common short forms
+    /// like "bc7", "astc4x4" or "etc2-rgba" in addition to the variant names.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| *c != '_' && *c != '-')
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+
+        Ok(match &*normalized {
+            "bc1" | "bc1rgb" => OutputFormat::BC1_RGB,
+            "bc3" | "bc3rgba" => OutputFormat::BC3_RGBA,
+            "bc4" | "bc4r" => OutputFormat::BC4_R,
+            "bc5" | "bc5rg" => OutputFormat::BC5_RG,
+            "bc7" | "bc7rgba" => OutputFormat::BC7_RGBA,
+            "etc1" | "etc1rgb" => OutputFormat::ETC1_RGB,
+            "etc2" | "etc2rgba" => OutputFormat::ETC2_RGBA,
+            "etc2eacr11" | "eacr11" => OutputFormat::ETC2_EAC_R11,
+            "etc2eacrg11" | "eacrg11" => OutputFormat::ETC2_EAC_RG11,
+            "astc" | "astc4x4" | "astc4x4rgba" => OutputFormat::ASTC_4x4_RGBA,
+            "pvrtc1rgb" | "pvrtc14rgb" => OutputFormat::PVRTC1_4_RGB,
+            "pvrtc1rgba" | "pvrtc14rgba" => OutputFormat::PVRTC1_4_RGBA,
+            "pvrtc2rgb" | "pvrtc24rgb" => OutputFormat::PVRTC2_4_RGB,
+            "pvrtc2rgba" | "pvrtc24rgba" => OutputFormat::PVRTC2_4_RGBA,
+            "atc" | "atcrgb" => OutputFormat::ATC_RGB,
+            "atcrgba" => OutputFormat::ATC_RGBA,
+            "fxt1" | "fxt1rgb" => OutputFormat::FXT1_RGB,
+            "rgba32" | "rgba8" => OutputFormat::RGBA32,
+            "rgb565" => OutputFormat::RGB565,
+            "rgba4444" => OutputFormat::RGBA4444,
+            "bgr565" => OutputFormat::BGR565,
+            _ => return Err(ParseOutputFormatError(s.to_owned())),
+        })
+    }
+}
+
+#[repr(i32)]
+#[allow(non_camel_case_types)]
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Low level block format that individual slices can be transcoded to. This covers some outputs
+/// that [`OutputFormat`] doesn't, like standalone alpha blocks and partial pixel writes that can be
+/// combined to assemble a texture from several slices.
+pub enum BlockFormat {
+    /// ETC1 block, 8 bytes.
+    ETC1 = basist::block_format_cETC1,
+    /// ETC2 EAC A8 block followed by an ETC1 block, 16 bytes.
+    ETC2_RGBA = basist::block_format_cETC2_RGBA,
+    /// BC1 block, 8 bytes.
+    BC1 = basist::block_format_cBC1,
+    /// BC4 block followed by a BC1 block, 16 bytes.
+    BC3 = basist::block_format_cBC3,
+    /// BC4 block, 8 bytes.
+    BC4 = basist::block_format_cBC4,
+    /// Two BC4 blocks, 16 bytes.
+    BC5 = basist::block_format_cBC5,
+    /// Opaque PVRTC1 4bpp block, 8 bytes.
+    PVRTC1_4_RGB = basist::block_format_cPVRTC1_4_RGB,
+    /// PVRTC1 4bpp block with alpha, 8 bytes.
+    PVRTC1_4_RGBA = basist::block_format_cPVRTC1_4_RGBA,
+    /// BC7 block, 16 bytes.
+    BC7 = basist::block_format_cBC7,
+    /// Writes only the color bits of a BC7 mode 5 block, 16 bytes. ETC1S only.
+    BC7_M5_COLOR = basist::block_format_cBC7_M5_COLOR,
+    /// Writes only the alpha bits of a BC7 mode 5 block, 16 bytes. ETC1S only.
+    BC7_M5_ALPHA = basist::block_format_cBC7_M5_ALPHA,
+    /// ETC2 EAC A8 alpha block, 8 bytes.
+    ETC2_EAC_A8 = basist::block_format_cETC2_EAC_A8,
+    /// ASTC 4x4 block, 16 bytes.
+    ASTC_4x4 = basist::block_format_cASTC_4x4,
+    /// ATC RGB block, 8 bytes.
+    ATC_RGB = basist::block_format_cATC_RGB,
+    /// ATC RGBA interpolated alpha block, 16 bytes.
+    ATC_RGBA_INTERPOLATED_ALPHA = basist::block_format_cATC_RGBA_INTERPOLATED_ALPHA,
+    /// FXT1 8x4 block, 16 bytes.
+    FXT1_RGB = basist::block_format_cFXT1_RGB,
+    /// Opaque PVRTC2 4bpp block, 8 bytes.
+    PVRTC2_4_RGB = basist::block_format_cPVRTC2_4_RGB,
+    /// PVRTC2 4bpp block with alpha, 8 bytes.
+    PVRTC2_4_RGBA = basist::block_format_cPVRTC2_4_RGBA,
+    /// ETC2 EAC R11 block, 8 bytes.
+    ETC2_EAC_R11 = basist::block_format_cETC2_EAC_R11,
+    /// Two ETC2 EAC R11 blocks, 16 bytes.
+    ETC2_EAC_RG11 = basist::block_format_cETC2_EAC_RG11,
+    /// Raw 16-bit endpoint and selector codebook indices of each block, 4 bytes. ETC1S only.
+    Indices = basist::block_format_cIndices,
+    /// Writes only the RGB components of 32bpp pixels, leaving alpha untouched.
+    RGB32 = basist::block_format_cRGB32,
+    /// 32bpp RGBA pixels.
+    RGBA32 = basist::block_format_cRGBA32,
+    /// Writes only the alpha component of 32bpp pixels, leaving RGB untouched.
+    A32 = basist::block_format_cA32,
+    /// 16bpp RGB pixels, R at bit position 11.
+    RGB565 = basist::block_format_cRGB565,
+    /// 16bpp RGB pixels, R at bit position 0.
+    BGR565 = basist::block_format_cBGR565,
+    /// Writes only the RGB components of 16bpp RGBA4444 pixels, leaving alpha untouched.
+    RGBA4444_COLOR = basist::block_format_cRGBA4444_COLOR,
+    /// Writes only the alpha component of 16bpp RGBA4444 pixels, leaving RGB untouched.
+    RGBA4444_ALPHA = basist::block_format_cRGBA4444_ALPHA,
+    /// Writes the RGB components of 16bpp RGBA4444 pixels with alpha set to opaque.
+    RGBA4444_COLOR_OPAQUE = basist::block_format_cRGBA4444_COLOR_OPAQUE,
+    /// 16bpp RGBA pixels, R at bit position 12, A at bit position 0.
+    RGBA4444 = basist::block_format_cRGBA4444,
+}
+impl BlockFormat {
+    /// Number of bytes in each block, or in each pixel for uncompressed formats.
+    pub fn bytes_per_block(&self) -> u32 {
+        match self {
+            BlockFormat::RGB565
+            | BlockFormat::BGR565
+            | BlockFormat::RGBA4444_COLOR
+            | BlockFormat::RGBA4444_ALPHA
+            | BlockFormat::RGBA4444_COLOR_OPAQUE
+            | BlockFormat::RGBA4444 => 2,
+            BlockFormat::Indices | BlockFormat::RGB32 | BlockFormat::RGBA32 | BlockFormat::A32 => 4,
+            BlockFormat::ETC1
+            | BlockFormat::BC1
+            | BlockFormat::BC4
+            | BlockFormat::PVRTC1_4_RGB
+            | BlockFormat::PVRTC1_4_RGBA
+            | BlockFormat::ATC_RGB
+            | BlockFormat::PVRTC2_4_RGB
+            | BlockFormat::PVRTC2_4_RGBA
+            | BlockFormat::ETC2_EAC_A8
+            | BlockFormat::ETC2_EAC_R11 => 8,
+            BlockFormat::ETC2_RGBA
+            | BlockFormat::BC3
+            | BlockFormat::BC5
+            | BlockFormat::BC7
+            | BlockFormat::BC7_M5_COLOR
+            | BlockFormat::BC7_M5_ALPHA
+            | BlockFormat::ASTC_4x4
+            | BlockFormat::ATC_RGBA_INTERPOLATED_ALPHA
+            | BlockFormat::FXT1_RGB
+            | BlockFormat::ETC2_EAC_RG11 => 16,
+        }
+    }
+}
+
+/// How the images in a .basis file should be interpreted.
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TextureType {
+    /// An arbitrary array of 2D images with optional mipmaps. Each image may have a different
+    /// resolution and number of mip levels.
+    Texture2D = basist::basis_texture_type_cBASISTexType2D,
+    /// An array of 2D images with optional mipmaps. All images have the same resolution and number
+    /// of mip levels.
+    Texture2DArray = basist::basis_texture_type_cBASISTexType2DArray,
+    /// An array of cubemaps with optional mipmaps. The number of images is a multiple of 6, with
+    /// faces in X+, X-, Y+, Y-, Z+, Z- order.
+    CubemapArray = basist::basis_texture_type_cBASISTexTypeCubemapArray,
+    /// A sequence of video frames, one per image, all with the same resolution and number of mip
+    /// levels.
+    VideoFrames = basist::basis_texture_type_cBASISTexTypeVideoFrames,
+    /// A 3D texture with optional mipmaps. The Z dimension is the number of images.
+    Volume = basist::basis_texture_type_cBASISTexTypeVolume,
+}
+impl TextureType {
+    fn from_raw(raw: basist::basis_texture_type) -> Option<Self> {
+        Some(match raw {
+            basist::basis_texture_type_cBASISTexType2D => TextureType::Texture2D,
+            basist::basis_texture_type_cBASISTexType2DArray => TextureType::Texture2DArray,
+            basist::basis_texture_type_cBASISTexTypeCubemapArray => TextureType::CubemapArray,
+            basist::basis_texture_type_cBASISTexTypeVideoFrames => TextureType::VideoFrames,
+            basist::basis_texture_type_cBASISTexTypeVolume => TextureType::Volume,
+            _ => return None,
+        })
+    }
+}
+
+/// The compression scheme used by a .basis file.
+#[repr(i32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TexFormat {
+    /// ETC1S, a low bitrate format based on ETC1 that transcodes well to BC1 and ETC1.
+    ETC1S = basist::basis_tex_format_cETC1S,
+    /// UASTC, a high quality format based on ASTC 4x4 that transcodes well to BC7 and ASTC.
+    UASTC4x4 = basist::basis_tex_format_cUASTC4x4,
+}
+impl TexFormat {
+    fn from_raw(raw: basist::basis_tex_format) -> Option<Self> {
+        Some(match raw {
+            basist::basis_tex_format_cETC1S => TexFormat::ETC1S,
+            basist::basis_tex_format_cUASTC4x4 => TexFormat::UASTC4x4,
+            _ => return None,
+        })
+    }
+}
+
+bitflags! {
+    /// Flags controlling how the transcoder decodes image data.
+    #[derive(Default)]
+    pub struct DecodeFlags: u32 {
+        /// When transcoding to an opaque format, output the alpha data rather than the color
+        /// data. Useful for storing alpha in a separate BC4 or ETC1 texture.
+        const TRANSCODE_ALPHA_DATA_TO_OPAQUE_FORMATS =
+            basist::basisu_decode_flags_cDecodeFlagsTranscodeAlphaDataToOpaqueFormats;
+        /// Never emit BC1 blocks that use three color mode. Some hardware handles these blocks
+        /// incorrectly.
+        const BC1_FORBID_THREE_COLOR_BLOCKS =
+            basist::basisu_decode_flags_cDecodeFlagsBC1ForbidThreeColorBlocks;
+        /// Use a slower but higher quality path for targets that have one, notably PVRTC1 and
+        /// BC1/BC3 from UASTC. Intended for offline tooling.
+        const HIGH_QUALITY = basist::basisu_decode_flags_cDecodeFlagsHighQuality;
+    }
+}
+
+/// Optional parameters for transcoding an image level.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TranscodeParams {
+    /// Flags passed to the decoder.
+    pub decode_flags: DecodeFlags,
+    /// Distance between the starts of consecutive rows of the output, in blocks for compressed
+    /// formats or pixels for uncompressed ones. Zero means rows are tightly packed; anything else
+    /// shorter than a row fails with [`BasisError::InvalidArgument`].
+    pub output_row_pitch: u32,
+    /// Total number of rows in the output buffer, in pixels. Only used for uncompressed formats,
+    /// where it allows writing into a buffer taller than the level. Zero means the level height.
+    pub output_rows: u32,
+    /// Color space conversion applied to the pixels after transcoding. Only uncompressed formats
+    /// can be converted; other formats fail with [`BasisError::InvalidArgument`].
+    pub color_conversion: ColorConversion,
+    /// Multiply the color channels by alpha after any color conversion. Only available for
+    /// [`OutputFormat::RGBA32`] and [`OutputFormat::RGBA4444`]; other formats fail with
+    /// [`BasisError::InvalidArgument`].
+    pub premultiply_alpha: bool,
+    /// Reorder the channels of uncompressed output after the other post-processing steps: output
+    /// channel `i` is taken from channel `swizzle[i]`, where 0 to 3 stand for red, green, blue
+    /// and alpha. For example `[2, 1, 0, 3]` gives BGRA8 from [`OutputFormat::RGBA32`] and
+    /// `[0, 0, 0, 3]` copies red into green and blue. Formats without alpha read it as fully
+    /// opaque and drop it from the output. Compressed formats and values above 3 fail with
+    /// [`BasisError::InvalidArgument`].
+    pub swizzle: Option<[u8; 4]>,
+}
+
+impl TranscodeParams {
+    /// Check that the row pitch leaves room for a whole row of a `width` pixels wide image in
+    /// `format`.
+    fn check_row_pitch(&self, format: OutputFormat, width: u32) -> Result<(), BasisError> {
+        let row_len = div_ceil(width, format.block_width());
+        match self.output_row_pitch {
+            pitch if pitch != 0 && pitch < row_len => Err(BasisError::InvalidArgument),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `x / n` rounded up. `u32::div_ceil` needs a newer compiler than the crate supports.
+fn div_ceil(x: u32, n: u32) -> u32 {
+    x / n + (x % n != 0) as u32
+}
+
+/// Summary information about a .basis file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FileInfo {
+    /// File format version.
+    pub version: u32,
+    /// Total size of the header, including the slice descriptors.
+    pub total_header_size: u32,
+    /// Number of ETC1S selector codebook entries.
+    pub total_selectors: u32,
+    /// Size in bytes of the selector codebook.
+    pub selector_codebook_size: u32,
+    /// Number of ETC1S endpoint codebook entries.
+    pub total_endpoints: u32,
+    /// Size in bytes of the endpoint codebook.
+    pub endpoint_codebook_size: u32,
+    /// Size in bytes of the Huffman tables.
+    pub tables_size: u32,
+    /// Total size in bytes of all slice data.
+    pub slices_size: u32,
+    /// How the images should be interpreted.
+    pub texture_type: TextureType,
+    /// Microseconds per frame, only meaningful for video files.
+    pub us_per_frame: u32,
+    /// Total number of slices. ETC1S files with alpha store two slices per image level.
+    pub total_slices: u32,
+    /// Total number of images.
+    pub total_images: u32,
+    /// Number of mip levels of each image.
+    pub image_mipmap_levels: Vec<u32>,
+    /// First user defined header field.
+    pub userdata0: u32,
+    /// Second user defined header field.
+    pub userdata1: u32,
+    /// Whether the images were vertically flipped when the file was encoded.
+    pub y_flipped: bool,
+    /// Whether the file uses ETC1S rather than UASTC compression.
+    pub etc1s: bool,
+    /// Whether the file contains alpha data.
+    pub has_alpha_slices: bool,
+}
+
+/// Information about a single image within a .basis file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageInfo {
+    /// Index of the image within the file.
+    pub image_index: u32,
+    /// Number of mip levels in the image.
+    pub total_levels: u32,
+    /// Width of the base level before padding to a multiple of the block size.
+    pub orig_width: u32,
+    /// Height of the base level before padding to a multiple of the block size.
+    pub orig_height: u32,
+    /// Width of the base level rounded up to a multiple of 4.
+    pub width: u32,
+    /// Height of the base level rounded up to a multiple of 4.
+    pub height: u32,
+    /// Number of 4x4 blocks in each row of the base level.
+    pub num_blocks_x: u32,
+    /// Number of 4x4 blocks in each column of the base level.
+    pub num_blocks_y: u32,
+    /// Total number of 4x4 blocks in the base level.
+    pub total_blocks: u32,
+    /// Index of the first slice of the image.
+    pub first_slice_index: u32,
+    /// Whether the image has alpha data.
+    pub alpha_flag: bool,
+    /// Whether the image is an I-frame (video files only).
+    pub iframe_flag: bool,
+}
+
+/// Information about a single mip level of an image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelInfo {
+    /// Index of the image the level belongs to.
+    pub image_index: u32,
+    /// Index of the level within its image.
+    pub level_index: u32,
+    /// Width of the level before padding to a multiple of the block size.
+    pub orig_width: u32,
+    /// Height of the level before padding to a multiple of the block size.
+    pub orig_height: u32,
+    /// Width of the level rounded up to a multiple of 4.
+    pub width: u32,
+    /// Height of the level rounded up to a multiple of 4.
+    pub height: u32,
+    /// Number of 4x4 blocks in each row of the level.
+    pub num_blocks_x: u32,
+    /// Number of 4x4 blocks in each column of the level.
+    pub num_blocks_y: u32,
+    /// Total number of 4x4 blocks in the level.
+    pub total_blocks: u32,
+    /// Index of the first slice of the level.
+    pub first_slice_index: u32,
+    /// Whether the level has alpha data.
+    pub alpha_flag: bool,
+    /// Whether the level is an I-frame (video files only).
+    pub iframe_flag: bool,
+}
+
+/// Information about a single slice of a .basis file. Each image level is stored as one slice, or
+/// as separate color and alpha slices for ETC1S files with alpha.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SliceInfo {
+    /// Index of the slice within the file.
+    pub slice_index: u32,
+    /// Index of the image the slice belongs to.
+    pub image_index: u32,
+    /// Index of the mip level the slice belongs to.
+    pub level_index: u32,
+    /// Width of the slice before padding to a multiple of the block size.
+    pub orig_width: u32,
+    /// Height of the slice before padding to a multiple of the block size.
+    pub orig_height: u32,
+    /// Width of the slice rounded up to a multiple of 4.
+    pub width: u32,
+    /// Height of the slice rounded up to a multiple of 4.
+    pub height: u32,
+    /// Number of 4x4 blocks in each row of the slice.
+    pub num_blocks_x: u32,
+    /// Number of 4x4 blocks in each column of the slice.
+    pub num_blocks_y: u32,
+    /// Total number of 4x4 blocks in the slice.
+    pub total_blocks: u32,
+    /// Size in bytes of the compressed slice data.
+    pub compressed_size: u32,
+    /// CRC-16 of the unpacked slice data, as stored in the file.
+    pub unpacked_slice_crc16: u32,
+    /// Whether the slice holds alpha data.
+    pub alpha_flag: bool,
+    /// Whether the slice is an I-frame (video files only).
+    pub iframe_flag: bool,
+}
+
+/// Either a reference to a transcoder or the transcoder itself. File transcoders normally borrow
+/// the transcoder they were started from, but [`Transcoder`] owns one instead.
+pub(crate) enum MaybeOwned<R, T> {
+    Borrowed(R),
+    Owned(T),
+}
+
+impl<R: Deref<Target = T>, T> Deref for MaybeOwned<R, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            MaybeOwned::Borrowed(r) => r,
+            MaybeOwned::Owned(t) => t,
+        }
+    }
+}
+
+/// A transcoder for converting .basis files to GPU texture formats.
+///
+/// The second field keeps a custom selector codebook alive for as long as the transcoder uses it.
+pub struct BasisTranscoder(basist::basisu_transcoder, Option<Arc<SelectorCodebook>>);
+
+/// A .basis file that is ready to be transcoded, returned by
+/// [`BasisTranscoder::start_transcoding`].
+///
+/// Transcoding only requires `&self`: every call decodes with its own fresh decoder state rather
+/// than state shared inside the transcoder. Video P-frames depend on the state left by the previous
+/// frame, so they will not decode correctly through these methods; use
+/// [`video_decoder`](Self::video_decoder) for video files.
+///
+/// The image, level and slice tables are read once when transcoding starts, so metadata queries
+/// are cheap and can't fail.
 pub struct BasisFileTranscoder<'a> {
-    transcoder: &'a mut BasisTranscoder,
-    data: &'a [u8],
+    transcoder: MaybeOwned<&'a BasisTranscoder, BasisTranscoder>,
+    /// At most `u32::MAX` bytes long, which `start_transcoding` checks. Owned when the file was
+    /// read by [`BasisTranscoder::start_transcoding_reader`].
+    data: Cow<'a, [u8]>,
+    metadata: FileMetadata,
+}
+
+/// The header, level and slice tables of a .basis file, read once in `start_transcoding` so that
+/// metadata queries don't go through the C++ library or reparse the file each time.
+struct FileMetadata {
+    info: FileInfo,
+    tex_format: TexFormat,
+    images: Vec<ImageInfo>,
+    /// Indexed by image and then by level.
+    levels: Vec<Vec<LevelInfo>>,
+    /// Indexed like `levels`. `None` where the slice descriptors don't describe the level.
+    level_ranges: Vec<Vec<Option<Range<usize>>>>,
+    slices: Vec<SliceInfo>,
+}
+
+impl FileMetadata {
+    fn read(transcoder: &BasisTranscoder, data: &[u8]) -> Result<Self, BasisError> {
+        let info = transcoder.get_file_info(data)?;
+        let images = (0..info.total_images)
+            .map(|image| transcoder.get_image_info(data, image))
+            .collect::<Result<Vec<_>, _>>()?;
+        let levels = images
+            .iter()
+            .map(|image| {
+                (0..image.total_levels)
+                    .map(|level| transcoder.get_level_info(data, image.image_index, level))
+                    .collect()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let parsed = header::ParsedHeader::parse(data).ok();
+        let level_ranges = images
+            .iter()
+            .map(|image| {
+                (0..image.total_levels)
+                    .map(|level| parsed.as_ref()?.level_data_range(image.image_index, level))
+                    .collect()
+            })
+            .collect();
+        Ok(Self {
+            tex_format: transcoder.get_tex_format(data)?,
+            slices: transcoder.get_slice_info(data)?,
+            info,
+            images,
+            levels,
+            level_ranges,
+        })
+    }
+}
+
+/// Whether the transcoder supports converting `tex_format` data to `output_format`.
+fn format_supported(output_format: OutputFormat, tex_format: TexFormat) -> bool {
+    output_format.is_enabled()
+        && unsafe {
+            basist::basis_is_format_supported(
+                output_format as basist::transcoder_texture_format,
+                tex_format as basist::basis_tex_format,
+            )
+        }
+}
+
+/// Scratch state used by the decoder. Video P-frames also read the state left by the previous
+/// frame.
+pub(crate) struct TranscoderState(*mut basist::basisu_transcoder_state);
+
+// The state is uniquely owned heap memory that is only accessed through this handle.
+unsafe impl Send for TranscoderState {}
+
+impl TranscoderState {
+    pub(crate) fn new() -> Self {
+        Self(unsafe { shim::new_transcoder_state() })
+    }
+}
+
+impl Drop for TranscoderState {
+    fn drop(&mut self) {
+        unsafe { shim::delete_transcoder_state(self.0) }
+    }
+}
+
+/// The transcoder takes 32-bit data sizes, so larger inputs are rejected up front rather than
+/// being silently truncated.
+fn data_len(data: &[u8]) -> Result<u32, BasisError> {
+    data.len().try_into().map_err(|_| BasisError::FileTooLarge)
+}
+
+/// Read one .basis file from `reader`: the fixed size header, then as many bytes as it says
+/// follow it.
+#[cfg(feature = "std")]
+fn read_file<R: std::io::Read>(mut reader: R) -> Result<Vec<u8>, BasisError> {
+    use std::io::Read;
+
+    let mut data = vec![0; header::HEADER_SIZE];
+    reader.read_exact(&mut data)?;
+    let header_size = u16::from_le_bytes([data[4], data[5]]) as usize;
+    if &data[..2] != b"sB" || header_size != header::HEADER_SIZE {
+        return Err(BasisError::InvalidHeader);
+    }
+    let data_size = u32::from_le_bytes([data[8], data[9], data[10], data[11]]) as u64;
+    // Grow the buffer as data arrives rather than trusting the header with the allocation size.
+    reader.take(data_size).read_to_end(&mut data)?;
+    if data.len() as u64 != header_size as u64 + data_size {
+        return Err(BasisError::Io(std::io::ErrorKind::UnexpectedEof));
+    }
+    Ok(data)
+}
+
+/// Number of whole blocks (or pixels) that fit in an output buffer of `len` bytes, saturating at
+/// `u32::MAX` for buffers too big to describe with 32 bits.
+fn output_blocks(len: usize, bytes_per_block: u32) -> u32 {
+    (len / bytes_per_block as usize)
+        .try_into()
+        .unwrap_or(u32::MAX)
+}
+
+/// An ETC2 EAC A8 block decoding to alpha 255 everywhere: base 255, multiplier 1, and table 13
+/// with every selector pointing at its zero modifier.
+const OPAQUE_EAC_A8_BLOCK: [u8; 8] = [255, 0x1d, 0x92, 0x49, 0x24, 0x92, 0x49, 0x24];
+
+/// Keep only the ETC2 EAC A8 alpha half of each 16 byte ETC2 RGBA block.
+fn eac_a8_blocks(etc2_rgba: &[u8]) -> Vec<u8> {
+    etc2_rgba
+        .chunks_exact(16)
+        .flat_map(|block| &block[..8])
+        .copied()
+        .collect()
 }
 
 impl BasisTranscoder {
 	/// Create a new transcoder. The first time this is called, it does some library wide
-	/// initialization.
+	/// initialization. The built-in global selector codebook is only built once a transcoder
+	/// starts on an ETC1S file.
     pub fn new() -> Self {
-		INIT.call_once(|| unsafe {
-			basist::basisu_transcoder_init();
-		});
+		init();
 
         unsafe {
             let mut t: basist::basisu_transcoder = mem::zeroed();
-            basist::basisu_transcoder_basisu_transcoder(&mut t as *mut _, &*CODEBOOK as *const _);
-            Self(t)
+            basist::basisu_transcoder_basisu_transcoder(&mut t as *mut _, global_codebook_ptr());
+            Self(t, None)
+        }
+    }
+
+    /// Create a new transcoder that decodes ETC1S files using `codebook` instead of the built-in
+    /// global selector codebook.
+    pub fn with_selector_codebook(codebook: Arc<SelectorCodebook>) -> Self {
+        init();
+
+        unsafe {
+            let mut t: basist::basisu_transcoder = mem::zeroed();
+            basist::basisu_transcoder_basisu_transcoder(&mut t as *mut _, codebook.as_ptr());
+            Self(t, Some(codebook))
+        }
+    }
+
+    /// Return the custom selector codebook this transcoder was created with, if any.
+    pub fn get_selector_codebook(&self) -> Option<&Arc<SelectorCodebook>> {
+        self.1.as_ref()
+    }
+
+    /// Create a transcoder that is only used to query metadata. This skips the global
+    /// initialization and codebook construction, so it must never start transcoding.
+    pub(crate) fn metadata_only() -> Self {
+        unsafe {
+            let mut t: basist::basisu_transcoder = mem::zeroed();
+            basist::basisu_transcoder_basisu_transcoder(&mut t as *mut _, ptr::null());
+            Self(t, None)
         }
     }
 
 	/// Return whether the file checksums are valid. This is an expensive operation because it must
 	/// scan the full file data.
     pub fn validate_file_checksums(&self, data: &[u8], full_validation: bool) -> bool {
+        let len = match data_len(data) {
+            Ok(len) => len,
+            Err(_) => return false,
+        };
         unsafe {
-            self.0.validate_file_checksums(
-                data.as_ptr() as *const _,
-                data.len().try_into().unwrap(),
-                full_validation,
-            )
+            self.0
+                .validate_file_checksums(data.as_ptr() as *const _, len, full_validation)
         }
     }
 
 	/// Check just whether the header indicates a valid .basis file.
     pub fn validate_file_header(&self, data: &[u8]) -> bool {
-        unsafe {
-            self.0
-                .validate_header(data.as_ptr() as *const _, data.len().try_into().unwrap())
-        }
+        let len = match data_len(data) {
+            Ok(len) => len,
+            Err(_) => return false,
+        };
+        unsafe { self.0.validate_header(data.as_ptr() as *const _, len) }
     }
 
 	/// Return the number of images in the provided .basis file.
     pub fn get_total_images(&self, data: &[u8]) -> u32 {
+        let len = match data_len(data) {
+            Ok(len) => len,
+            Err(_) => return 0,
+        };
+        unsafe { self.0.get_total_images(data.as_ptr() as *const _, len) }
+    }
+
+	/// Return the number of levels in the indicated image of the provided .basis file.
+    pub fn get_total_image_levels(&self, data: &[u8], image_index: u32) -> u32 {
+        let len = match data_len(data) {
+            Ok(len) => len,
+            Err(_) => return 0,
+        };
         unsafe {
             self.0
-                .get_total_images(data.as_ptr() as *const _, data.len().try_into().unwrap())
+                .get_total_image_levels(data.as_ptr() as *const _, len, image_index)
         }
     }
 
-	/// Return the number of levels in the indicated image of the provided .basis file.
-    pub fn get_total_image_levels(&self, data: &[u8], image_index: u32) -> u32 {
+	/// Return summary information about the provided .basis file.
+    pub fn get_file_info(&self, data: &[u8]) -> Result<FileInfo, BasisError> {
+        let len = data_len(data)?;
+        let mut info: shim::file_info = unsafe { mem::zeroed() };
         unsafe {
-            self.0.get_total_image_levels(
+            if !shim::get_file_info(
+                &self.0 as *const _,
                 data.as_ptr() as *const _,
-                data.len().try_into().unwrap(),
+                len,
+                &mut info as *mut _,
+            ) {
+                return Err(BasisError::InvalidHeader);
+            }
+        }
+
+        Ok(FileInfo {
+            version: info.version,
+            total_header_size: info.total_header_size,
+            total_selectors: info.total_selectors,
+            selector_codebook_size: info.selector_codebook_size,
+            total_endpoints: info.total_endpoints,
+            endpoint_codebook_size: info.endpoint_codebook_size,
+            tables_size: info.tables_size,
+            slices_size: info.slices_size,
+            texture_type: TextureType::from_raw(info.tex_type).ok_or(BasisError::InvalidHeader)?,
+            us_per_frame: info.us_per_frame,
+            total_slices: info.total_slices,
+            total_images: info.total_images,
+            image_mipmap_levels: (0..info.total_images)
+                .map(|i| self.get_total_image_levels(data, i))
+                .collect(),
+            userdata0: info.userdata0,
+            userdata1: info.userdata1,
+            y_flipped: info.y_flipped,
+            etc1s: info.etc1s,
+            has_alpha_slices: info.has_alpha_slices,
+        })
+    }
+
+	/// Return information about every slice of the provided .basis file, in file order.
+    pub fn get_slice_info(&self, data: &[u8]) -> Result<Vec<SliceInfo>, BasisError> {
+        let len = data_len(data)?;
+        let total_slices = self.get_file_info(data)?.total_slices;
+
+        let mut slices: Vec<basist::basisu_slice_info> =
+            vec![unsafe { mem::zeroed() }; total_slices as usize];
+        unsafe {
+            if !shim::get_slice_info(
+                &self.0 as *const _,
+                data.as_ptr() as *const _,
+                len,
+                slices.as_mut_ptr(),
+                total_slices,
+            ) {
+                return Err(BasisError::InvalidHeader);
+            }
+        }
+
+        Ok(slices
+            .iter()
+            .map(|slice| SliceInfo {
+                slice_index: slice.m_slice_index,
+                image_index: slice.m_image_index,
+                level_index: slice.m_level_index,
+                orig_width: slice.m_orig_width,
+                orig_height: slice.m_orig_height,
+                width: slice.m_width,
+                height: slice.m_height,
+                num_blocks_x: slice.m_num_blocks_x,
+                num_blocks_y: slice.m_num_blocks_y,
+                total_blocks: slice.m_total_blocks,
+                compressed_size: slice.m_compressed_size,
+                unpacked_slice_crc16: slice.m_unpacked_slice_crc16,
+                alpha_flag: slice.m_alpha_flag,
+                iframe_flag: slice.m_iframe_flag,
+            })
+            .collect())
+    }
+
+	/// Return how the images in the provided .basis file should be interpreted.
+    pub fn get_texture_type(&self, data: &[u8]) -> Result<TextureType, BasisError> {
+        let len = data_len(data)?;
+        let raw = unsafe { self.0.get_texture_type(data.as_ptr() as *const _, len) };
+        TextureType::from_raw(raw).ok_or(BasisError::InvalidHeader)
+    }
+
+	/// Return whether the provided .basis file uses ETC1S or UASTC compression.
+    pub fn get_tex_format(&self, data: &[u8]) -> Result<TexFormat, BasisError> {
+        let len = data_len(data)?;
+        if !self.validate_file_header(data) {
+            return Err(BasisError::InvalidHeader);
+        }
+        let raw = unsafe { self.0.get_tex_format(data.as_ptr() as *const _, len) };
+        TexFormat::from_raw(raw).ok_or(BasisError::InvalidHeader)
+    }
+
+	/// Return the two user defined 32-bit fields stored in the header of the provided .basis file.
+    pub fn get_userdata(&self, data: &[u8]) -> Result<(u32, u32), BasisError> {
+        let len = data_len(data)?;
+        let mut userdata0 = 0;
+        let mut userdata1 = 0;
+        unsafe {
+            if !self.0.get_userdata(
+                data.as_ptr() as *const _,
+                len,
+                &mut userdata0 as *mut _,
+                &mut userdata1 as *mut _,
+            ) {
+                return Err(BasisError::InvalidHeader);
+            }
+        }
+        Ok((userdata0, userdata1))
+    }
+
+	/// Return whether the provided .basis file can be transcoded to `output_format`. This depends
+	/// both on the compression used by the file and on which formats the transcoder was built with.
+    pub fn can_transcode(&self, data: &[u8], output_format: OutputFormat) -> bool {
+        let len = match data_len(data) {
+            Ok(len) => len,
+            Err(_) => return false,
+        };
+        let raw = unsafe { self.0.get_tex_format(data.as_ptr() as *const _, len) };
+        TexFormat::from_raw(raw).map_or(false, |tex_format| {
+            format_supported(output_format, tex_format)
+        })
+    }
+
+	/// Return information about the indicated image of the provided .basis file.
+    pub fn get_image_info(&self, data: &[u8], image_index: u32) -> Result<ImageInfo, BasisError> {
+        let len = data_len(data)?;
+        let total = self.get_total_images(data);
+        if image_index >= total {
+            return Err(BasisError::ImageIndexOutOfRange {
+                index: image_index,
+                total,
+            });
+        }
+
+        let mut info: basist::basisu_image_info = unsafe { mem::zeroed() };
+        unsafe {
+            if !self.0.get_image_info(
+                data.as_ptr() as *const _,
+                len,
+                &mut info as *mut _,
                 image_index,
-            )
+            ) {
+                return Err(BasisError::InvalidFileContents);
+            }
         }
+
+        Ok(ImageInfo {
+            image_index: info.m_image_index,
+            total_levels: info.m_total_levels,
+            orig_width: info.m_orig_width,
+            orig_height: info.m_orig_height,
+            width: info.m_width,
+            height: info.m_height,
+            num_blocks_x: info.m_num_blocks_x,
+            num_blocks_y: info.m_num_blocks_y,
+            total_blocks: info.m_total_blocks,
+            first_slice_index: info.m_first_slice_index,
+            alpha_flag: info.m_alpha_flag,
+            iframe_flag: info.m_iframe_flag,
+        })
+    }
+
+    /// Return information about the indicated `image_index` / `level_index` pair of the provided
+    /// .basis file. The indices must be in range.
+    fn get_level_info(
+        &self,
+        data: &[u8],
+        image_index: u32,
+        level_index: u32,
+    ) -> Result<LevelInfo, BasisError> {
+        let len = data_len(data)?;
+        let mut level_info: basist::basisu_image_level_info = unsafe { mem::zeroed() };
+        unsafe {
+            if !self.0.get_image_level_info(
+                data.as_ptr() as *const _,
+                len,
+                &mut level_info as *mut _,
+                image_index,
+                level_index,
+            ) {
+                return Err(BasisError::InvalidFileContents);
+            }
+        }
+
+        Ok(LevelInfo {
+            image_index: level_info.m_image_index,
+            level_index: level_info.m_level_index,
+            orig_width: level_info.m_orig_width,
+            orig_height: level_info.m_orig_height,
+            width: level_info.m_width,
+            height: level_info.m_height,
+            num_blocks_x: level_info.m_num_blocks_x,
+            num_blocks_y: level_info.m_num_blocks_y,
+            total_blocks: level_info.m_total_blocks,
+            first_slice_index: level_info.m_first_slice_index,
+            alpha_flag: level_info.m_alpha_flag,
+            iframe_flag: level_info.m_iframe_flag,
+        })
     }
 
 	/// Initialize the transcoder to begin transcoding this .basis file.
+	///
+	/// A transcoder can be reused for any number of files by calling this again once the previous
+	/// `BasisFileTranscoder` has been dropped. The decoded codebooks and tables of the new file are
+	/// written into the buffers allocated for the previous one, avoiding the cost of constructing a
+	/// new transcoder per file.
     pub fn start_transcoding<'a>(
         &'a mut self,
         data: &'a [u8],
     ) -> Result<BasisFileTranscoder<'a>, BasisError> {
+        let metadata = self.start(data)?;
+        Ok(BasisFileTranscoder {
+            transcoder: MaybeOwned::Borrowed(self),
+            data: Cow::Borrowed(data),
+            metadata,
+        })
+    }
+
+    /// Like [`start_transcoding`](Self::start_transcoding), but read the file from `reader`
+    /// into a buffer owned by the returned file transcoder.
+    ///
+    /// Exactly the bytes of one .basis file are read, using the size recorded in its header, so
+    /// a stream holding several files or other data can keep being read afterwards. Read errors
+    /// are returned as [`BasisError::Io`].
+    #[cfg(feature = "std")]
+    pub fn start_transcoding_reader<R: std::io::Read>(
+        &mut self,
+        reader: R,
+    ) -> Result<BasisFileTranscoder<'_>, BasisError> {
+        let data = read_file(reader)?;
+        let metadata = self.start(&data)?;
+        Ok(BasisFileTranscoder {
+            transcoder: MaybeOwned::Borrowed(self),
+            data: Cow::Owned(data),
+            metadata,
+        })
+    }
+
+    /// Like `start_transcoding`, but the returned file transcoder takes ownership of `self`.
+    pub(crate) fn into_file_transcoder(
+        mut self,
+        data: &[u8],
+    ) -> Result<BasisFileTranscoder<'_>, BasisError> {
+        let metadata = self.start(data)?;
+        Ok(BasisFileTranscoder {
+            transcoder: MaybeOwned::Owned(self),
+            data: Cow::Borrowed(data),
+            metadata,
+        })
+    }
+
+    fn start(&mut self, data: &[u8]) -> Result<FileMetadata, BasisError> {
+        let len = data_len(data)?;
+        if !self.validate_file_header(data) {
+            return Err(BasisError::InvalidHeader);
+        }
+        if self.1.is_none() && self.get_tex_format(data)? == TexFormat::ETC1S {
+            global_codebook();
+        }
+
         unsafe {
-            if !self
-                .0
-                .start_transcoding(data.as_ptr() as *const _, data.len().try_into().unwrap())
-            {
+            if !self.0.start_transcoding(data.as_ptr() as *const _, len) {
                 return Err(BasisError::InvalidFileContents);
             }
         }
+        FileMetadata::read(self, data)
+    }
 
-        Ok(BasisFileTranscoder {
-            transcoder: self,
-            data,
-        })
+    /// Release the codebooks and tables decoded by the last call to `start_transcoding`. This is
+    /// never required before starting on another file, but frees memory held by an idle
+    /// transcoder.
+    pub fn stop_transcoding(&mut self) {
+        unsafe {
+            self.0.stop_transcoding();
+        }
+    }
+}
+
+// The C++ transcoder only holds a pointer to the immutable global codebook plus state that is
+// modified exclusively by `start_transcoding` and `stop_transcoding`, which take `&mut self`. All
+// other methods are const and, since every transcode call passes its own `TranscoderState`, never
+// touch the transcoder's internal default state.
+unsafe impl Send for BasisTranscoder {}
+unsafe impl Sync for BasisTranscoder {}
+
+impl Drop for BasisTranscoder {
+    fn drop(&mut self) {
+        unsafe { shim::destroy_transcoder(&mut self.0 as *mut _) }
+    }
+}
+
+impl Default for BasisTranscoder {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl<'a> BasisFileTranscoder<'a> {
 	/// Return the total number of images.
     pub fn get_total_images(&self) -> u32 {
-		self.transcoder.get_total_images(self.data)
+		self.metadata.images.len() as u32
     }
 	/// Return the total number of levels in the image with index `image_index`.
     pub fn get_total_image_levels(&self, image_index: u32) -> u32 {
-		self.transcoder.get_total_image_levels(self.data, image_index)
+        self.metadata
+            .levels
+            .get(image_index as usize)
+            .map_or(0, |levels| levels.len() as u32)
 	}
+    /// Return summary information about the file.
+    pub fn get_file_info(&self) -> &FileInfo {
+        &self.metadata.info
+    }
+    /// Return information about every slice of the file, in file order.
+    pub fn slices(&self) -> &[SliceInfo] {
+        &self.metadata.slices
+    }
+    /// Return how the images in the file should be interpreted.
+    pub fn get_texture_type(&self) -> TextureType {
+        self.metadata.info.texture_type
+    }
+    /// Return whether the file uses ETC1S or UASTC compression.
+    pub fn get_tex_format(&self) -> TexFormat {
+        self.metadata.tex_format
+    }
+    /// Return the two user defined 32-bit fields stored in the file header.
+    pub fn get_userdata(&self) -> (u32, u32) {
+        (self.metadata.info.userdata0, self.metadata.info.userdata1)
+    }
+    /// Return whether the file can be transcoded to `output_format`.
+    pub fn can_transcode(&self, output_format: OutputFormat) -> bool {
+        format_supported(output_format, self.metadata.tex_format)
+    }
+    /// Return information about the image with index `image_index`.
+    pub fn get_image_info(&self, image_index: u32) -> Result<ImageInfo, BasisError> {
+        self.metadata
+            .images
+            .get(image_index as usize)
+            .copied()
+            .ok_or(BasisError::ImageIndexOutOfRange {
+                index: image_index,
+                total: self.get_total_images(),
+            })
+    }
+
+    fn check_level_index(&self, image_index: u32, level_index: u32) -> Result<(), BasisError> {
+        let total = self.get_total_images();
+        if image_index >= total {
+            return Err(BasisError::ImageIndexOutOfRange {
+                index: image_index,
+                total,
+            });
+        }
+        let total = self.get_total_image_levels(image_index);
+        if level_index >= total {
+            return Err(BasisError::LevelIndexOutOfRange {
+                index: level_index,
+                total,
+            });
+        }
+        Ok(())
+    }
 
 	/// Return the dimension of the indicated `image_index` / `level_index` pair.
     pub fn level_dimensions(
@@ -225,34 +1478,266 @@ impl<'a> BasisFileTranscoder<'a> {
         image_index: u32,
         level_index: u32,
     ) -> Result<(u32, u32), BasisError> {
-        let mut level_info: basist::basisu_image_level_info = unsafe { mem::zeroed() };
+        let info = self.level_info(image_index, level_index)?;
+        Ok((info.width, info.height))
+    }
+
+    /// Return information about the indicated `image_index` / `level_index` pair.
+    pub fn level_info(&self, image_index: u32, level_index: u32) -> Result<LevelInfo, BasisError> {
+        self.check_level_index(image_index, level_index)?;
+        Ok(self.metadata.levels[image_index as usize][level_index as usize])
+    }
+
+    /// Return the number of bytes needed to hold the indicated `image_index` / `level_index` pair
+    /// when transcoded to `output_format` with tightly packed rows.
+    pub fn required_output_size(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output_format: OutputFormat,
+    ) -> Result<usize, BasisError> {
+        let info = self.level_info(image_index, level_index)?;
+        output_format.image_size(info.orig_width, info.orig_height)
+    }
+
+    /// Return the range of file bytes holding the compressed data of the indicated `image_index` /
+    /// `level_index` pair. See [`header::ParsedHeader::level_data_range`].
+    pub fn level_data_range(
+        &self,
+        image_index: u32,
+        level_index: u32,
+    ) -> Result<Range<usize>, BasisError> {
+        self.check_level_index(image_index, level_index)?;
+        self.metadata.level_ranges[image_index as usize][level_index as usize]
+            .clone()
+            .ok_or(BasisError::InvalidHeader)
+    }
+
+	/// Transcode the indicated `image_index` / `level_index` pair into the provided output
+	/// buffer. The resulting data will be in format `output_format`.
+    ///
+    /// Returns [`BasisError::OutputBufferTooSmall`] if `output` is smaller than
+    /// [`required_output_size`](Self::required_output_size).
+    pub fn transcode_image_level(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output: &mut [u8],
+        output_format: OutputFormat,
+    ) -> Result<(), BasisError> {
+        self.transcode_image_level_with_params(
+            image_index,
+            level_index,
+            output,
+            output_format,
+            TranscodeParams::default(),
+        )
+    }
+
+    /// Transcode the indicated `image_index` / `level_index` pair into a newly allocated buffer of
+    /// exactly the required size.
+    pub fn transcode_image_level_to_vec(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output_format: OutputFormat,
+    ) -> Result<Vec<u8>, BasisError> {
+        let size = self.required_output_size(image_index, level_index, output_format)?;
+        let mut output = Vec::with_capacity(size);
+        let len = self.transcode_image_level_uninit(
+            image_index,
+            level_index,
+            output.spare_capacity_mut(),
+            output_format,
+            DecodeFlags::empty(),
+        )?;
+        unsafe { output.set_len(len) };
+        Ok(output)
+    }
+
+    /// Transcode the alpha channel of the indicated `image_index` / `level_index` pair to ETC2 EAC
+    /// A8 blocks, for renderers that keep alpha in a separate texture. Files without alpha give
+    /// fully opaque blocks.
+    ///
+    /// Only the alpha data is decoded: the alpha slice of ETC1S files, or the single slice of
+    /// UASTC files.
+    pub fn transcode_image_level_to_eac_a8(
+        &self,
+        image_index: u32,
+        level_index: u32,
+    ) -> Result<Vec<u8>, BasisError> {
+        let info = self.level_info(image_index, level_index)?;
+        if !self.can_transcode(OutputFormat::ETC2_RGBA) {
+            return Err(BasisError::UnsupportedFormat(OutputFormat::ETC2_RGBA));
+        }
+        let slice_index = match self.metadata.tex_format {
+            TexFormat::UASTC4x4 => info.first_slice_index,
+            TexFormat::ETC1S if info.alpha_flag => info.first_slice_index + 1,
+            TexFormat::ETC1S => return Ok(OPAQUE_EAC_A8_BLOCK.repeat(info.total_blocks as usize)),
+        };
+        let block_format = BlockFormat::ETC2_EAC_A8;
+        let mut output =
+            vec![0; info.total_blocks as usize * block_format.bytes_per_block() as usize];
+        self.transcode_slice(
+            slice_index,
+            &mut output,
+            block_format,
+            TranscodeParams::default(),
+        )?;
+        Ok(output)
+    }
+
+    /// Transcode a single slice into the provided output buffer in block format `output_format`.
+    ///
+    /// ETC1S files with alpha store the color and alpha data of each level in two consecutive
+    /// slices starting at [`LevelInfo::first_slice_index`], so this can be used to transcode them
+    /// into separate outputs. PVRTC1 RGBA output needs both slices at once and is only available
+    /// through `transcode_image_level`.
+    pub fn transcode_slice(
+        &self,
+        slice_index: u32,
+        output: &mut [u8],
+        output_format: BlockFormat,
+        params: TranscodeParams,
+    ) -> Result<(), BasisError> {
+        if params.needs_postprocess() {
+            return Err(BasisError::InvalidArgument);
+        }
+        let stride = output_format.bytes_per_block();
+        let output_size_blocks = output_blocks(output.len(), stride);
+
+        let state = TranscoderState::new();
         unsafe {
-            if !self.transcoder.0.get_image_level_info(
+            if !self.transcoder.0.transcode_slice(
                 self.data.as_ptr() as *const _,
                 self.data.len() as u32,
-                &mut level_info as *mut _,
-                image_index,
-                level_index,
+                slice_index,
+                output.as_mut_ptr() as *mut _,
+                output_size_blocks,
+                output_format as basist::block_format,
+                stride,
+                params.decode_flags.bits(),
+                params.output_row_pitch,
+                state.0,
+                ptr::null_mut(),
+                params.output_rows,
+                -1,
+                -1,
             ) {
-                return Err(BasisError::InvalidArgument);
+                return Err(BasisError::SliceDecodeFailed);
             }
         }
 
-        Ok((level_info.m_width, level_info.m_height))
+        Ok(())
     }
 
-	/// Transcode the indicated `image_index` / `level_index` pair into the provided output
-	/// buffer. The resulting data will be in format `output_format`.
-    pub fn transcode_image_level(
+    /// Like [`transcode_image_level`](Self::transcode_image_level), but with additional
+    /// parameters controlling the transcode.
+    pub fn transcode_image_level_with_params(
         &self,
         image_index: u32,
         level_index: u32,
         output: &mut [u8],
         output_format: OutputFormat,
+        params: TranscodeParams,
     ) -> Result<(), BasisError> {
-        let output_size_blocks = (output.len() / output_format.bytes_per_block() as usize)
-            .try_into()
-            .unwrap();
+        // The transcoder only ever writes initialized bytes, so viewing `output` as uninitialized
+        // memory for the duration of the call is sound.
+        let output = unsafe { &mut *(output as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        self.transcode_image_level_with_state(
+            image_index,
+            level_index,
+            output,
+            output_format,
+            params,
+            &mut TranscoderState::new(),
+        )?;
+        Ok(())
+    }
+
+    /// Like [`transcode_image_level_with_params`](Self::transcode_image_level_with_params), but
+    /// decodes using the scratch memory of `workspace` so that it can be reused across calls.
+    pub fn transcode_image_level_with_workspace(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output: &mut [u8],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+        workspace: &mut Workspace,
+    ) -> Result<(), BasisError> {
+        // See `transcode_image_level_with_params`.
+        let output = unsafe { &mut *(output as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        self.transcode_image_level_with_state(
+            image_index,
+            level_index,
+            output,
+            output_format,
+            params,
+            &mut workspace.basis,
+        )?;
+        Ok(())
+    }
+
+    /// Like [`transcode_image_level`](Self::transcode_image_level), but writes into possibly
+    /// uninitialized memory so large staging buffers don't need to be zeroed first. Rows are
+    /// always tightly packed.
+    ///
+    /// On success, returns the number of bytes at the start of `output` that are now initialized.
+    /// This composes with [`Vec::spare_capacity_mut`]: reserve
+    /// [`required_output_size`](Self::required_output_size) bytes, transcode into the spare
+    /// capacity, then `set_len` to the returned length.
+    pub fn transcode_image_level_uninit(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output: &mut [MaybeUninit<u8>],
+        output_format: OutputFormat,
+        decode_flags: DecodeFlags,
+    ) -> Result<usize, BasisError> {
+        // With the default row pitch and row count the transcoder writes every byte of the
+        // `needed` range, so no padding is left uninitialized.
+        let params = TranscodeParams {
+            decode_flags,
+            ..Default::default()
+        };
+        self.transcode_image_level_with_state(
+            image_index,
+            level_index,
+            output,
+            output_format,
+            params,
+            &mut TranscoderState::new(),
+        )
+    }
+
+    /// Transcode using the provided decoder `state`, returning the number of bytes of `output`
+    /// the level occupies.
+    pub(crate) fn transcode_image_level_with_state(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output: &mut [MaybeUninit<u8>],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+        state: &mut TranscoderState,
+    ) -> Result<usize, BasisError> {
+        let info = self.level_info(image_index, level_index)?;
+
+        if !self.can_transcode(output_format) {
+            return Err(BasisError::UnsupportedFormat(output_format));
+        }
+        postprocess::check(output_format, &params)?;
+
+        let needed = output_format.output_size(info.orig_width, info.orig_height, &params)?;
+        if output.len() < needed {
+            return Err(BasisError::OutputBufferTooSmall {
+                needed,
+                got: output.len(),
+            });
+        }
+
+        let output_size_blocks = output_blocks(output.len(), output_format.bytes_per_block());
 
         unsafe {
             if !self.transcoder.0.transcode_image_level(
@@ -263,23 +1748,387 @@ impl<'a> BasisFileTranscoder<'a> {
                 output.as_mut_ptr() as *mut _,
                 output_size_blocks,
                 output_format as basist::transcoder_texture_format,
-                0,
-                0,
-                std::ptr::null_mut(),
-                0,
+                params.decode_flags.bits(),
+                params.output_row_pitch,
+                state.0,
+                params.output_rows,
             ) {
-                return Err(BasisError::InvalidFileContents);
+                return Err(BasisError::SliceDecodeFailed);
             }
         }
+        postprocess::apply(
+            output,
+            output_format,
+            info.orig_width,
+            info.orig_height,
+            &params,
+        );
 
-        Ok(())
+        Ok(needed)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
     }
+
+    #[test]
+    fn transcoder_types_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BasisTranscoder>();
+        assert_send_sync::<BasisFileTranscoder>();
+    }
+
+    // Run under valgrind or a sanitizer to check that the C++ transcoder state is released.
+    #[test]
+    fn drop_transcoders() {
+        for _ in 0..100 {
+            drop(BasisTranscoder::new());
+        }
+    }
+
+    #[test]
+    fn initialize_from_many_threads() {
+        use std::sync::{Arc, Barrier};
+
+        // The threads wait for each other so that they race to be the first to initialize.
+        let barrier = Arc::new(Barrier::new(16));
+        let threads: Vec<_> = (0..16)
+            .map(|i| {
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..20 {
+                        match i % 3 {
+                            0 => drop(BasisTranscoder::new()),
+                            1 => drop(Ktx2Transcoder::new()),
+                            _ => assert_eq!(global_codebook(), global_codebook_ptr()),
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn split_eac_a8_blocks() {
+        let etc2: Vec<u8> = (0..32).collect();
+        assert_eq!(
+            eac_a8_blocks(&etc2),
+            [0, 1, 2, 3, 4, 5, 6, 7, 16, 17, 18, 19, 20, 21, 22, 23]
+        );
+    }
+
+    #[test]
+    fn reject_invalid_header() {
+        let transcoder = BasisTranscoder::new();
+        let data = [0; 128];
+        assert_eq!(
+            transcoder.get_file_info(&data),
+            Err(BasisError::InvalidHeader)
+        );
+        assert_eq!(
+            transcoder.get_userdata(&data),
+            Err(BasisError::InvalidHeader)
+        );
+    }
+
+    #[test]
+    fn round_up_division() {
+        assert_eq!(div_ceil(12, 4), 3);
+        assert_eq!(div_ceil(13, 4), 4);
+        assert_eq!(div_ceil(0, 4), 0);
+        assert_eq!(div_ceil(u32::MAX, 4), 1 << 30);
+    }
+
+    #[test]
+    fn check_row_pitch() {
+        let params = TranscodeParams::default();
+        assert_eq!(params.check_row_pitch(OutputFormat::RGBA32, 13), Ok(()));
+
+        let params = TranscodeParams {
+            output_row_pitch: 12,
+            ..Default::default()
+        };
+        assert_eq!(
+            params.check_row_pitch(OutputFormat::RGBA32, 13),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(params.check_row_pitch(OutputFormat::BC1_RGB, 13), Ok(()));
+
+        let params = TranscodeParams {
+            output_row_pitch: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            params.check_row_pitch(OutputFormat::BC1_RGB, 13),
+            Err(BasisError::InvalidArgument)
+        );
+    }
+
+    /// Build a file with a single slice that only passes the transcoder's quick header checks.
+    fn quick_header(tex_format: basist::basis_tex_format) -> Vec<u8> {
+        let mut file = vec![0; 77 + 23];
+        file[..2].copy_from_slice(b"sB");
+        file[2] = 0x13;
+        file[4] = 77;
+        file[8] = 23;
+        file[14] = 1;
+        file[17] = 1;
+        file[20] = tex_format as u8;
+        file[65] = 77;
+        file
+    }
+
+    #[test]
+    fn can_transcode() {
+        let transcoder = BasisTranscoder::new();
+        let uastc = quick_header(basist::basis_tex_format_cUASTC4x4);
+        assert!(transcoder.can_transcode(&uastc, OutputFormat::RGBA32));
+        assert!(!transcoder.can_transcode(&uastc, OutputFormat::FXT1_RGB));
+        let etc1s = quick_header(basist::basis_tex_format_cETC1S);
+        assert!(transcoder.can_transcode(&etc1s, OutputFormat::BC1_RGB));
+    }
+
+    #[test]
+    fn uncompressed_formats() {
+        assert_eq!(OutputFormat::RGBA32.bytes_per_pixel(), Some(4));
+        assert_eq!(OutputFormat::RGB565.bytes_per_pixel(), Some(2));
+        assert_eq!(OutputFormat::BC1_RGB.bytes_per_pixel(), None);
+        assert!(OutputFormat::RGBA4444.is_uncompressed());
+        assert!(!OutputFormat::ASTC_4x4_RGBA.is_uncompressed());
+        assert_eq!(OutputFormat::BGR565.bytes_per_block(), 2);
+        assert_eq!(OutputFormat::BGR565.block_width(), 1);
+    }
+
+    #[test]
+    fn formats_with_alpha() {
+        assert!(OutputFormat::BC3_RGBA.has_alpha());
+        assert!(OutputFormat::RGBA4444.has_alpha());
+        assert!(!OutputFormat::BC1_RGB.has_alpha());
+        assert!(!OutputFormat::RGB565.has_alpha());
+    }
+
+    #[test]
+    fn output_format_display() {
+        assert_eq!(OutputFormat::BC7_RGBA.to_string(), "BC7_RGBA");
+        assert_eq!(OutputFormat::ASTC_4x4_RGBA.to_string(), "ASTC_4x4_RGBA");
+    }
+
+    #[test]
+    fn output_size() {
+        let params = TranscodeParams::default();
+        assert_eq!(
+            OutputFormat::BC7_RGBA.output_size(13, 5, &params),
+            Ok(4 * 2 * 16)
+        );
+        assert_eq!(
+            OutputFormat::RGBA32.output_size(13, 5, &params),
+            Ok(13 * 5 * 4)
+        );
+        assert_eq!(
+            OutputFormat::FXT1_RGB.output_size(13, 5, &params),
+            Ok(2 * 2 * 16)
+        );
+
+        let params = TranscodeParams {
+            output_row_pitch: 16,
+            output_rows: 8,
+            ..Default::default()
+        };
+        assert_eq!(
+            OutputFormat::BC1_RGB.output_size(13, 5, &params),
+            Ok(16 * 2 * 8)
+        );
+        assert_eq!(
+            OutputFormat::RGB565.output_size(13, 5, &params),
+            Ok(16 * 8 * 2)
+        );
+        assert_eq!(
+            OutputFormat::RGB565.output_size(17, 5, &params),
+            Err(BasisError::InvalidArgument)
+        );
+
+        let params = TranscodeParams {
+            output_row_pitch: u32::MAX,
+            output_rows: u32::MAX,
+            ..Default::default()
+        };
+        assert_eq!(
+            OutputFormat::RGBA32.output_size(1, 1, &params),
+            Err(BasisError::ImageTooLarge)
+        );
+        assert_eq!(
+            OutputFormat::RGBA32.image_size(u32::MAX, u32::MAX),
+            Err(BasisError::ImageTooLarge)
+        );
+    }
+
+    #[test]
+    fn all_formats() {
+        let all = OutputFormat::ALL;
+        assert!(all
+            .windows(2)
+            .all(|pair| (pair[0] as u32) < (pair[1] as u32)));
+    }
+
+    #[test]
+    fn enabled_formats() {
+        assert!(OutputFormat::ETC1_RGB.is_enabled());
+        assert!(OutputFormat::BC3_RGBA.is_enabled());
+        assert!(OutputFormat::RGBA32.is_enabled());
+        // Other crates may enable more `basisu-sys` features, never fewer.
+        assert!(!cfg!(feature = "bc7") || OutputFormat::BC7_RGBA.is_enabled());
+        assert!(!cfg!(feature = "etc2-eac") || OutputFormat::ETC2_EAC_RG11.is_enabled());
+
+        let formats = supported_formats();
+        assert!(formats.contains(&OutputFormat::ETC1_RGB));
+        assert!(formats.iter().all(OutputFormat::is_enabled));
+        assert_eq!(
+            formats.len(),
+            OutputFormat::ALL.iter().filter(|f| f.is_enabled()).count()
+        );
+    }
+
+    #[test]
+    fn output_format_from_str() {
+        assert_eq!("bc7".parse(), Ok(OutputFormat::BC7_RGBA));
+        assert_eq!("astc4x4".parse(), Ok(OutputFormat::ASTC_4x4_RGBA));
+        assert_eq!("etc2-rgba".parse(), Ok(OutputFormat::ETC2_RGBA));
+        assert_eq!("PVRTC1_4_RGB".parse(), Ok(OutputFormat::PVRTC1_4_RGB));
+        assert!("bc8".parse::<OutputFormat>().is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn read_file_from_stream() {
+        let mut stream = vec![0; header::HEADER_SIZE];
+        stream[..2].copy_from_slice(b"sB");
+        stream[4] = header::HEADER_SIZE as u8;
+        stream[8] = 3;
+        stream.extend_from_slice(&[1, 2, 3, 4, 5]);
+
+        let mut reader = &stream[..];
+        let data = read_file(&mut reader).unwrap();
+        assert_eq!(data.len(), header::HEADER_SIZE + 3);
+        assert_eq!(reader, [4, 5]);
+
+        assert_eq!(
+            read_file(&stream[..header::HEADER_SIZE + 1]),
+            Err(BasisError::Io(std::io::ErrorKind::UnexpectedEof))
+        );
+        assert_eq!(
+            read_file(&b"\xABKTX 20\xBB\r\n\x1A\n"[..]),
+            Err(BasisError::Io(std::io::ErrorKind::UnexpectedEof))
+        );
+        // A header claiming 4 GiB of data doesn't make the reader allocate it up front.
+        stream[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            read_file(&stream[..]),
+            Err(BasisError::Io(std::io::ErrorKind::UnexpectedEof))
+        );
+        stream[0] = b'x';
+        assert_eq!(read_file(&stream[..]), Err(BasisError::InvalidHeader));
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_and_read_from_stream() {
+        let rgba = [200; 8 * 8 * 4];
+        let mut compressor = Compressor::new(CompressorParams::new());
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let mut stream = compressor.compress().unwrap();
+        let len = stream.len();
+        stream.extend_from_slice(b"trailing data");
+
+        let mut reader = &stream[..];
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding_reader(&mut reader).unwrap();
+        assert_eq!(file.get_total_images(), 1);
+        assert_eq!(reader, b"trailing data");
+        assert_eq!(reader.as_ptr(), stream[len..].as_ptr());
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_and_cache_metadata() {
+        let rgba = [90; 16 * 8 * 4];
+        let mut compressor = Compressor::new(CompressorParams::new().mip_gen(true));
+        compressor.add_image_rgba(&rgba, 16, 8).unwrap();
+        compressor.add_image_rgba(&rgba, 16, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding(&data).unwrap();
+        let uncached = BasisTranscoder::metadata_only();
+        assert_eq!(
+            file.get_file_info(),
+            &uncached.get_file_info(&data).unwrap()
+        );
+        assert_eq!(file.slices(), &uncached.get_slice_info(&data).unwrap()[..]);
+        let parsed = header::ParsedHeader::parse(&data).unwrap();
+        assert_eq!(file.get_total_images(), uncached.get_total_images(&data));
+        for image in 0..2 {
+            assert_eq!(
+                file.get_image_info(image),
+                uncached.get_image_info(&data, image)
+            );
+            assert_eq!(file.get_total_image_levels(image), 5);
+            for level in 0..5 {
+                assert_eq!(
+                    file.level_info(image, level),
+                    uncached.get_level_info(&data, image, level)
+                );
+                assert_eq!(
+                    file.level_data_range(image, level).ok(),
+                    parsed.level_data_range(image, level)
+                );
+            }
+        }
+        assert_eq!(file.level_dimensions(1, 4), Ok((1, 1)));
+        assert_eq!(
+            file.get_image_info(2),
+            Err(BasisError::ImageIndexOutOfRange { index: 2, total: 2 })
+        );
+    }
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_and_transcode_to_linear() {
+        let rgba: Vec<u8> = (0..8 * 8 * 4).map(|i| (i * 3) as u8).collect();
+        let mut compressor = Compressor::new(CompressorParams::new());
+        compressor.add_image_rgba(&rgba, 8, 8).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let mut transcoder = BasisTranscoder::new();
+        let file = transcoder.start_transcoding(&data).unwrap();
+        let srgb = file
+            .transcode_image_level_to_vec(0, 0, OutputFormat::RGBA32)
+            .unwrap();
+        let mut linear = vec![0; srgb.len()];
+        let params = TranscodeParams {
+            color_conversion: ColorConversion::SrgbToLinear,
+            ..Default::default()
+        };
+        file.transcode_image_level_with_params(0, 0, &mut linear, OutputFormat::RGBA32, params)
+            .unwrap();
+        assert_ne!(srgb, linear);
+        for (srgb, linear) in srgb.chunks_exact(4).zip(linear.chunks_exact(4)) {
+            assert!(srgb[..3].iter().zip(&linear[..3]).all(|(s, l)| l <= s));
+            assert_eq!(srgb[3], linear[3]);
+        }
+
+        let mut bc1 = vec![0; 32];
+        assert_eq!(
+            file.transcode_image_level_with_params(0, 0, &mut bc1, OutputFormat::BC1_RGB, params),
+            Err(BasisError::InvalidArgument)
+        );
+    }
 }
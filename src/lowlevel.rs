@@ -0,0 +1,710 @@
+//! Transcoders for compressed slice data stored outside of .basis and .ktx2 files, for engines
+//! that keep textures in a container format of their own.
+
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem::{self, MaybeUninit};
+use core::ops::Range;
+use core::{ptr, slice};
+
+use basisu_sys::{basist, shim};
+
+use crate::{
+    data_len, div_ceil, format_supported, global_codebook, global_codebook_ptr, init,
+    output_blocks, postprocess, BasisError, OutputFormat, SelectorCodebook, TexFormat,
+    TranscodeParams, TranscoderState, Workspace,
+};
+
+/// Size of a UASTC block in bytes.
+const UASTC_BLOCK_SIZE: usize = 16;
+
+/// Describes one ETC1S image within a buffer of compressed data, for
+/// [`Etc1sTranscoder::transcode_image`].
+///
+/// In a .basis file, these are the fields of the slice descriptors of a level.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Etc1sImageDesc {
+    /// Width of the image before padding to a multiple of 4.
+    pub orig_width: u32,
+    /// Height of the image before padding to a multiple of 4.
+    pub orig_height: u32,
+    /// Mip level of the image. Video P-frames are decoded relative to the previous frame of the
+    /// same level.
+    pub level_index: u32,
+    /// Bytes of the data holding the color slice.
+    pub rgb: Range<usize>,
+    /// Bytes of the data holding the alpha slice, for images with alpha.
+    pub alpha: Option<Range<usize>>,
+    /// Whether the image is a frame of a video.
+    pub is_video: bool,
+}
+
+/// An entry of the ETC1S endpoint codebook.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Etc1sEndpoint {
+    /// Base color of the blocks using the endpoint, with 5 bits per channel.
+    pub color5: [u8; 3],
+    /// Index of the ETC1 intensity modifier table, from 0 to 7.
+    pub inten5: u8,
+}
+
+/// An entry of the ETC1S selector codebook: which of the four colors given by an endpoint each
+/// pixel of a 4x4 block uses.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Etc1sSelector {
+    /// The 2 bit selectors of each row, with the pixel at `x` in bits `2 * x` and `2 * x + 1`.
+    pub selectors: [u8; 4],
+}
+
+impl Etc1sSelector {
+    /// Return the selector of the pixel at (`x`, `y`), from 0 for the darkest color to 3 for the
+    /// brightest.
+    pub fn get(&self, x: u32, y: u32) -> u8 {
+        (self.selectors[y as usize] >> (x * 2)) & 3
+    }
+}
+
+/// A transcoder for raw ETC1S slices, using codebooks and Huffman tables supplied by the caller
+/// instead of reading them from a file header.
+///
+/// Decode the codebooks with [`decode_palettes`](Self::decode_palettes) and the tables with
+/// [`decode_tables`](Self::decode_tables) before transcoding. They are kept until the next call
+/// to either, so any number of images sharing them can then be transcoded.
+pub struct Etc1sTranscoder {
+    transcoder: *mut basist::basisu_lowlevel_etc1s_transcoder,
+    codebook: Option<Arc<SelectorCodebook>>,
+    has_palettes: bool,
+    has_tables: bool,
+}
+
+// The C++ transcoder is uniquely owned heap memory. Decoding palettes and tables takes `&mut
+// self`, and transcoding only reads them since every call passes its own decoder state.
+unsafe impl Send for Etc1sTranscoder {}
+unsafe impl Sync for Etc1sTranscoder {}
+
+impl Etc1sTranscoder {
+    /// Create a transcoder that uses the built-in global selector codebook, which is only built
+    /// once palettes are decoded.
+    pub fn new() -> Self {
+        init();
+
+        Self {
+            transcoder: unsafe { shim::new_lowlevel_etc1s_transcoder(global_codebook_ptr()) },
+            codebook: None,
+            has_palettes: false,
+            has_tables: false,
+        }
+    }
+
+    /// Create a transcoder that decodes palettes using `codebook` instead of the built-in global
+    /// selector codebook.
+    pub fn with_selector_codebook(codebook: Arc<SelectorCodebook>) -> Self {
+        init();
+
+        Self {
+            transcoder: unsafe { shim::new_lowlevel_etc1s_transcoder(codebook.as_ptr()) },
+            codebook: Some(codebook),
+            has_palettes: false,
+            has_tables: false,
+        }
+    }
+
+    /// Return the custom selector codebook this transcoder was created with, if any.
+    pub fn get_selector_codebook(&self) -> Option<&Arc<SelectorCodebook>> {
+        self.codebook.as_ref()
+    }
+
+    /// Decode the endpoint and selector codebooks, stored as in a .basis file.
+    pub fn decode_palettes(
+        &mut self,
+        num_endpoints: u32,
+        endpoints: &[u8],
+        num_selectors: u32,
+        selectors: &[u8],
+    ) -> Result<(), BasisError> {
+        if self.codebook.is_none() {
+            global_codebook();
+        }
+        self.has_palettes = false;
+        unsafe {
+            if !(*self.transcoder).decode_palettes(
+                num_endpoints,
+                endpoints.as_ptr(),
+                data_len(endpoints)?,
+                num_selectors,
+                selectors.as_ptr(),
+                data_len(selectors)?,
+            ) {
+                return Err(BasisError::InvalidFileContents);
+            }
+        }
+        self.has_palettes = true;
+        Ok(())
+    }
+
+    /// Decode the Huffman tables used by the slices, stored as in a .basis file.
+    pub fn decode_tables(&mut self, tables: &[u8]) -> Result<(), BasisError> {
+        self.has_tables = false;
+        unsafe {
+            if !(*self.transcoder).decode_tables(tables.as_ptr(), data_len(tables)?) {
+                return Err(BasisError::InvalidFileContents);
+            }
+        }
+        self.has_tables = true;
+        Ok(())
+    }
+
+    /// Return the endpoint codebook decoded by [`decode_palettes`](Self::decode_palettes), which
+    /// is empty until palettes have been decoded.
+    pub fn get_endpoints(&self) -> Vec<Etc1sEndpoint> {
+        if !self.has_palettes {
+            return Vec::new();
+        }
+        unsafe {
+            let total = shim::get_etc1s_total_endpoints(self.transcoder);
+            let mut endpoints: Vec<shim::etc1s_endpoint> = vec![mem::zeroed(); total as usize];
+            shim::get_etc1s_endpoints(self.transcoder, endpoints.as_mut_ptr());
+            endpoints
+                .iter()
+                .map(|endpoint| Etc1sEndpoint {
+                    color5: endpoint.color5,
+                    inten5: endpoint.inten5,
+                })
+                .collect()
+        }
+    }
+
+    /// Return the selector codebook decoded by [`decode_palettes`](Self::decode_palettes), which
+    /// is empty until palettes have been decoded.
+    ///
+    /// For files using the global selector codebook, these are the selectors after looking them
+    /// up in it.
+    pub fn get_selectors(&self) -> Vec<Etc1sSelector> {
+        if !self.has_palettes {
+            return Vec::new();
+        }
+        unsafe {
+            let total = shim::get_etc1s_total_selectors(self.transcoder);
+            let mut selectors: Vec<shim::etc1s_selector> = vec![mem::zeroed(); total as usize];
+            shim::get_etc1s_selectors(self.transcoder, selectors.as_mut_ptr());
+            selectors
+                .iter()
+                .map(|selector| Etc1sSelector {
+                    selectors: selector.selectors,
+                })
+                .collect()
+        }
+    }
+
+    /// Return the number of bytes needed to hold `image` when transcoded to `output_format` with
+    /// tightly packed rows.
+    pub fn required_output_size(
+        &self,
+        image: &Etc1sImageDesc,
+        output_format: OutputFormat,
+    ) -> Result<usize, BasisError> {
+        output_format.image_size(image.orig_width, image.orig_height)
+    }
+
+    /// Transcode `image`, stored in `data`, writing it to `output` and returning the number of
+    /// bytes written.
+    ///
+    /// Every call uses fresh decoder state, so video P-frames will not decode correctly; use
+    /// [`transcode_image_with_workspace`](Self::transcode_image_with_workspace) with the same
+    /// workspace for all frames of a video instead.
+    pub fn transcode_image(
+        &self,
+        data: &[u8],
+        image: &Etc1sImageDesc,
+        output: &mut [u8],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+    ) -> Result<usize, BasisError> {
+        self.transcode_image_with_workspace(
+            data,
+            image,
+            output,
+            output_format,
+            params,
+            &mut Workspace::new(),
+        )
+    }
+
+    /// Like [`transcode_image`](Self::transcode_image), but using the scratch memory and
+    /// previous frame state of `workspace`.
+    pub fn transcode_image_with_workspace(
+        &self,
+        data: &[u8],
+        image: &Etc1sImageDesc,
+        output: &mut [u8],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+        workspace: &mut Workspace,
+    ) -> Result<usize, BasisError> {
+        let output = unsafe {
+            slice::from_raw_parts_mut(output.as_mut_ptr() as *mut MaybeUninit<u8>, output.len())
+        };
+        self.transcode(
+            data,
+            image,
+            output,
+            output_format,
+            params,
+            &mut workspace.basis,
+        )
+    }
+
+    fn transcode(
+        &self,
+        data: &[u8],
+        image: &Etc1sImageDesc,
+        output: &mut [MaybeUninit<u8>],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+        state: &mut TranscoderState,
+    ) -> Result<usize, BasisError> {
+        if !self.has_palettes || !self.has_tables || image.orig_width == 0 || image.orig_height == 0
+        {
+            return Err(BasisError::InvalidArgument);
+        }
+        if !format_supported(output_format, TexFormat::ETC1S) {
+            return Err(BasisError::UnsupportedFormat(output_format));
+        }
+        postprocess::check(output_format, &params)?;
+        let len = data_len(data)?;
+        // Both ranges are within `data`, which is at most `u32::MAX` bytes long.
+        let range = |range: &Range<usize>| match data.get(range.clone()) {
+            Some(bytes) => Ok((range.start as u32, bytes.len() as u32)),
+            None => Err(BasisError::InvalidArgument),
+        };
+        let (rgb_offset, rgb_length) = range(&image.rgb)?;
+        let (alpha_offset, alpha_length) = match &image.alpha {
+            Some(alpha) => range(alpha)?,
+            None => (0, 0),
+        };
+
+        let needed = output_format.output_size(image.orig_width, image.orig_height, &params)?;
+        if output.len() < needed {
+            return Err(BasisError::OutputBufferTooSmall {
+                needed,
+                got: output.len(),
+            });
+        }
+
+        unsafe {
+            if !(*self.transcoder).transcode_image(
+                output_format as basist::transcoder_texture_format,
+                output.as_mut_ptr() as *mut _,
+                output_blocks(output.len(), output_format.bytes_per_block()),
+                data.as_ptr(),
+                len,
+                div_ceil(image.orig_width, 4),
+                div_ceil(image.orig_height, 4),
+                image.orig_width,
+                image.orig_height,
+                image.level_index,
+                rgb_offset,
+                rgb_length,
+                alpha_offset,
+                alpha_length,
+                params.decode_flags.bits(),
+                image.alpha.is_some(),
+                image.is_video,
+                params.output_row_pitch,
+                state.0,
+                params.output_rows,
+            ) {
+                return Err(BasisError::SliceDecodeFailed);
+            }
+        }
+        postprocess::apply(
+            output,
+            output_format,
+            image.orig_width,
+            image.orig_height,
+            &params,
+        );
+        Ok(needed)
+    }
+}
+
+impl Default for Etc1sTranscoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Etc1sTranscoder {
+    fn drop(&mut self) {
+        unsafe { shim::delete_lowlevel_etc1s_transcoder(self.transcoder) }
+    }
+}
+
+/// Describes one UASTC image stored as raw 16 byte blocks, for
+/// [`UastcTranscoder::transcode_image`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct UastcImageDesc {
+    /// Width of the image before padding to a multiple of 4.
+    pub orig_width: u32,
+    /// Height of the image before padding to a multiple of 4.
+    pub orig_height: u32,
+    /// Whether the blocks hold meaningful alpha, which decides how formats like BC3 and ETC2
+    /// RGBA are filled in.
+    pub has_alpha: bool,
+}
+
+/// A transcoder for raw UASTC blocks, such as the slices of a .basis file or the levels of a
+/// .ktx2 file without supercompression.
+///
+/// UASTC blocks are independent of each other, so any rectangle of blocks, stored row by row, can
+/// be transcoded as an image of its own. This allows updating part of a texture without
+/// transcoding all of it.
+pub struct UastcTranscoder(*mut basist::basisu_lowlevel_uastc_transcoder);
+
+// The C++ transcoder has no state of its own.
+unsafe impl Send for UastcTranscoder {}
+unsafe impl Sync for UastcTranscoder {}
+
+impl UastcTranscoder {
+    /// Create a new transcoder.
+    pub fn new() -> Self {
+        init();
+
+        Self(unsafe { shim::new_lowlevel_uastc_transcoder() })
+    }
+
+    /// Return the number of bytes needed to hold `image` when transcoded to `output_format` with
+    /// tightly packed rows.
+    pub fn required_output_size(
+        &self,
+        image: &UastcImageDesc,
+        output_format: OutputFormat,
+    ) -> Result<usize, BasisError> {
+        output_format.image_size(image.orig_width, image.orig_height)
+    }
+
+    /// Transcode the UASTC `blocks` of `image`, writing it to `output` and returning the number of
+    /// bytes written. `blocks` must hold at least one block for each 4x4 pixels of the image.
+    pub fn transcode_image(
+        &self,
+        blocks: &[u8],
+        image: &UastcImageDesc,
+        output: &mut [u8],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+    ) -> Result<usize, BasisError> {
+        if image.orig_width == 0 || image.orig_height == 0 {
+            return Err(BasisError::InvalidArgument);
+        }
+        if !format_supported(output_format, TexFormat::UASTC4x4) {
+            return Err(BasisError::UnsupportedFormat(output_format));
+        }
+        postprocess::check(output_format, &params)?;
+        let (num_blocks_x, num_blocks_y) = (
+            div_ceil(image.orig_width, 4),
+            div_ceil(image.orig_height, 4),
+        );
+        let blocks_len = (num_blocks_x as usize)
+            .checked_mul(num_blocks_y as usize)
+            .and_then(|total_blocks| total_blocks.checked_mul(UASTC_BLOCK_SIZE))
+            .ok_or(BasisError::ImageTooLarge)?;
+        let blocks = blocks
+            .get(..blocks_len)
+            .ok_or(BasisError::InvalidArgument)?;
+        let len = data_len(blocks)?;
+
+        let needed = output_format.output_size(image.orig_width, image.orig_height, &params)?;
+        if output.len() < needed {
+            return Err(BasisError::OutputBufferTooSmall {
+                needed,
+                got: output.len(),
+            });
+        }
+
+        unsafe {
+            if !(*self.0).transcode_image(
+                output_format as basist::transcoder_texture_format,
+                output.as_mut_ptr() as *mut _,
+                output_blocks(output.len(), output_format.bytes_per_block()),
+                blocks.as_ptr(),
+                len,
+                num_blocks_x,
+                num_blocks_y,
+                image.orig_width,
+                image.orig_height,
+                0,
+                0,
+                len,
+                params.decode_flags.bits(),
+                image.has_alpha,
+                false,
+                params.output_row_pitch,
+                ptr::null_mut(),
+                params.output_rows,
+                -1,
+                -1,
+            ) {
+                return Err(BasisError::SliceDecodeFailed);
+            }
+        }
+        // The transcoder only ever writes initialized bytes.
+        let output = unsafe { &mut *(output as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        postprocess::apply(
+            output,
+            output_format,
+            image.orig_width,
+            image.orig_height,
+            &params,
+        );
+        Ok(needed)
+    }
+}
+
+/// Decode a single UASTC `block` to its 4x4 RGBA8 pixels, stored row by row.
+///
+/// This is meant for debugging, tools and software sampling, where transcoding a whole level
+/// would be wasteful. `srgb` selects the sRGB variant of ASTC decoding, which interpolates the
+/// colors of some blocks slightly differently.
+pub fn unpack_uastc_block(
+    block: &[u8; UASTC_BLOCK_SIZE],
+    srgb: bool,
+) -> Result<[u8; 4 * 4 * 4], BasisError> {
+    init();
+
+    let mut pixels = [0; 4 * 4 * 4];
+    if !unsafe { shim::unpack_uastc_block(block.as_ptr(), pixels.as_mut_ptr(), srgb) } {
+        return Err(BasisError::SliceDecodeFailed);
+    }
+    Ok(pixels)
+}
+
+impl Default for UastcTranscoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for UastcTranscoder {
+    fn drop(&mut self) {
+        unsafe { shim::delete_lowlevel_uastc_transcoder(self.0) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selector_pixels() {
+        let selector = Etc1sSelector {
+            selectors: [0b11_10_01_00, 0, 0b01 << 6, 0],
+        };
+        assert_eq!(selector.get(0, 0), 0);
+        assert_eq!(selector.get(2, 0), 2);
+        assert_eq!(selector.get(3, 0), 3);
+        assert_eq!(selector.get(3, 2), 1);
+        assert_eq!(selector.get(1, 3), 0);
+    }
+
+    #[test]
+    fn transcoders_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Etc1sTranscoder>();
+        assert_send_sync::<UastcTranscoder>();
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_and_transcode_raw_etc1s_slices() {
+        use crate::header::ParsedHeader;
+        use crate::{BasisTranscoder, Compressor, CompressorParams};
+
+        let rgba: Vec<u8> = (0..32 * 16 * 4).map(|i| (i % 251) as u8).collect();
+        let mut compressor = Compressor::new(CompressorParams::new().mip_gen(true));
+        compressor.add_image_rgba(&rgba, 32, 16).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let parsed = ParsedHeader::parse(&data).unwrap();
+        let header = &parsed.header;
+        let bytes = |offset: u32, size: u32| &data[offset as usize..(offset + size) as usize];
+        let mut transcoder = Etc1sTranscoder::new();
+        assert!(transcoder.get_endpoints().is_empty());
+        let image = Etc1sImageDesc {
+            orig_width: 32,
+            orig_height: 16,
+            ..Default::default()
+        };
+        let mut output = vec![0; 32 * 16 * 4];
+        assert_eq!(
+            transcoder.transcode_image(
+                &data,
+                &image,
+                &mut output,
+                OutputFormat::RGBA32,
+                TranscodeParams::default()
+            ),
+            Err(BasisError::InvalidArgument)
+        );
+
+        transcoder
+            .decode_palettes(
+                header.total_endpoints as u32,
+                bytes(header.endpoint_cb_file_ofs, header.endpoint_cb_file_size),
+                header.total_selectors as u32,
+                bytes(header.selector_cb_file_ofs, header.selector_cb_file_size),
+            )
+            .unwrap();
+        transcoder
+            .decode_tables(bytes(header.tables_file_ofs, header.tables_file_size))
+            .unwrap();
+        let endpoints = transcoder.get_endpoints();
+        let selectors = transcoder.get_selectors();
+        assert_eq!(endpoints.len(), header.total_endpoints as usize);
+        assert_eq!(selectors.len(), header.total_selectors as usize);
+        assert!(endpoints
+            .iter()
+            .all(|endpoint| endpoint.color5.iter().all(|&c| c < 32) && endpoint.inten5 < 8));
+
+        let mut basis = BasisTranscoder::new();
+        let file = basis.start_transcoding(&data).unwrap();
+        for level in parsed.image_levels(0) {
+            let slice = parsed.slices[level.slice_index as usize];
+            let image = Etc1sImageDesc {
+                orig_width: slice.orig_width as u32,
+                orig_height: slice.orig_height as u32,
+                level_index: level.level_index,
+                rgb: slice.data_range(),
+                ..Default::default()
+            };
+            for &format in &[OutputFormat::ETC1_RGB, OutputFormat::RGBA32] {
+                let mut raw = vec![0; transcoder.required_output_size(&image, format).unwrap()];
+                let len = transcoder
+                    .transcode_image(&data, &image, &mut raw, format, TranscodeParams::default())
+                    .unwrap();
+                assert_eq!(len, raw.len());
+                let expected = file
+                    .transcode_image_level_to_vec(0, level.level_index, format)
+                    .unwrap();
+                assert_eq!(raw, expected);
+            }
+        }
+
+        let out_of_range = Etc1sImageDesc {
+            rgb: 0..data.len() + 1,
+            ..image
+        };
+        assert_eq!(
+            transcoder.transcode_image(
+                &data,
+                &out_of_range,
+                &mut output,
+                OutputFormat::RGBA32,
+                TranscodeParams::default()
+            ),
+            Err(BasisError::InvalidArgument)
+        );
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_and_transcode_raw_uastc_blocks() {
+        use crate::encoder::UastcLevel;
+        use crate::header::ParsedHeader;
+        use crate::{BasisTranscoder, Compressor, CompressorParams};
+
+        let rgba: Vec<u8> = (0..12 * 8 * 4).map(|i| (i % 253) as u8).collect();
+        let mut compressor = Compressor::new(CompressorParams::new().uastc(UastcLevel::Fastest));
+        compressor.add_image_rgba(&rgba, 12, 8).unwrap();
+        let data = compressor.compress().unwrap();
+        let parsed = ParsedHeader::parse(&data).unwrap();
+        let blocks = &data[parsed.slices[0].data_range()];
+        assert_eq!(blocks.len(), 3 * 2 * UASTC_BLOCK_SIZE);
+
+        let transcoder = UastcTranscoder::new();
+        let image = UastcImageDesc {
+            orig_width: 12,
+            orig_height: 8,
+            has_alpha: false,
+        };
+        let mut basis = BasisTranscoder::new();
+        let file = basis.start_transcoding(&data).unwrap();
+        for &format in &[OutputFormat::BC7_RGBA, OutputFormat::RGBA32] {
+            let mut output = vec![0; transcoder.required_output_size(&image, format).unwrap()];
+            transcoder
+                .transcode_image(
+                    blocks,
+                    &image,
+                    &mut output,
+                    format,
+                    TranscodeParams::default(),
+                )
+                .unwrap();
+            assert_eq!(
+                output,
+                file.transcode_image_level_to_vec(0, 0, format).unwrap()
+            );
+        }
+
+        // The second row of blocks on its own.
+        let row = UastcImageDesc {
+            orig_height: 4,
+            ..image
+        };
+        let mut output = vec![0; 12 * 4 * 4];
+        transcoder
+            .transcode_image(
+                &blocks[3 * UASTC_BLOCK_SIZE..],
+                &row,
+                &mut output,
+                OutputFormat::RGBA32,
+                TranscodeParams::default(),
+            )
+            .unwrap();
+        let full = file
+            .transcode_image_level_to_vec(0, 0, OutputFormat::RGBA32)
+            .unwrap();
+        assert_eq!(output, full[12 * 4 * 4..]);
+
+        assert_eq!(
+            transcoder.transcode_image(
+                &blocks[1..],
+                &image,
+                &mut output,
+                OutputFormat::RGBA32,
+                TranscodeParams::default()
+            ),
+            Err(BasisError::InvalidArgument)
+        );
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_and_unpack_uastc_blocks() {
+        use core::convert::TryInto;
+
+        use crate::encoder::UastcLevel;
+        use crate::header::ParsedHeader;
+        use crate::{BasisTranscoder, Compressor, CompressorParams};
+
+        let rgba: Vec<u8> = (0..8 * 4 * 4).map(|i| (i * 5 % 256) as u8).collect();
+        let mut compressor = Compressor::new(CompressorParams::new().uastc(UastcLevel::Fastest));
+        compressor.add_image_rgba(&rgba, 8, 4).unwrap();
+        let data = compressor.compress().unwrap();
+        let parsed = ParsedHeader::parse(&data).unwrap();
+        let blocks = &data[parsed.slices[0].data_range()];
+
+        let mut basis = BasisTranscoder::new();
+        let file = basis.start_transcoding(&data).unwrap();
+        let expected = file
+            .transcode_image_level_to_vec(0, 0, OutputFormat::RGBA32)
+            .unwrap();
+        for (i, block) in blocks.chunks_exact(UASTC_BLOCK_SIZE).enumerate() {
+            let pixels = unpack_uastc_block(block.try_into().unwrap(), false).unwrap();
+            for (y, row) in pixels.chunks_exact(16).enumerate() {
+                let start = (y * 8 + i * 4) * 4;
+                assert_eq!(row, &expected[start..start + 16]);
+            }
+        }
+    }
+}
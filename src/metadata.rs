@@ -0,0 +1,59 @@
+//! Functions for inspecting .basis files without a [`BasisTranscoder`].
+//!
+//! None of these need the selector codebook or the transcoder's global tables, so unlike
+//! [`BasisTranscoder::new`] they don't trigger any library wide initialization.
+
+use alloc::vec::Vec;
+
+use crate::{BasisError, BasisTranscoder, FileInfo, ImageInfo, SliceInfo, TexFormat, TextureType};
+
+/// Check just whether the header indicates a valid .basis file.
+pub fn validate_file_header(data: &[u8]) -> bool {
+    BasisTranscoder::metadata_only().validate_file_header(data)
+}
+
+/// Return whether the file checksums are valid. This is an expensive operation because it must
+/// scan the full file data.
+pub fn validate_file_checksums(data: &[u8], full_validation: bool) -> bool {
+    BasisTranscoder::metadata_only().validate_file_checksums(data, full_validation)
+}
+
+/// Return the number of images in the provided .basis file.
+pub fn get_total_images(data: &[u8]) -> u32 {
+    BasisTranscoder::metadata_only().get_total_images(data)
+}
+
+/// Return the number of levels in the indicated image of the provided .basis file.
+pub fn get_total_image_levels(data: &[u8], image_index: u32) -> u32 {
+    BasisTranscoder::metadata_only().get_total_image_levels(data, image_index)
+}
+
+/// Return how the images in the provided .basis file should be interpreted.
+pub fn get_texture_type(data: &[u8]) -> Result<TextureType, BasisError> {
+    BasisTranscoder::metadata_only().get_texture_type(data)
+}
+
+/// Return whether the provided .basis file uses ETC1S or UASTC compression.
+pub fn get_tex_format(data: &[u8]) -> Result<TexFormat, BasisError> {
+    BasisTranscoder::metadata_only().get_tex_format(data)
+}
+
+/// Return the two user defined 32-bit fields stored in the header of the provided .basis file.
+pub fn get_userdata(data: &[u8]) -> Result<(u32, u32), BasisError> {
+    BasisTranscoder::metadata_only().get_userdata(data)
+}
+
+/// Return summary information about the provided .basis file.
+pub fn get_file_info(data: &[u8]) -> Result<FileInfo, BasisError> {
+    BasisTranscoder::metadata_only().get_file_info(data)
+}
+
+/// Return information about every slice of the provided .basis file, in file order.
+pub fn get_slice_info(data: &[u8]) -> Result<Vec<SliceInfo>, BasisError> {
+    BasisTranscoder::metadata_only().get_slice_info(data)
+}
+
+/// Return information about the indicated image of the provided .basis file.
+pub fn get_image_info(data: &[u8], image_index: u32) -> Result<ImageInfo, BasisError> {
+    BasisTranscoder::metadata_only().get_image_info(data, image_index)
+}
@@ -0,0 +1,43 @@
+//! One-time initialization of the C++ library's global state. This is `std::sync::Once` when
+//! `std` is available, and otherwise a spinning equivalent built on atomics.
+
+#[cfg(feature = "std")]
+pub(crate) use std::sync::Once;
+
+#[cfg(not(feature = "std"))]
+pub(crate) use spin::Once;
+
+#[cfg(not(feature = "std"))]
+mod spin {
+    use core::hint;
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    const INCOMPLETE: u8 = 0;
+    const RUNNING: u8 = 1;
+    const COMPLETE: u8 = 2;
+
+    pub(crate) struct Once(AtomicU8);
+
+    impl Once {
+        pub(crate) const fn new() -> Self {
+            Self(AtomicU8::new(INCOMPLETE))
+        }
+
+        /// Run `f` if no other call has, and wait until it has finished otherwise. Unlike
+        /// `std::sync::Once`, a panic in `f` leaves other callers waiting forever.
+        pub(crate) fn call_once<F: FnOnce()>(&self, f: F) {
+            if self
+                .0
+                .compare_exchange(INCOMPLETE, RUNNING, Ordering::Acquire, Ordering::Acquire)
+                .is_ok()
+            {
+                f();
+                self.0.store(COMPLETE, Ordering::Release);
+                return;
+            }
+            while self.0.load(Ordering::Acquire) != COMPLETE {
+                hint::spin_loop();
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Transcoding every level of a file in parallel, available with the `rayon` feature.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use rayon::prelude::*;
+
+use crate::ktx2::Ktx2Transcoder;
+use crate::{
+    BasisError, BasisFileTranscoder, OutputFormat, TextureType, TranscodeParams, Transcoder,
+    TranscoderState,
+};
+
+/// Every `(image_index, level_index)` pair of a file, in order.
+fn image_levels(total_images: u32, total_levels: impl Fn(u32) -> u32) -> Vec<(u32, u32)> {
+    (0..total_images)
+        .flat_map(|image| (0..total_levels(image)).map(move |level| (image, level)))
+        .collect()
+}
+
+/// Group the levels transcoded for `levels`, which were listed in order, by image.
+fn group_by_image(
+    total_images: u32,
+    levels: &[(u32, u32)],
+    data: Vec<Vec<u8>>,
+) -> Vec<Vec<Vec<u8>>> {
+    let mut images = vec![Vec::new(); total_images as usize];
+    for (&(image, _), level) in levels.iter().zip(data) {
+        images[image as usize].push(level);
+    }
+    images
+}
+
+impl BasisFileTranscoder<'_> {
+    /// Transcode every mip level of every image to `output_format` on the rayon thread pool.
+    ///
+    /// The result is indexed by image and then by level, as with
+    /// [`transcode_image_level_to_vec`](Self::transcode_image_level_to_vec). Each worker thread
+    /// decodes with its own transcoder state. Video frames depend on the previous frame, so video
+    /// files are rejected with [`BasisError::InvalidArgument`]; use
+    /// [`video_decoder`](Self::video_decoder) for them instead.
+    pub fn transcode_all_levels_parallel(
+        &self,
+        output_format: OutputFormat,
+    ) -> Result<Vec<Vec<Vec<u8>>>, BasisError> {
+        if self.get_texture_type() == TextureType::VideoFrames {
+            return Err(BasisError::InvalidArgument);
+        }
+        let total_images = self.get_total_images();
+        let levels = image_levels(total_images, |image| self.get_total_image_levels(image));
+        let data = levels
+            .par_iter()
+            .map_init(TranscoderState::new, |state, &(image, level)| {
+                let size = self.required_output_size(image, level, output_format)?;
+                let mut output = Vec::with_capacity(size);
+                let len = self.transcode_image_level_with_state(
+                    image,
+                    level,
+                    output.spare_capacity_mut(),
+                    output_format,
+                    TranscodeParams::default(),
+                    state,
+                )?;
+                unsafe { output.set_len(len) };
+                Ok(output)
+            })
+            .collect::<Result<Vec<_>, BasisError>>()?;
+        Ok(group_by_image(total_images, &levels, data))
+    }
+}
+
+impl Transcoder<'_> {
+    /// Transcode every mip level of every image to `output_format` on the rayon thread pool,
+    /// indexed by image and then by level. Images are numbered as described on [`Transcoder`], so
+    /// the layers and cubemap faces of .ktx2 files are spread across threads too.
+    ///
+    /// See [`BasisFileTranscoder::transcode_all_levels_parallel`]. The .ktx2 transcoder can only
+    /// be used by one thread at a time, so each worker thread parses its own copy of a .ktx2 file.
+    pub fn transcode_all_levels_parallel(
+        &self,
+        output_format: OutputFormat,
+    ) -> Result<Vec<Vec<Vec<u8>>>, BasisError> {
+        let file = match (self.as_basis(), self.as_ktx2()) {
+            (Some(file), _) => return file.transcode_all_levels_parallel(output_format),
+            (None, Some(file)) => file,
+            (None, None) => unreachable!(),
+        };
+        if self.get_texture_type() == TextureType::VideoFrames {
+            return Err(BasisError::InvalidArgument);
+        }
+        let (data, codebook) = file.parts();
+        let open = || {
+            let transcoder = match &codebook {
+                Some(codebook) => Ktx2Transcoder::with_selector_codebook(codebook.clone()),
+                None => Ktx2Transcoder::new(),
+            };
+            transcoder
+                .into_file_transcoder(data)
+                .map(Transcoder::from_ktx2)
+        };
+
+        let total_images = self.get_total_images();
+        let levels = image_levels(total_images, |image| self.get_total_image_levels(image));
+        let data = levels
+            .par_iter()
+            .map_init(open, |file, &(image, level)| {
+                let file = file.as_ref().map_err(|&error| error)?;
+                file.transcode_image_level_to_vec(image, level, output_format)
+            })
+            .collect::<Result<Vec<_>, BasisError>>()?;
+        Ok(group_by_image(total_images, &levels, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_levels() {
+        let levels = image_levels(3, |image| [2, 0, 1][image as usize]);
+        assert_eq!(levels, [(0, 0), (0, 1), (2, 0)]);
+
+        let data = vec![vec![1], vec![2], vec![3]];
+        assert_eq!(
+            group_by_image(3, &levels, data),
+            [vec![vec![1], vec![2]], vec![], vec![vec![3]]]
+        );
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_and_transcode_in_parallel() {
+        use crate::{Compressor, CompressorParams};
+
+        let rgba = [96; 64 * 64 * 4];
+        let mut compressor = Compressor::new(CompressorParams::new().mip_gen(true));
+        compressor.add_image_rgba(&rgba, 64, 64).unwrap();
+        compressor.add_image_rgba(&rgba, 64, 64).unwrap();
+        let data = compressor.compress().unwrap();
+
+        let file = Transcoder::open(&data).unwrap();
+        let images = file
+            .transcode_all_levels_parallel(OutputFormat::ETC1_RGB)
+            .unwrap();
+        assert_eq!(images.len(), 2);
+        for (image, levels) in images.iter().enumerate() {
+            assert_eq!(levels.len(), 7);
+            for (level, output) in levels.iter().enumerate() {
+                let expected = file
+                    .transcode_image_level_to_vec(
+                        image as u32,
+                        level as u32,
+                        OutputFormat::ETC1_RGB,
+                    )
+                    .unwrap();
+                assert_eq!(output, &expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! Conversions applied to uncompressed output after the transcoder has written it.
+
+use core::mem::MaybeUninit;
+
+use crate::{BasisError, OutputFormat, TranscodeParams};
+
+/// Color space conversion applied to the color channels of uncompressed output. Alpha is left
+/// unchanged.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorConversion {
+    /// Keep the colors as they were encoded.
+    #[default]
+    None,
+    /// Decode sRGB colors to linear ones.
+    SrgbToLinear,
+    /// Encode linear colors as sRGB.
+    LinearToSrgb,
+}
+
+/// `SRGB_TO_LINEAR[c]` is the 8 bit linear value of the 8 bit sRGB value `c`, rounded to nearest.
+const SRGB_TO_LINEAR: [u8; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3,
+    4, 4, 4, 4, 4, 5, 5, 5, 5, 6, 6, 6, 6, 7, 7, 7, 8, 8, 8, 8, 9, 9, 9, 10, 10, 10, 11, 11, 12,
+    12, 12, 13, 13, 13, 14, 14, 15, 15, 16, 16, 17, 17, 17, 18, 18, 19, 19, 20, 20, 21, 22, 22, 23,
+    23, 24, 24, 25, 25, 26, 27, 27, 28, 29, 29, 30, 30, 31, 32, 32, 33, 34, 35, 35, 36, 37, 37, 38,
+    39, 40, 41, 41, 42, 43, 44, 45, 45, 46, 47, 48, 49, 50, 51, 51, 52, 53, 54, 55, 56, 57, 58, 59,
+    60, 61, 62, 63, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 76, 77, 78, 79, 80, 81, 82, 84, 85,
+    86, 87, 88, 90, 91, 92, 93, 95, 96, 97, 99, 100, 101, 103, 104, 105, 107, 108, 109, 111, 112,
+    114, 115, 116, 118, 119, 121, 122, 124, 125, 127, 128, 130, 131, 133, 134, 136, 138, 139, 141,
+    142, 144, 146, 147, 149, 151, 152, 154, 156, 157, 159, 161, 163, 164, 166, 168, 170, 171, 173,
+    175, 177, 179, 181, 183, 184, 186, 188, 190, 192, 194, 196, 198, 200, 202, 204, 206, 208, 210,
+    212, 214, 216, 218, 220, 222, 224, 226, 229, 231, 233, 235, 237, 239, 242, 244, 246, 248, 250,
+    253, 255,
+];
+
+/// `LINEAR_TO_SRGB[c]` is the 8 bit sRGB value of the 8 bit linear value `c`, rounded to nearest.
+const LINEAR_TO_SRGB: [u8; 256] = [
+    0, 13, 22, 28, 34, 38, 42, 46, 50, 53, 56, 59, 61, 64, 66, 69, 71, 73, 75, 77, 79, 81, 83, 85,
+    86, 88, 90, 92, 93, 95, 96, 98, 99, 101, 102, 104, 105, 106, 108, 109, 110, 112, 113, 114, 115,
+    117, 118, 119, 120, 121, 122, 124, 125, 126, 127, 128, 129, 130, 131, 132, 133, 134, 135, 136,
+    137, 138, 139, 140, 141, 142, 143, 144, 145, 146, 147, 148, 148, 149, 150, 151, 152, 153, 154,
+    155, 155, 156, 157, 158, 159, 159, 160, 161, 162, 163, 163, 164, 165, 166, 167, 167, 168, 169,
+    170, 170, 171, 172, 173, 173, 174, 175, 175, 176, 177, 178, 178, 179, 180, 180, 181, 182, 182,
+    183, 184, 185, 185, 186, 187, 187, 188, 189, 189, 190, 190, 191, 192, 192, 193, 194, 194, 195,
+    196, 196, 197, 197, 198, 199, 199, 200, 200, 201, 202, 202, 203, 203, 204, 205, 205, 206, 206,
+    207, 208, 208, 209, 209, 210, 210, 211, 212, 212, 213, 213, 214, 214, 215, 215, 216, 216, 217,
+    218, 218, 219, 219, 220, 220, 221, 221, 222, 222, 223, 223, 224, 224, 225, 226, 226, 227, 227,
+    228, 228, 229, 229, 230, 230, 231, 231, 232, 232, 233, 233, 234, 234, 235, 235, 236, 236, 237,
+    237, 238, 238, 238, 239, 239, 240, 240, 241, 241, 242, 242, 243, 243, 244, 244, 245, 245, 246,
+    246, 246, 247, 247, 248, 248, 249, 249, 250, 250, 251, 251, 251, 252, 252, 253, 253, 254, 254,
+    255, 255,
+];
+
+impl TranscodeParams {
+    /// Whether any post-processing was requested.
+    pub(crate) fn needs_postprocess(&self) -> bool {
+        self.color_conversion != ColorConversion::None
+            || self.premultiply_alpha
+            || self.swizzle.is_some()
+    }
+}
+
+/// Fail with [`BasisError::InvalidArgument`] if `params` asks for post-processing that
+/// `output_format` doesn't support.
+pub(crate) fn check(
+    output_format: OutputFormat,
+    params: &TranscodeParams,
+) -> Result<(), BasisError> {
+    if params.needs_postprocess() && !output_format.is_uncompressed() {
+        return Err(BasisError::InvalidArgument);
+    }
+    if params.premultiply_alpha
+        && !matches!(output_format, OutputFormat::RGBA32 | OutputFormat::RGBA4444)
+    {
+        return Err(BasisError::InvalidArgument);
+    }
+    if let Some(swizzle) = params.swizzle {
+        if swizzle.iter().any(|&channel| channel > 3) {
+            return Err(BasisError::InvalidArgument);
+        }
+    }
+    Ok(())
+}
+
+/// Apply the post-processing requested by `params` to the `width` x `height` image just
+/// transcoded into `output`, which must have passed [`check`].
+pub(crate) fn apply(
+    output: &mut [MaybeUninit<u8>],
+    output_format: OutputFormat,
+    width: u32,
+    height: u32,
+    params: &TranscodeParams,
+) {
+    if !params.needs_postprocess() {
+        return;
+    }
+    let bytes_per_pixel = output_format.bytes_per_pixel().unwrap() as usize;
+    let row_pitch = match params.output_row_pitch {
+        0 => width,
+        pitch => pitch,
+    } as usize
+        * bytes_per_pixel;
+    let row_len = width as usize * bytes_per_pixel;
+    for row in output.chunks_mut(row_pitch).take(height as usize) {
+        for pixel in row[..row_len].chunks_exact_mut(bytes_per_pixel) {
+            // The transcoder wrote every pixel of the image, only row padding may be left
+            // uninitialized.
+            let pixel = unsafe { &mut *(pixel as *mut [MaybeUninit<u8>] as *mut [u8]) };
+            let mut rgba = unpack(output_format, pixel);
+            let table = match params.color_conversion {
+                ColorConversion::None => None,
+                ColorConversion::SrgbToLinear => Some(&SRGB_TO_LINEAR),
+                ColorConversion::LinearToSrgb => Some(&LINEAR_TO_SRGB),
+            };
+            if let Some(table) = table {
+                for c in &mut rgba[..3] {
+                    *c = table[*c as usize];
+                }
+            }
+            if params.premultiply_alpha {
+                let alpha = rgba[3] as u32;
+                for c in &mut rgba[..3] {
+                    *c = ((*c as u32 * alpha + 127) / 255) as u8;
+                }
+            }
+            if let Some(swizzle) = params.swizzle {
+                rgba = swizzle.map(|channel| rgba[channel as usize]);
+            }
+            pack(output_format, rgba, pixel);
+        }
+    }
+}
+
+/// Widen a `bits` bit channel value to 8 bits.
+fn expand(value: u16, bits: u32) -> u8 {
+    let max = (1 << bits) - 1;
+    ((value as u32 * 255 + max / 2) / max) as u8
+}
+
+/// Narrow an 8 bit channel value to `bits` bits.
+fn quantize(value: u8, bits: u32) -> u16 {
+    let max = (1 << bits) - 1;
+    ((value as u32 * max + 127) / 255) as u16
+}
+
+/// Read `pixel` as RGBA8. Formats without alpha read as opaque.
+fn unpack(format: OutputFormat, pixel: &[u8]) -> [u8; 4] {
+    let packed = || u16::from_ne_bytes([pixel[0], pixel[1]]);
+    match format {
+        OutputFormat::RGBA32 => [pixel[0], pixel[1], pixel[2], pixel[3]],
+        OutputFormat::RGB565 => {
+            let p = packed();
+            [
+                expand(p >> 11, 5),
+                expand((p >> 5) & 63, 6),
+                expand(p & 31, 5),
+                255,
+            ]
+        }
+        OutputFormat::BGR565 => {
+            let p = packed();
+            [
+                expand(p & 31, 5),
+                expand((p >> 5) & 63, 6),
+                expand(p >> 11, 5),
+                255,
+            ]
+        }
+        OutputFormat::RGBA4444 => {
+            let p = packed();
+            [
+                expand(p >> 12, 4),
+                expand((p >> 8) & 15, 4),
+                expand((p >> 4) & 15, 4),
+                expand(p & 15, 4),
+            ]
+        }
+        _ => unreachable!("{} is not an uncompressed format", format),
+    }
+}
+
+/// Store `rgba` into `pixel`, the inverse of [`unpack`].
+fn pack(format: OutputFormat, [r, g, b, a]: [u8; 4], pixel: &mut [u8]) {
+    let packed = match format {
+        OutputFormat::RGBA32 => {
+            pixel.copy_from_slice(&[r, g, b, a]);
+            return;
+        }
+        OutputFormat::RGB565 => quantize(r, 5) << 11 | quantize(g, 6) << 5 | quantize(b, 5),
+        OutputFormat::BGR565 => quantize(b, 5) << 11 | quantize(g, 6) << 5 | quantize(r, 5),
+        OutputFormat::RGBA4444 => {
+            quantize(r, 4) << 12 | quantize(g, 4) << 8 | quantize(b, 4) << 4 | quantize(a, 4)
+        }
+        _ => unreachable!("{} is not an uncompressed format", format),
+    };
+    pixel.copy_from_slice(&packed.to_ne_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn postprocess(output: &mut [u8], format: OutputFormat, width: u32, params: TranscodeParams) {
+        let height = (output.len() / format.bytes_per_pixel().unwrap() as usize) as u32 / width;
+        let output = unsafe { &mut *(output as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        apply(output, format, width, height, &params);
+    }
+
+    #[test]
+    fn packed_formats_round_trip() {
+        for format in [
+            OutputFormat::RGB565,
+            OutputFormat::BGR565,
+            OutputFormat::RGBA4444,
+        ] {
+            for packed in 0..=u16::MAX {
+                let mut pixel = packed.to_ne_bytes();
+                pack(format, unpack(format, &pixel), &mut pixel);
+                assert_eq!(u16::from_ne_bytes(pixel), packed, "{}", format);
+            }
+        }
+    }
+
+    #[test]
+    fn convert_color_space() {
+        let to_linear = TranscodeParams {
+            color_conversion: ColorConversion::SrgbToLinear,
+            ..Default::default()
+        };
+        let mut rgba = [0, 128, 255, 128, 188, 10, 55, 0];
+        postprocess(&mut rgba, OutputFormat::RGBA32, 2, to_linear);
+        assert_eq!(rgba, [0, 55, 255, 128, 128, 1, 10, 0]);
+
+        let to_srgb = TranscodeParams {
+            color_conversion: ColorConversion::LinearToSrgb,
+            ..Default::default()
+        };
+        postprocess(&mut rgba, OutputFormat::RGBA32, 2, to_srgb);
+        assert_eq!(rgba, [0, 128, 255, 128, 188, 13, 56, 0]);
+
+        // White stays white and black stays black in packed formats too.
+        let mut rgb565 = [0xffff_u16.to_ne_bytes(), 0u16.to_ne_bytes()].concat();
+        postprocess(&mut rgb565, OutputFormat::RGB565, 1, to_linear);
+        assert_eq!(
+            rgb565,
+            [0xffff_u16.to_ne_bytes(), 0u16.to_ne_bytes()].concat()
+        );
+    }
+
+    #[test]
+    fn premultiply_alpha() {
+        let params = TranscodeParams {
+            premultiply_alpha: true,
+            ..Default::default()
+        };
+        let mut rgba = [255, 128, 0, 128, 200, 100, 50, 255, 200, 100, 50, 0];
+        postprocess(&mut rgba, OutputFormat::RGBA32, 3, params);
+        assert_eq!(rgba, [128, 64, 0, 128, 200, 100, 50, 255, 0, 0, 0, 0]);
+
+        let mut rgba4444 = 0xf8c8u16.to_ne_bytes();
+        postprocess(&mut rgba4444, OutputFormat::RGBA4444, 1, params);
+        assert_eq!(u16::from_ne_bytes(rgba4444), 0x8468);
+
+        assert_eq!(
+            check(OutputFormat::RGB565, &params),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(check(OutputFormat::RGBA4444, &params), Ok(()));
+    }
+
+    #[test]
+    fn swizzle_channels() {
+        let bgra = TranscodeParams {
+            swizzle: Some([2, 1, 0, 3]),
+            ..Default::default()
+        };
+        let mut rgba = [1, 2, 3, 4, 5, 6, 7, 8];
+        postprocess(&mut rgba, OutputFormat::RGBA32, 2, bgra);
+        assert_eq!(rgba, [3, 2, 1, 4, 7, 6, 5, 8]);
+
+        let replicate_red = TranscodeParams {
+            swizzle: Some([0, 0, 0, 3]),
+            ..Default::default()
+        };
+        let mut rgb565 = (31u16 << 11 | 5).to_ne_bytes();
+        postprocess(&mut rgb565, OutputFormat::RGB565, 1, replicate_red);
+        assert_eq!(u16::from_ne_bytes(rgb565), 0xffff);
+
+        // Alpha reads as opaque from formats without it.
+        let alpha_to_blue = TranscodeParams {
+            swizzle: Some([0, 1, 3, 3]),
+            ..Default::default()
+        };
+        let mut bgr565 = 0u16.to_ne_bytes();
+        postprocess(&mut bgr565, OutputFormat::BGR565, 1, alpha_to_blue);
+        assert_eq!(u16::from_ne_bytes(bgr565), 31 << 11);
+
+        let invalid = TranscodeParams {
+            swizzle: Some([0, 1, 2, 4]),
+            ..Default::default()
+        };
+        assert_eq!(
+            check(OutputFormat::RGBA32, &invalid),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(
+            check(OutputFormat::BC7_RGBA, &bgra),
+            Err(BasisError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn skip_row_padding() {
+        let params = TranscodeParams {
+            color_conversion: ColorConversion::SrgbToLinear,
+            output_row_pitch: 2,
+            ..Default::default()
+        };
+        let mut rgba = [128; 16];
+        postprocess(&mut rgba, OutputFormat::RGBA32, 1, params);
+        assert_eq!(rgba[..8], [55, 55, 55, 128, 128, 128, 128, 128]);
+        assert_eq!(rgba[8..], [55, 55, 55, 128, 128, 128, 128, 128]);
+    }
+
+    #[test]
+    fn compressed_formats_need_no_postprocess() {
+        let params = TranscodeParams {
+            color_conversion: ColorConversion::LinearToSrgb,
+            ..Default::default()
+        };
+        assert_eq!(
+            check(OutputFormat::BC1_RGB, &params),
+            Err(BasisError::InvalidArgument)
+        );
+        assert_eq!(check(OutputFormat::RGB565, &params), Ok(()));
+        assert_eq!(
+            check(OutputFormat::BC1_RGB, &TranscodeParams::default()),
+            Ok(())
+        );
+    }
+}
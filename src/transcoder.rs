@@ -0,0 +1,343 @@
+//! A single entry point for both .basis and .ktx2 files.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::ktx2::{self, Ktx2FileTranscoder, Ktx2Transcoder};
+use crate::{
+    BasisError, BasisFileTranscoder, BasisTranscoder, OutputFormat, TexFormat, TextureType,
+    TranscodeParams, Workspace,
+};
+
+/// The container format of a file holding Basis Universal texture data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Container {
+    /// A .basis file.
+    Basis,
+    /// A .ktx2 file.
+    Ktx2,
+}
+
+impl Container {
+    /// Identify the container format of `data` from its first bytes.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&ktx2::IDENTIFIER) {
+            Some(Container::Ktx2)
+        } else if data.starts_with(b"sB") {
+            Some(Container::Basis)
+        } else {
+            None
+        }
+    }
+}
+
+/// A .basis or .ktx2 file that is ready to be transcoded.
+///
+/// Images are numbered the same way for both containers: a .ktx2 file with several layers or
+/// cubemap faces stores face `f` of layer `l` as image `l * faces + f`, matching the layout of
+/// .basis texture arrays and cubemap arrays. The container specific API is available through
+/// [`as_basis`](Self::as_basis) and [`as_ktx2`](Self::as_ktx2).
+pub struct Transcoder<'a> {
+    inner: Inner<'a>,
+}
+
+enum Inner<'a> {
+    Basis(Box<BasisFileTranscoder<'a>>),
+    Ktx2(Ktx2FileTranscoder<'a>),
+}
+
+impl<'a> Transcoder<'a> {
+    /// Detect the container format of `data` and prepare to transcode it with a new transcoder.
+    pub fn open(data: &'a [u8]) -> Result<Self, BasisError> {
+        let inner = match Container::detect(data) {
+            Some(Container::Basis) => {
+                Inner::Basis(Box::new(BasisTranscoder::new().into_file_transcoder(data)?))
+            }
+            Some(Container::Ktx2) => Inner::Ktx2(Ktx2Transcoder::new().into_file_transcoder(data)?),
+            None => return Err(BasisError::InvalidHeader),
+        };
+        Ok(Self { inner })
+    }
+
+    /// Wrap a .ktx2 file transcoder.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn from_ktx2(file: Ktx2FileTranscoder<'a>) -> Self {
+        Self {
+            inner: Inner::Ktx2(file),
+        }
+    }
+
+    /// Return the container format of the file.
+    pub fn container(&self) -> Container {
+        match self.inner {
+            Inner::Basis(_) => Container::Basis,
+            Inner::Ktx2(_) => Container::Ktx2,
+        }
+    }
+
+    /// Return the underlying transcoder if the file is a .basis file.
+    pub fn as_basis(&self) -> Option<&BasisFileTranscoder<'a>> {
+        match &self.inner {
+            Inner::Basis(file) => Some(file),
+            Inner::Ktx2(_) => None,
+        }
+    }
+
+    /// Return the underlying transcoder if the file is a .ktx2 file.
+    pub fn as_ktx2(&self) -> Option<&Ktx2FileTranscoder<'a>> {
+        match &self.inner {
+            Inner::Basis(_) => None,
+            Inner::Ktx2(file) => Some(file),
+        }
+    }
+
+    /// Return how the images in the file should be interpreted.
+    pub fn get_texture_type(&self) -> TextureType {
+        match &self.inner {
+            Inner::Basis(file) => file.get_texture_type(),
+            Inner::Ktx2(file) => {
+                let header = &file.header().header;
+                if header.face_count == 6 {
+                    TextureType::CubemapArray
+                } else if file.anim_data().is_some() {
+                    TextureType::VideoFrames
+                } else if header.pixel_depth > 0 {
+                    TextureType::Volume
+                } else if header.layer_count > 0 {
+                    TextureType::Texture2DArray
+                } else {
+                    TextureType::Texture2D
+                }
+            }
+        }
+    }
+
+    /// Return whether the file uses ETC1S or UASTC compression.
+    pub fn get_tex_format(&self) -> TexFormat {
+        match &self.inner {
+            Inner::Basis(file) => file.get_tex_format(),
+            Inner::Ktx2(file) => file.get_tex_format(),
+        }
+    }
+
+    /// Return the total number of images in the file.
+    pub fn get_total_images(&self) -> u32 {
+        match &self.inner {
+            Inner::Basis(file) => file.get_total_images(),
+            Inner::Ktx2(file) => file.header().total_layers() * file.header().total_faces(),
+        }
+    }
+
+    /// Return the total number of levels in the image with index `image_index`.
+    pub fn get_total_image_levels(&self, image_index: u32) -> u32 {
+        match &self.inner {
+            Inner::Basis(file) => file.get_total_image_levels(image_index),
+            Inner::Ktx2(_) if image_index >= self.get_total_images() => 0,
+            Inner::Ktx2(file) => file.header().total_levels(),
+        }
+    }
+
+    /// Return whether the file can be transcoded to `output_format`.
+    pub fn can_transcode(&self, output_format: OutputFormat) -> bool {
+        match &self.inner {
+            Inner::Basis(file) => file.can_transcode(output_format),
+            Inner::Ktx2(file) => file.can_transcode(output_format),
+        }
+    }
+
+    /// Return whether the file has an alpha channel, so transcoding should target a format that
+    /// keeps it.
+    pub fn has_alpha(&self) -> bool {
+        match &self.inner {
+            Inner::Basis(file) => file.get_file_info().has_alpha_slices,
+            Inner::Ktx2(file) => file.dfd().map_or(false, |dfd| dfd.has_alpha()),
+        }
+    }
+
+    /// Map an image index of a .ktx2 file to its array layer and cubemap face.
+    fn layer_and_face(
+        &self,
+        file: &Ktx2FileTranscoder,
+        image_index: u32,
+    ) -> Result<(u32, u32), BasisError> {
+        let total = self.get_total_images();
+        if image_index >= total {
+            return Err(BasisError::ImageIndexOutOfRange {
+                index: image_index,
+                total,
+            });
+        }
+        let faces = file.header().total_faces();
+        Ok((image_index / faces, image_index % faces))
+    }
+
+    /// Return the dimension of the indicated `image_index` / `level_index` pair.
+    pub fn level_dimensions(
+        &self,
+        image_index: u32,
+        level_index: u32,
+    ) -> Result<(u32, u32), BasisError> {
+        match &self.inner {
+            Inner::Basis(file) => file.level_dimensions(image_index, level_index),
+            Inner::Ktx2(file) => {
+                let (layer, face) = self.layer_and_face(file, image_index)?;
+                let info = file.level_info(level_index, layer, face)?;
+                Ok((info.width, info.height))
+            }
+        }
+    }
+
+    /// Return the number of bytes needed to hold the indicated `image_index` / `level_index` pair
+    /// when transcoded to `output_format` with tightly packed rows.
+    pub fn required_output_size(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output_format: OutputFormat,
+    ) -> Result<usize, BasisError> {
+        match &self.inner {
+            Inner::Basis(file) => {
+                file.required_output_size(image_index, level_index, output_format)
+            }
+            Inner::Ktx2(file) => {
+                let (layer, face) = self.layer_and_face(file, image_index)?;
+                file.required_output_size(level_index, layer, face, output_format)
+            }
+        }
+    }
+
+    /// Transcode the indicated `image_index` / `level_index` pair into a newly allocated buffer.
+    pub fn transcode_image_level_to_vec(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output_format: OutputFormat,
+    ) -> Result<Vec<u8>, BasisError> {
+        match &self.inner {
+            Inner::Basis(file) => {
+                file.transcode_image_level_to_vec(image_index, level_index, output_format)
+            }
+            Inner::Ktx2(file) => {
+                let (layer, face) = self.layer_and_face(file, image_index)?;
+                file.transcode_image_level_to_vec(level_index, layer, face, output_format)
+            }
+        }
+    }
+
+    /// Transcode the alpha channel of the indicated `image_index` / `level_index` pair to ETC2 EAC
+    /// A8 blocks. See [`BasisFileTranscoder::transcode_image_level_to_eac_a8`].
+    pub fn transcode_image_level_to_eac_a8(
+        &self,
+        image_index: u32,
+        level_index: u32,
+    ) -> Result<Vec<u8>, BasisError> {
+        match &self.inner {
+            Inner::Basis(file) => file.transcode_image_level_to_eac_a8(image_index, level_index),
+            Inner::Ktx2(file) => {
+                let (layer, face) = self.layer_and_face(file, image_index)?;
+                file.transcode_image_level_to_eac_a8(level_index, layer, face)
+            }
+        }
+    }
+
+    /// Transcode the indicated `image_index` / `level_index` pair into the provided output
+    /// buffer. The resulting data will be in format `output_format`.
+    pub fn transcode_image_level(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output: &mut [u8],
+        output_format: OutputFormat,
+    ) -> Result<(), BasisError> {
+        self.transcode_image_level_with_params(
+            image_index,
+            level_index,
+            output,
+            output_format,
+            TranscodeParams::default(),
+        )
+    }
+
+    /// Like [`transcode_image_level`](Self::transcode_image_level), but with additional
+    /// parameters controlling the transcode.
+    pub fn transcode_image_level_with_params(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output: &mut [u8],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+    ) -> Result<(), BasisError> {
+        match &self.inner {
+            Inner::Basis(file) => file.transcode_image_level_with_params(
+                image_index,
+                level_index,
+                output,
+                output_format,
+                params,
+            ),
+            Inner::Ktx2(file) => {
+                let (layer, face) = self.layer_and_face(file, image_index)?;
+                file.transcode_image_level_with_params(
+                    level_index,
+                    layer,
+                    face,
+                    output,
+                    output_format,
+                    params,
+                )
+            }
+        }
+    }
+
+    /// Like [`transcode_image_level_with_params`](Self::transcode_image_level_with_params), but
+    /// decodes using the scratch memory of `workspace` so that it can be reused across calls.
+    pub fn transcode_image_level_with_workspace(
+        &self,
+        image_index: u32,
+        level_index: u32,
+        output: &mut [u8],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+        workspace: &mut Workspace,
+    ) -> Result<(), BasisError> {
+        match &self.inner {
+            Inner::Basis(file) => file.transcode_image_level_with_workspace(
+                image_index,
+                level_index,
+                output,
+                output_format,
+                params,
+                workspace,
+            ),
+            Inner::Ktx2(file) => {
+                let (layer, face) = self.layer_and_face(file, image_index)?;
+                file.transcode_image_level_with_workspace(
+                    level_index,
+                    layer,
+                    face,
+                    output,
+                    output_format,
+                    params,
+                    workspace,
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_container() {
+        let basis = crate::header::tests::test_file(1);
+        let ktx2 = crate::ktx2::tests::test_file(4, 4, 16, &[]);
+        assert_eq!(Container::detect(&basis), Some(Container::Basis));
+        assert_eq!(Container::detect(&ktx2), Some(Container::Ktx2));
+        assert_eq!(Container::detect(b"\x89PNG\r\n\x1a\n"), None);
+        assert_eq!(Container::detect(&[]), None);
+    }
+}
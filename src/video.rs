@@ -0,0 +1,129 @@
+//! Sequential decoding of .basis video files.
+//!
+//! Video P-frames are stored as differences from the previous frame, so they can only be decoded
+//! with the decoder state left behind by transcoding every earlier frame in order.
+
+use alloc::vec::Vec;
+use core::mem::MaybeUninit;
+
+use crate::{
+    BasisError, BasisFileTranscoder, OutputFormat, TextureType, TranscodeParams, TranscoderState,
+};
+
+/// Decodes the frames of a video file one after another, carrying the state P-frames depend on.
+///
+/// Frames can only be requested in order, so the previous-frame data is always the right one.
+/// Start over from the first frame with [`rewind`](Self::rewind).
+pub struct VideoDecoder<'a> {
+    file: &'a BasisFileTranscoder<'a>,
+    level_index: u32,
+    next_frame: u32,
+    state: TranscoderState,
+}
+
+impl<'a> BasisFileTranscoder<'a> {
+    /// Return a decoder for mip level `level_index` of every frame of a video file.
+    ///
+    /// Fails with [`BasisError::InvalidArgument`] if the file isn't a video.
+    pub fn video_decoder(&self, level_index: u32) -> Result<VideoDecoder<'_>, BasisError> {
+        if self.get_texture_type() != TextureType::VideoFrames {
+            return Err(BasisError::InvalidArgument);
+        }
+        let total = self.get_total_image_levels(0);
+        if level_index >= total {
+            return Err(BasisError::LevelIndexOutOfRange {
+                index: level_index,
+                total,
+            });
+        }
+
+        Ok(VideoDecoder {
+            file: self,
+            level_index,
+            next_frame: 0,
+            state: TranscoderState::new(),
+        })
+    }
+}
+
+impl<'a> VideoDecoder<'a> {
+    /// Total number of frames in the video.
+    pub fn total_frames(&self) -> u32 {
+        self.file.get_total_images()
+    }
+
+    /// Index of the frame the next call to [`next_frame`](Self::next_frame) will decode, or
+    /// `None` once every frame has been decoded.
+    pub fn next_frame_index(&self) -> Option<u32> {
+        if self.next_frame < self.total_frames() {
+            Some(self.next_frame)
+        } else {
+            None
+        }
+    }
+
+    /// Decode the next frame into a newly allocated buffer, or return `None` at the end of the
+    /// video.
+    pub fn next_frame(
+        &mut self,
+        output_format: OutputFormat,
+    ) -> Result<Option<Vec<u8>>, BasisError> {
+        let frame = match self.next_frame_index() {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let size = self
+            .file
+            .required_output_size(frame, self.level_index, output_format)?;
+        let mut output = Vec::with_capacity(size);
+        let params = TranscodeParams::default();
+        let len = self.decode(frame, output.spare_capacity_mut(), output_format, params)?;
+        unsafe { output.set_len(len) };
+        Ok(Some(output))
+    }
+
+    /// Decode the next frame into the provided output buffer. Returns the index of the decoded
+    /// frame, or `None` at the end of the video.
+    pub fn next_frame_into(
+        &mut self,
+        output: &mut [u8],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+    ) -> Result<Option<u32>, BasisError> {
+        let frame = match self.next_frame_index() {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        // The transcoder only ever writes initialized bytes.
+        let output = unsafe { &mut *(output as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        self.decode(frame, output, output_format, params)?;
+        Ok(Some(frame))
+    }
+
+    /// Restart decoding from the first frame.
+    pub fn rewind(&mut self) {
+        self.next_frame = 0;
+        self.state = TranscoderState::new();
+    }
+
+    fn decode(
+        &mut self,
+        frame: u32,
+        output: &mut [MaybeUninit<u8>],
+        output_format: OutputFormat,
+        params: TranscodeParams,
+    ) -> Result<usize, BasisError> {
+        let len = self.file.transcode_image_level_with_state(
+            frame,
+            self.level_index,
+            output,
+            output_format,
+            params,
+            &mut self.state,
+        )?;
+        self.next_frame += 1;
+        Ok(len)
+    }
+}
@@ -0,0 +1,213 @@
+//! Uploading .basis and .ktx2 files as `wgpu` textures, available with the `wgpu` feature.
+
+use std::num::NonZeroU32;
+
+use ::wgpu::{
+    Device, Extent3d, Features, ImageCopyTexture, ImageDataLayout, Origin3d, Queue, Texture,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsage, TextureView,
+    TextureViewDescriptor, TextureViewDimension, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use crate::container::Surfaces;
+use crate::{div_ceil, BasisError, OutputFormat, Transcoder};
+
+/// The `wgpu` texture format for `format`, using the sRGB variant if `srgb` is set and one exists.
+/// Returns `None` for formats `wgpu` has no texture format for.
+pub fn texture_format(format: OutputFormat, srgb: bool) -> Option<TextureFormat> {
+    let pick = |linear, srgb_format| if srgb { srgb_format } else { linear };
+    Some(match format {
+        OutputFormat::BC1_RGB => pick(TextureFormat::Bc1RgbaUnorm, TextureFormat::Bc1RgbaUnormSrgb),
+        OutputFormat::BC3_RGBA => {
+            pick(TextureFormat::Bc3RgbaUnorm, TextureFormat::Bc3RgbaUnormSrgb)
+        }
+        OutputFormat::BC4_R => TextureFormat::Bc4RUnorm,
+        OutputFormat::BC5_RG => TextureFormat::Bc5RgUnorm,
+        OutputFormat::BC7_RGBA => {
+            pick(TextureFormat::Bc7RgbaUnorm, TextureFormat::Bc7RgbaUnormSrgb)
+        }
+        // ETC1 blocks are valid ETC2 blocks.
+        OutputFormat::ETC1_RGB => {
+            pick(TextureFormat::Etc2RgbUnorm, TextureFormat::Etc2RgbUnormSrgb)
+        }
+        OutputFormat::ETC2_RGBA => pick(
+            TextureFormat::Etc2RgbA8Unorm,
+            TextureFormat::Etc2RgbA8UnormSrgb,
+        ),
+        OutputFormat::ETC2_EAC_R11 => TextureFormat::EacRUnorm,
+        OutputFormat::ETC2_EAC_RG11 => TextureFormat::EacRgUnorm,
+        OutputFormat::ASTC_4x4_RGBA => pick(
+            TextureFormat::Astc4x4RgbaUnorm,
+            TextureFormat::Astc4x4RgbaUnormSrgb,
+        ),
+        OutputFormat::RGBA32 => pick(TextureFormat::Rgba8Unorm, TextureFormat::Rgba8UnormSrgb),
+        _ => return None,
+    })
+}
+
+/// Pick the format to transcode `file` to on a device with `features`.
+///
+/// BC7, ASTC and ETC2 are tried in that order, keeping alpha when the file has it, with
+/// [`OutputFormat::RGBA32`] as the fallback every device supports.
+pub fn select_format(file: &Transcoder, features: Features) -> Result<OutputFormat, BasisError> {
+    let etc = if file.has_alpha() {
+        OutputFormat::ETC2_RGBA
+    } else {
+        OutputFormat::ETC1_RGB
+    };
+    let candidates = [
+        (Features::TEXTURE_COMPRESSION_BC, OutputFormat::BC7_RGBA),
+        (
+            Features::TEXTURE_COMPRESSION_ASTC_LDR,
+            OutputFormat::ASTC_4x4_RGBA,
+        ),
+        (Features::TEXTURE_COMPRESSION_ETC2, etc),
+    ];
+    Ok(candidates
+        .iter()
+        .find(|&&(feature, format)| features.contains(feature) && file.can_transcode(format))
+        .map_or(OutputFormat::RGBA32, |&(_, format)| format))
+}
+
+/// Transcode every image and mip level of `file` to a format supported by `device` and upload
+/// them to a new texture, returned along with a view of the whole texture.
+///
+/// Set `srgb` for color textures so they are sampled in linear light. Files with several images
+/// become array textures and cubemaps get a cube or cube array view; see [`Transcoder`] for how
+/// images are numbered. Block compressed textures whose size isn't a multiple of the block size
+/// are rounded up to one, as `wgpu` requires.
+pub fn create_texture(
+    device: &Device,
+    queue: &Queue,
+    file: &Transcoder,
+    srgb: bool,
+    label: Option<&str>,
+) -> Result<(Texture, TextureView), BasisError> {
+    let format = select_format(file, device.features())?;
+    create_texture_with_format(device, queue, file, format, srgb, label)
+}
+
+/// Like [`create_texture`], but with the format chosen by the caller. The device must support
+/// `format`.
+pub fn create_texture_with_format(
+    device: &Device,
+    queue: &Queue,
+    file: &Transcoder,
+    format: OutputFormat,
+    srgb: bool,
+    label: Option<&str>,
+) -> Result<(Texture, TextureView), BasisError> {
+    let texture_format =
+        texture_format(format, srgb).ok_or(BasisError::UnsupportedFormat(format))?;
+    let surfaces = Surfaces::transcode(file, format)?;
+    let image_count = surfaces.levels.len() as u32;
+    let (block_width, block_height) = (format.block_width(), format.block_height());
+
+    let texture = device.create_texture(&TextureDescriptor {
+        label,
+        size: Extent3d {
+            width: div_ceil(surfaces.width, block_width) * block_width,
+            height: div_ceil(surfaces.height, block_height) * block_height,
+            depth_or_array_layers: image_count,
+        },
+        mip_level_count: surfaces.level_count(),
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: texture_format,
+        usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
+    });
+
+    for level in 0..surfaces.level_count() {
+        let (width, height) = file.level_dimensions(0, level)?;
+        let blocks_x = div_ceil(width, block_width);
+        let blocks_y = div_ceil(height, block_height);
+        let images = surfaces
+            .levels
+            .iter()
+            .map(|image| &image[level as usize][..]);
+        let (data, bytes_per_row) = pad_rows(images, blocks_x * format.bytes_per_block());
+        queue.write_texture(
+            ImageCopyTexture {
+                texture: &texture,
+                mip_level: level,
+                origin: Origin3d::ZERO,
+            },
+            &data,
+            ImageDataLayout {
+                offset: 0,
+                bytes_per_row: NonZeroU32::new(bytes_per_row),
+                rows_per_image: NonZeroU32::new(blocks_y),
+            },
+            Extent3d {
+                width: blocks_x * block_width,
+                height: blocks_y * block_height,
+                depth_or_array_layers: image_count,
+            },
+        );
+    }
+
+    let dimension = match (surfaces.faces, surfaces.layers) {
+        (6, 1) => TextureViewDimension::Cube,
+        (6, _) => TextureViewDimension::CubeArray,
+        (_, 1) => TextureViewDimension::D2,
+        _ => TextureViewDimension::D2Array,
+    };
+    let view = texture.create_view(&TextureViewDescriptor {
+        label,
+        dimension: Some(dimension),
+        ..Default::default()
+    });
+    Ok((texture, view))
+}
+
+/// Concatenate `images`, padding each row of `row_bytes` bytes to a multiple of
+/// `COPY_BYTES_PER_ROW_ALIGNMENT`. Returns the data and the padded row size.
+fn pad_rows<'a>(images: impl Iterator<Item = &'a [u8]>, row_bytes: u32) -> (Vec<u8>, u32) {
+    let bytes_per_row =
+        div_ceil(row_bytes, COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+    let mut data = Vec::new();
+    for image in images {
+        for row in image.chunks(row_bytes as usize) {
+            data.extend_from_slice(row);
+            data.resize(data.len() + (bytes_per_row - row_bytes) as usize, 0);
+        }
+    }
+    (data, bytes_per_row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        assert_eq!(
+            texture_format(OutputFormat::BC7_RGBA, true),
+            Some(TextureFormat::Bc7RgbaUnormSrgb)
+        );
+        assert_eq!(
+            texture_format(OutputFormat::ETC1_RGB, false),
+            Some(TextureFormat::Etc2RgbUnorm)
+        );
+        assert_eq!(
+            texture_format(OutputFormat::BC4_R, true),
+            Some(TextureFormat::Bc4RUnorm)
+        );
+        assert_eq!(texture_format(OutputFormat::PVRTC1_4_RGB, false), None);
+    }
+
+    #[test]
+    fn pad_image_rows() {
+        let images = [[1; 16], [2; 16]];
+        let (data, bytes_per_row) = pad_rows(images.iter().map(|image| &image[..]), 8);
+        assert_eq!(bytes_per_row, COPY_BYTES_PER_ROW_ALIGNMENT);
+        assert_eq!(data.len(), 4 * bytes_per_row as usize);
+        assert_eq!(data[..8], [1; 8]);
+        assert_eq!(data[8], 0);
+        assert_eq!(data[bytes_per_row as usize..][..8], [1; 8]);
+        assert_eq!(data[2 * bytes_per_row as usize], 2);
+
+        let row = vec![3; 512];
+        let (data, bytes_per_row) = pad_rows(std::iter::once(&row[..]), 512);
+        assert_eq!((data.len(), bytes_per_row), (512, 512));
+    }
+}
@@ -0,0 +1,91 @@
+//! Scratch memory that can be kept across transcodes.
+
+use crate::ktx2::Ktx2TranscoderState;
+use crate::TranscoderState;
+
+/// Scratch memory for the decoder, for callers that transcode many levels and want to avoid the
+/// allocations each transcode otherwise makes.
+///
+/// Pass the same workspace to the `transcode_image_level_with_workspace` methods of
+/// [`Transcoder`](crate::Transcoder), [`BasisFileTranscoder`](crate::BasisFileTranscoder) and
+/// [`Ktx2FileTranscoder`](crate::Ktx2FileTranscoder). Its buffers grow to fit the largest level
+/// seen and are then reused, so streaming textures into preallocated output buffers makes no
+/// further heap allocations. A workspace can be used with any number of files, but only by one
+/// thread at a time; give each thread its own.
+///
+/// Video files need the state left by the previous frame, which
+/// [`VideoDecoder`](crate::VideoDecoder) keeps track of.
+pub struct Workspace {
+    pub(crate) basis: TranscoderState,
+    pub(crate) ktx2: Ktx2TranscoderState,
+}
+
+impl Workspace {
+    /// Create an empty workspace.
+    pub fn new() -> Self {
+        Self {
+            basis: TranscoderState::new(),
+            ktx2: Ktx2TranscoderState::new(),
+        }
+    }
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Workspace>();
+    }
+
+    #[cfg(feature = "encoder")]
+    #[test]
+    fn encode_and_reuse_workspace() {
+        use crate::encoder::UastcLevel;
+        use crate::{
+            Compressor, CompressorParams, Container, OutputFormat, TranscodeParams, Transcoder,
+        };
+
+        let rgba = [200; 16 * 16 * 4];
+        let mut compressor = Compressor::new(CompressorParams::new().mip_gen(true));
+        compressor.add_image_rgba(&rgba, 16, 16).unwrap();
+        let basis = compressor.compress().unwrap();
+        let mut compressor = Compressor::new(
+            CompressorParams::new()
+                .uastc(UastcLevel::Fastest)
+                .container(Container::Ktx2),
+        );
+        compressor.add_image_rgba(&rgba, 16, 16).unwrap();
+        let ktx2 = compressor.compress().unwrap();
+
+        let mut workspace = Workspace::new();
+        let mut output = vec![0; 16 * 16 * 4];
+        for data in &[basis, ktx2] {
+            let file = Transcoder::open(data).unwrap();
+            for _ in 0..2 {
+                file.transcode_image_level_with_workspace(
+                    0,
+                    0,
+                    &mut output,
+                    OutputFormat::RGBA32,
+                    TranscodeParams::default(),
+                    &mut workspace,
+                )
+                .unwrap();
+                assert_eq!(
+                    output,
+                    file.transcode_image_level_to_vec(0, 0, OutputFormat::RGBA32)
+                        .unwrap()
+                );
+            }
+        }
+    }
+}